@@ -119,6 +119,15 @@ pub fn verify_session(wallet_address: &str) -> CanisterResult<()> {
     Ok(())
 }
 
+// Verify the caller is a controller of this canister (admin-only endpoints)
+pub fn verify_controller() -> CanisterResult<()> {
+    if !ic_cdk::api::is_controller(&ic_cdk::caller()) {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+    
+    Ok(())
+}
+
 // Verify Ethereum signature
 fn verify_ethereum_signature(address: &str, message: &str, signature: &str) -> CanisterResult<()> {
     // Remove 0x prefix if present
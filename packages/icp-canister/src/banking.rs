@@ -3,6 +3,8 @@ use crate::error::*;
 use crate::storage;
 use crate::utils;
 use crate::auth;
+use crate::savings;
+use crate::currency;
 
 // Get dual currency balance
 pub fn get_dual_balance(wallet_address: String) -> CanisterResult<DualBalance> {
@@ -25,11 +27,25 @@ pub async fn exchange_currency(
     // Verify session
     auth::verify_session(&wallet_address)?;
     
+    execute_exchange(wallet_address, from_currency, to_currency, amount)
+}
+
+// Perform an exchange for a wallet without session checks. Used directly by
+// canister-driven flows such as standing instructions.
+pub fn execute_exchange(
+    wallet_address: String,
+    from_currency: String,
+    to_currency: String,
+    amount: f64,
+) -> CanisterResult<ExchangeResult> {
     // Validate inputs
     utils::validate_amount(amount)?;
     utils::validate_currency(&from_currency)?;
     utils::validate_currency(&to_currency)?;
     
+    let from_currency = currency::normalize_symbol(&from_currency);
+    let to_currency = currency::normalize_symbol(&to_currency);
+    
     if from_currency == to_currency {
         return Err(CanisterError::InvalidInput("Cannot exchange same currency".to_string()));
     }
@@ -38,36 +54,33 @@ pub async fn exchange_currency(
     let mut user_data = storage::get_user_data(&wallet_address)
         .ok_or(CanisterError::UserNotFound)?;
     
-    // Get exchange rate
-    let exchange_rate = storage::get_exchange_rate();
+    // Get exchange rate for the pair
+    let (rate, rate_source) = currency::exchange_rate(&from_currency, &to_currency, ic_cdk::api::time())?;
     
-    let (from_amount, to_amount, rate) = if from_currency == "rupees" && to_currency == "tokens" {
-        // Rupees to tokens
-        if user_data.dual_balance.rupees_balance < amount {
-            return Err(CanisterError::InsufficientBalance);
-        }
-        
-        let to_amount = utils::safe_multiply(amount, exchange_rate)?;
-        user_data.dual_balance.rupees_balance = utils::safe_subtract(user_data.dual_balance.rupees_balance, amount)?;
-        user_data.dual_balance.token_balance = utils::safe_add(user_data.dual_balance.token_balance, to_amount)?;
-        
-        (amount, to_amount, exchange_rate)
+    if currency::balance_of(&user_data, &from_currency) < amount {
+        return Err(CanisterError::InsufficientBalance);
+    }
+    
+    let to_amount = utils::calculate_exchange(amount, rate)?;
+    let to_amount = if currency::is_builtin(&to_currency) {
+        to_amount
     } else {
-        // Tokens to rupees
-        if user_data.dual_balance.token_balance < amount {
-            return Err(CanisterError::InsufficientBalance);
-        }
-        
-        let rate = 1.0 / exchange_rate;
-        let to_amount = utils::calculate_exchange(amount, rate)?;
-        user_data.dual_balance.token_balance = utils::safe_subtract(user_data.dual_balance.token_balance, amount)?;
-        user_data.dual_balance.rupees_balance = utils::safe_add(user_data.dual_balance.rupees_balance, to_amount)?;
-        
-        (amount, to_amount, rate)
+        currency::round_down(to_amount, currency::decimals_of(&to_currency))
     };
+    if to_amount <= 0.0 {
+        return Err(CanisterError::InvalidAmount);
+    }
     
-    // Update balance timestamp
-    user_data.dual_balance.last_updated = ic_cdk::api::time();
+    let mut writes = PendingWrites::default();
+    currency::debit(&mut user_data, &mut writes, &from_currency, amount)?;
+    currency::credit(&mut user_data, &mut writes, &to_currency, to_amount)?;
+    if to_currency == currency::RUPEES {
+        savings::sweep_incoming_credit(&mut user_data, &mut writes, to_amount)?;
+    }
+    
+    let from_amount = amount;
+    
+    // Balance checkpoints are moved by the currency helpers
     user_data.last_activity = ic_cdk::api::time();
     
     // Create transaction record
@@ -82,24 +95,75 @@ pub async fn exchange_currency(
         hash: Some(utils::generate_transaction_hash()),
     };
     
-    user_data.transactions.push(transaction.clone());
-    
     // Check for achievements
     check_exchange_achievements(&mut user_data, from_amount);
     
     // Save updated user data
-    storage::update_user_data(&wallet_address, user_data)?;
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::append_transaction(&wallet_address, transaction.clone());
     
     Ok(ExchangeResult {
         success: true,
         from_amount,
         to_amount,
         rate,
+        rate_source,
         transaction: Some(transaction),
         error: None,
     })
 }
 
+// Move rupees or tokens from one player to another without session checks
+pub fn execute_transfer(
+    from_address: &str,
+    to_address: &str,
+    symbol: &str,
+    amount: f64,
+) -> CanisterResult<Web3Transaction> {
+    utils::validate_amount(amount)?;
+    utils::validate_currency(symbol)?;
+    
+    if from_address == to_address {
+        return Err(CanisterError::InvalidInput("Cannot transfer to the same wallet".to_string()));
+    }
+    
+    let mut sender = storage::get_user_data(from_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let mut recipient = storage::get_user_data(to_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    
+    let symbol = currency::normalize_symbol(symbol);
+    let mut sender_writes = PendingWrites::default();
+    let mut recipient_writes = PendingWrites::default();
+    currency::debit(&mut sender, &mut sender_writes, &symbol, amount)?;
+    currency::credit(&mut recipient, &mut recipient_writes, &symbol, amount)?;
+    if symbol == currency::RUPEES {
+        savings::sweep_incoming_credit(&mut recipient, &mut recipient_writes, amount)?;
+    }
+    
+    let now = ic_cdk::api::time();
+    sender.last_activity = now;
+    
+    let transaction = Web3Transaction {
+        id: utils::generate_transaction_id(),
+        from: from_address.to_string(),
+        to: Some(to_address.to_string()),
+        amount,
+        transaction_type: TransactionType::Transfer,
+        timestamp: now,
+        status: TransactionStatus::Confirmed,
+        hash: Some(utils::generate_transaction_hash()),
+    };
+    
+    storage::commit_user_data(from_address, sender, sender_writes)?;
+    storage::commit_user_data(to_address, recipient, recipient_writes)?;
+    
+    // Both parties see the transfer in their history
+    storage::append_transaction(from_address, transaction.clone());
+    storage::append_transaction(to_address, transaction.clone());
+    
+    Ok(transaction)
+}
 
 // Get achievements
 pub fn get_achievements(wallet_address: String) -> Vec<Achievement> {
@@ -139,17 +203,21 @@ pub async fn claim_achievement_reward(
     
     // Apply reward to balance
     let now = ic_cdk::api::time();
-    if reward.reward_type == "rupees" {
-        user_data.dual_balance.rupees_balance = utils::safe_add(user_data.dual_balance.rupees_balance, reward.amount)?;
+    let mut writes = PendingWrites::default();
+    if reward.reward_type == currency::RUPEES {
+        currency::credit(&mut user_data, &mut writes, currency::RUPEES, reward.amount)?;
+        savings::sweep_incoming_credit(&mut user_data, &mut writes, reward.amount)?;
+    } else if !currency::is_builtin(&reward.reward_type) && storage::get_currency(&reward.reward_type).is_some() {
+        // Seasonal achievements may pay out in a registry currency
+        currency::credit(&mut user_data, &mut writes, &reward.reward_type, reward.amount)?;
     } else {
-        user_data.dual_balance.token_balance = utils::safe_add(user_data.dual_balance.token_balance, reward.amount)?;
+        currency::credit(&mut user_data, &mut writes, currency::TOKENS, reward.amount)?;
     }
     
-    user_data.dual_balance.last_updated = now;
     user_data.last_activity = now;
     
     // Save updated user data
-    storage::update_user_data(&wallet_address, user_data)?;
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
     
     Ok(reward)
 }
@@ -182,8 +250,8 @@ pub async fn simulate_liquidity_pool(
     
     // Add rewards to balance
     let now = ic_cdk::api::time();
-    user_data.dual_balance.token_balance = utils::safe_add(user_data.dual_balance.token_balance, rewards)?;
-    user_data.dual_balance.last_updated = now;
+    let mut writes = PendingWrites::default();
+    currency::credit(&mut user_data, &mut writes, currency::TOKENS, rewards)?;
     user_data.last_activity = now;
     
     // Create transaction record
@@ -198,10 +266,9 @@ pub async fn simulate_liquidity_pool(
         hash: Some(utils::generate_transaction_hash()),
     };
     
-    user_data.transactions.push(transaction);
-    
     // Save updated user data
-    storage::update_user_data(&wallet_address, user_data)?;
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::append_transaction(&wallet_address, transaction);
     
     Ok(rewards)
 }
@@ -234,8 +301,8 @@ pub async fn simulate_yield_farming(
     
     // Add rewards to balance
     let now = ic_cdk::api::time();
-    user_data.dual_balance.token_balance = utils::safe_add(user_data.dual_balance.token_balance, rewards)?;
-    user_data.dual_balance.last_updated = now;
+    let mut writes = PendingWrites::default();
+    currency::credit(&mut user_data, &mut writes, currency::TOKENS, rewards)?;
     user_data.last_activity = now;
     
     // Create transaction record
@@ -250,10 +317,9 @@ pub async fn simulate_yield_farming(
         hash: Some(utils::generate_transaction_hash()),
     };
     
-    user_data.transactions.push(transaction);
-    
     // Save updated user data
-    storage::update_user_data(&wallet_address, user_data)?;
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::append_transaction(&wallet_address, transaction);
     
     Ok(rewards)
 }
@@ -287,27 +353,52 @@ pub async fn create_transaction(
         hash: Some(utils::generate_transaction_hash()),
     };
     
-    user_data.transactions.push(transaction.clone());
     user_data.last_activity = now;
     
     // Save updated user data
     storage::update_user_data(&wallet_address, user_data)?;
+    storage::append_transaction(&wallet_address, transaction.clone());
     
     Ok(transaction)
 }
 
 // Get transaction history
 pub fn get_transaction_history(wallet_address: String) -> Vec<Web3Transaction> {
-    if let Some(user_data) = storage::get_user_data(&wallet_address) {
-        let mut transactions = user_data.transactions;
-        transactions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        transactions
-    } else {
-        Vec::new()
-    }
+    let mut transactions = storage::get_transactions(&wallet_address);
+    transactions.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    transactions
 }
 
 
+// Unlock an achievement from the global definitions. Returns true if it was newly unlocked.
+pub fn unlock_achievement(user_data: &mut UserData, achievement_id: &str) -> bool {
+    let now = ic_cdk::api::time();
+    
+    if let Some(achievement) = user_data.achievements.iter_mut().find(|a| a.id == achievement_id) {
+        if achievement.unlocked {
+            return false;
+        }
+        achievement.unlocked = true;
+        achievement.unlocked_at = Some(now);
+        return true;
+    }
+    
+    let definition = storage::get_global_settings()
+        .achievement_definitions
+        .into_iter()
+        .find(|a| a.id == achievement_id);
+    
+    match definition {
+        Some(mut achievement) => {
+            achievement.unlocked = true;
+            achievement.unlocked_at = Some(now);
+            user_data.achievements.push(achievement);
+            true
+        }
+        None => false,
+    }
+}
+
 // Helper function to check exchange achievements
 fn check_exchange_achievements(user_data: &mut UserData, amount: f64) {
     let now = ic_cdk::api::time();
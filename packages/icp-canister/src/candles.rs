@@ -0,0 +1,198 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::trading;
+use crate::monitoring::PriceSnapshot;
+use crate::utils::NANOS_PER_DAY;
+
+const NANOS_PER_MINUTE: u64 = 60 * 1_000_000_000;
+const MAX_CANDLES_PER_QUERY: usize = 1_000;
+
+const RESOLUTIONS: [CandleResolution; 4] = [
+    CandleResolution::Minute1,
+    CandleResolution::Minute5,
+    CandleResolution::Hour1,
+    CandleResolution::Day1,
+];
+
+fn bucket_nanos(resolution: CandleResolution) -> u64 {
+    match resolution {
+        CandleResolution::Minute1 => NANOS_PER_MINUTE,
+        CandleResolution::Minute5 => 5 * NANOS_PER_MINUTE,
+        CandleResolution::Hour1 => 60 * NANOS_PER_MINUTE,
+        CandleResolution::Day1 => NANOS_PER_DAY,
+    }
+}
+
+// How far back each resolution is kept; finer candles age out sooner
+fn retention_nanos(resolution: CandleResolution) -> u64 {
+    match resolution {
+        CandleResolution::Minute1 => NANOS_PER_DAY,
+        CandleResolution::Minute5 => 7 * NANOS_PER_DAY,
+        CandleResolution::Hour1 => 90 * NANOS_PER_DAY,
+        CandleResolution::Day1 => 5 * 365 * NANOS_PER_DAY,
+    }
+}
+
+// Accepts the short forms used by the HTTP route ("1m", "5m", "1h", "1d")
+pub fn parse_resolution(value: &str) -> CanisterResult<CandleResolution> {
+    match value.trim().to_lowercase().as_str() {
+        "1m" => Ok(CandleResolution::Minute1),
+        "5m" => Ok(CandleResolution::Minute5),
+        "1h" => Ok(CandleResolution::Hour1),
+        "1d" => Ok(CandleResolution::Day1),
+        other => Err(CanisterError::InvalidInput(format!("Unknown resolution: {}", other))),
+    }
+}
+
+fn bucket_start(timestamp: u64, resolution: CandleResolution) -> u64 {
+    timestamp - timestamp % bucket_nanos(resolution)
+}
+
+// Fold one price into every resolution's current candle. Opening a new
+// bucket also drops that series' candles that have aged past retention.
+pub fn record_price(symbol: &str, price: f64, volume: f64, timestamp: u64) {
+    if !price.is_finite() || price <= 0.0 {
+        return;
+    }
+    let Ok(symbol) = trading::normalize_stock_id(symbol) else {
+        return;
+    };
+
+    for resolution in RESOLUTIONS {
+        let key = CandleKey {
+            symbol: symbol.clone(),
+            resolution,
+            bucket_start: bucket_start(timestamp, resolution),
+        };
+
+        let candle = match storage::get_candle(&key) {
+            Some(mut candle) => {
+                apply_tick(&mut candle, price, volume, timestamp);
+                candle
+            }
+            None => {
+                let cutoff = key.bucket_start.saturating_sub(retention_nanos(resolution));
+                storage::remove_candles_before(&symbol, resolution, cutoff);
+                open_candle(key, price, volume, timestamp)
+            }
+        };
+        storage::set_candle(&candle);
+    }
+}
+
+pub fn record_snapshot(snapshot: &PriceSnapshot) {
+    for entry in &snapshot.prices {
+        record_price(&entry.symbol, entry.price, 0.0, snapshot.timestamp);
+    }
+}
+
+// Candles for a symbol within the window (defaults to everything retained),
+// capped at the most recent MAX_CANDLES_PER_QUERY
+pub fn get_candles(symbol: String, resolution: CandleResolution, from: Option<u64>, to: Option<u64>) -> CanisterResult<Vec<Candle>> {
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    let from = from.unwrap_or(0);
+    let to = to.unwrap_or(u64::MAX);
+    if from > to {
+        return Err(CanisterError::InvalidInput("`from` must not be after `to`".to_string()));
+    }
+
+    let mut candles = storage::get_candles(&symbol, resolution, bucket_start(from, resolution), to);
+    if candles.len() > MAX_CANDLES_PER_QUERY {
+        candles.drain(..candles.len() - MAX_CANDLES_PER_QUERY);
+    }
+    Ok(candles)
+}
+
+// Scale a symbol's candles that open before `before`, e.g. to back-adjust
+// history for a split. Volume moves the opposite way to price.
+pub fn rescale(symbol: &str, before: u64, factor: f64) {
+    if factor <= 0.0 || !factor.is_finite() {
+        return;
+    }
+
+    for resolution in RESOLUTIONS {
+        for mut candle in storage::get_candles(symbol, resolution, 0, before.saturating_sub(1)) {
+            candle.open *= factor;
+            candle.high *= factor;
+            candle.low *= factor;
+            candle.close *= factor;
+            candle.volume /= factor;
+            storage::set_candle(&candle);
+        }
+    }
+}
+
+fn open_candle(key: CandleKey, price: f64, volume: f64, timestamp: u64) -> Candle {
+    Candle {
+        symbol: key.symbol,
+        resolution: key.resolution,
+        bucket_start: key.bucket_start,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume,
+        tick_count: 1,
+        updated_at: timestamp,
+    }
+}
+
+// A late price still widens the range but does not replace a newer close
+fn apply_tick(candle: &mut Candle, price: f64, volume: f64, timestamp: u64) {
+    candle.high = candle.high.max(price);
+    candle.low = candle.low.min(price);
+    candle.volume += volume;
+    candle.tick_count += 1;
+    if timestamp >= candle.updated_at {
+        candle.close = price;
+        candle.updated_at = timestamp;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle_at(timestamp: u64, price: f64) -> Candle {
+        let key = CandleKey {
+            symbol: "BTC".to_string(),
+            resolution: CandleResolution::Minute5,
+            bucket_start: bucket_start(timestamp, CandleResolution::Minute5),
+        };
+        open_candle(key, price, 0.0, timestamp)
+    }
+
+    #[test]
+    fn test_bucket_start() {
+        let timestamp = NANOS_PER_DAY + 7 * NANOS_PER_MINUTE + 30;
+        assert_eq!(bucket_start(timestamp, CandleResolution::Minute1), NANOS_PER_DAY + 7 * NANOS_PER_MINUTE);
+        assert_eq!(bucket_start(timestamp, CandleResolution::Minute5), NANOS_PER_DAY + 5 * NANOS_PER_MINUTE);
+        assert_eq!(bucket_start(timestamp, CandleResolution::Hour1), NANOS_PER_DAY);
+        assert_eq!(bucket_start(timestamp, CandleResolution::Day1), NANOS_PER_DAY);
+    }
+
+    #[test]
+    fn test_apply_tick() {
+        let mut candle = candle_at(100, 50.0);
+        apply_tick(&mut candle, 55.0, 2.0, 200);
+        apply_tick(&mut candle, 45.0, 1.0, 300);
+        apply_tick(&mut candle, 60.0, 0.0, 150); // arrives late
+
+        assert_eq!(candle.open, 50.0);
+        assert_eq!(candle.high, 60.0);
+        assert_eq!(candle.low, 45.0);
+        assert_eq!(candle.close, 45.0);
+        assert_eq!(candle.volume, 3.0);
+        assert_eq!(candle.tick_count, 4);
+        assert_eq!(candle.updated_at, 300);
+    }
+
+    #[test]
+    fn test_parse_resolution() {
+        assert_eq!(parse_resolution("1m").unwrap(), CandleResolution::Minute1);
+        assert_eq!(parse_resolution("1H").unwrap(), CandleResolution::Hour1);
+        assert!(parse_resolution("15m").is_err());
+        assert!(RESOLUTIONS.iter().all(|r| retention_nanos(*r) >= bucket_nanos(*r)));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Policy engine configuration
+const POLICY_INTERVAL_SECONDS: u64 = 60;
+const MAX_POLICY_RATE_PERCENT: f64 = 50.0;
+const MAX_NOTE_LENGTH: usize = 280;
+
+thread_local! {
+    static POLICY_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_policy_engine() {
+    POLICY_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(POLICY_INTERVAL_SECONDS), || {
+            for decision in apply_due_decisions(ic_cdk::api::time()) {
+                ic_cdk::println!("🏛️ Applied rate decision {}", decision.id);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Seed the rate history with the policy in force before any decision. It is
+// recorded as effective from time zero so every past transaction has a rate.
+pub fn ensure_rate_history() {
+    if storage::get_first_rate_record().is_none() {
+        storage::insert_rate_record(&record_from(&storage::get_monetary_policy(), 0, None));
+    }
+}
+
+pub fn get_monetary_policy() -> MonetaryPolicy {
+    storage::get_monetary_policy()
+}
+
+// Schedule a rate decision (controllers only). Decisions already due apply at once.
+pub fn schedule_rate_decision(request: RateDecisionRequest) -> CanisterResult<RateDecision> {
+    auth::verify_controller()?;
+
+    validate_request(&request)?;
+
+    let now = ic_cdk::api::time();
+    let decision = RateDecision {
+        id: utils::generate_decision_id(),
+        policy_rate_percent: request.policy_rate_percent,
+        exchange_rate: request.exchange_rate,
+        inflation_target_percent: request.inflation_target_percent,
+        inflation_percent: request.inflation_percent,
+        effective_at: request.effective_at.max(now),
+        note: request.note.map(|note| utils::sanitize_string(&note).chars().take(MAX_NOTE_LENGTH).collect()),
+        status: RateDecisionStatus::Scheduled,
+        created_at: now,
+        applied_at: None,
+    };
+
+    storage::set_rate_decision(&decision);
+
+    if decision.effective_at <= now {
+        apply_due_decisions(now);
+        return storage::get_rate_decision(&decision.id)
+            .ok_or(CanisterError::InternalError("Rate decision disappeared".to_string()));
+    }
+
+    Ok(decision)
+}
+
+// Withdraw a decision that has not taken effect yet (controllers only)
+pub fn cancel_rate_decision(decision_id: String) -> CanisterResult<RateDecision> {
+    auth::verify_controller()?;
+
+    let mut decision = storage::get_rate_decision(&decision_id)
+        .ok_or(CanisterError::NotFound(format!("Rate decision {}", decision_id)))?;
+
+    if decision.status != RateDecisionStatus::Scheduled {
+        return Err(CanisterError::InvalidInput("Only scheduled decisions can be cancelled".to_string()));
+    }
+
+    decision.status = RateDecisionStatus::Cancelled;
+    storage::set_rate_decision(&decision);
+
+    Ok(decision)
+}
+
+// All decisions, latest effective date first
+pub fn list_rate_decisions() -> Vec<RateDecision> {
+    let mut decisions = storage::get_all_rate_decisions();
+    decisions.sort_by_key(|decision| std::cmp::Reverse(decision.effective_at));
+    decisions
+}
+
+// Rate changes that took effect within the window (defaults to all history)
+pub fn get_rate_history(start: Option<u64>, end: Option<u64>) -> Vec<RateRecord> {
+    storage::get_rate_records(start.unwrap_or(0), end.unwrap_or(u64::MAX))
+}
+
+// The rates that were in force at a given moment
+pub fn get_rate_at(timestamp: u64) -> RateRecord {
+    storage::get_rate_record_at(timestamp)
+        .or_else(storage::get_first_rate_record)
+        .unwrap_or_else(|| record_from(&storage::get_monetary_policy(), 0, None))
+}
+
+// Deposit-rate adjustment currently in force, in percentage points
+pub fn deposit_rate_adjustment() -> f64 {
+    let policy = storage::get_monetary_policy();
+    policy.policy_rate_percent - policy.neutral_rate_percent
+}
+
+// Loans are priced off the policy rate
+pub fn lending_rate_percent() -> f64 {
+    let policy = storage::get_monetary_policy();
+    policy.policy_rate_percent + policy.lending_spread_percent
+}
+
+// Deposit-rate adjustment in force over [start, end), split at each rate change.
+// Returns (segment start, segment end, adjustment in percentage points).
+pub fn deposit_adjustment_segments(start: u64, end: u64) -> Vec<(u64, u64, f64)> {
+    if end <= start {
+        return Vec::new();
+    }
+
+    let initial = get_rate_at(start).deposit_rate_adjustment_percent;
+    let changes: Vec<(u64, f64)> = storage::get_rate_records(start.saturating_add(1), end)
+        .into_iter()
+        .map(|record| (record.effective_at, record.deposit_rate_adjustment_percent))
+        .collect();
+
+    split_segments(initial, &changes, start, end)
+}
+
+// Apply every scheduled decision that is due, oldest first. Returns the decisions applied.
+pub fn apply_due_decisions(now: u64) -> Vec<RateDecision> {
+    let mut due = storage::get_due_rate_decisions(now);
+    due.sort_by_key(|decision| decision.effective_at);
+
+    for decision in due.iter_mut() {
+        let policy = apply_to_policy(&storage::get_monetary_policy(), decision, now);
+
+        storage::set_monetary_policy(policy.clone());
+        storage::set_exchange_rate(policy.exchange_rate);
+        // Recorded at the moment the decision actually took effect
+        storage::insert_rate_record(&record_from(&policy, now, Some(decision.id.clone())));
+
+        decision.status = RateDecisionStatus::Applied;
+        decision.applied_at = Some(now);
+        storage::set_rate_decision(decision);
+    }
+
+    due
+}
+
+fn apply_to_policy(policy: &MonetaryPolicy, decision: &RateDecision, now: u64) -> MonetaryPolicy {
+    let mut updated = policy.clone();
+
+    if let Some(rate) = decision.policy_rate_percent {
+        updated.policy_rate_percent = rate;
+    }
+
+    // A hike strengthens the rupee (more tokens per rupee), a cut weakens it
+    updated.exchange_rate = match decision.exchange_rate {
+        Some(rate) => rate,
+        None => {
+            let change = updated.policy_rate_percent - policy.policy_rate_percent;
+            policy.exchange_rate * (1.0 + change / 100.0 * policy.exchange_rate_sensitivity)
+        }
+    };
+
+    if let Some(target) = decision.inflation_target_percent {
+        updated.inflation_target_percent = target;
+    }
+    if let Some(inflation) = decision.inflation_percent {
+        updated.inflation_percent = inflation;
+    }
+    updated.updated_at = now;
+
+    updated
+}
+
+fn record_from(policy: &MonetaryPolicy, effective_at: u64, decision_id: Option<String>) -> RateRecord {
+    RateRecord {
+        effective_at,
+        decision_id,
+        policy_rate_percent: policy.policy_rate_percent,
+        deposit_rate_adjustment_percent: policy.policy_rate_percent - policy.neutral_rate_percent,
+        lending_rate_percent: policy.policy_rate_percent + policy.lending_spread_percent,
+        exchange_rate: policy.exchange_rate,
+        inflation_target_percent: policy.inflation_target_percent,
+        inflation_percent: policy.inflation_percent,
+    }
+}
+
+fn split_segments(initial: f64, changes: &[(u64, f64)], start: u64, end: u64) -> Vec<(u64, u64, f64)> {
+    let mut segments = Vec::new();
+    let mut segment_start = start;
+    let mut adjustment = initial;
+
+    for &(effective_at, next_adjustment) in changes {
+        if effective_at >= end {
+            break;
+        }
+        if effective_at <= segment_start {
+            adjustment = next_adjustment;
+            continue;
+        }
+        segments.push((segment_start, effective_at, adjustment));
+        segment_start = effective_at;
+        adjustment = next_adjustment;
+    }
+    segments.push((segment_start, end, adjustment));
+
+    segments
+}
+
+fn validate_request(request: &RateDecisionRequest) -> CanisterResult<()> {
+    if request.policy_rate_percent.is_none()
+        && request.exchange_rate.is_none()
+        && request.inflation_target_percent.is_none()
+        && request.inflation_percent.is_none()
+    {
+        return Err(CanisterError::InvalidInput("Rate decision changes nothing".to_string()));
+    }
+
+    if let Some(rate) = request.policy_rate_percent {
+        if !(0.0..=MAX_POLICY_RATE_PERCENT).contains(&rate) {
+            return Err(CanisterError::InvalidInput(format!("Policy rate must be between 0 and {}%", MAX_POLICY_RATE_PERCENT)));
+        }
+    }
+    if let Some(rate) = request.exchange_rate {
+        if rate <= 0.0 || !rate.is_finite() {
+            return Err(CanisterError::ExchangeRateFailed);
+        }
+    }
+    for inflation in [request.inflation_target_percent, request.inflation_percent].into_iter().flatten() {
+        if !(-50.0..=100.0).contains(&inflation) {
+            return Err(CanisterError::InvalidInput("Inflation must be between -50% and 100%".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(policy_rate_percent: Option<f64>, exchange_rate: Option<f64>) -> RateDecision {
+        RateDecision {
+            id: "rd_test".to_string(),
+            policy_rate_percent,
+            exchange_rate,
+            inflation_target_percent: None,
+            inflation_percent: Some(5.0),
+            effective_at: 100,
+            note: None,
+            status: RateDecisionStatus::Scheduled,
+            created_at: 0,
+            applied_at: None,
+        }
+    }
+
+    #[test]
+    fn test_hike_propagates_to_deposit_lending_and_exchange() {
+        let policy = MonetaryPolicy::default();
+        let updated = apply_to_policy(&policy, &decision(Some(7.5), None), 100);
+
+        assert_eq!(updated.policy_rate_percent, 7.5);
+        assert!((updated.exchange_rate - 0.101).abs() < 1e-12);
+        assert_eq!(updated.inflation_percent, 5.0);
+
+        let record = record_from(&updated, 100, None);
+        assert_eq!(record.deposit_rate_adjustment_percent, 1.0);
+        assert_eq!(record.lending_rate_percent, 10.0);
+    }
+
+    #[test]
+    fn test_explicit_exchange_rate_overrides_sensitivity() {
+        let updated = apply_to_policy(&MonetaryPolicy::default(), &decision(Some(5.5), Some(0.08)), 100);
+        assert_eq!(updated.exchange_rate, 0.08);
+    }
+
+    #[test]
+    fn test_split_segments_at_rate_changes() {
+        let changes = vec![(150, 0.5), (300, -0.25)];
+        assert_eq!(
+            split_segments(0.0, &changes, 100, 400),
+            vec![(100, 150, 0.0), (150, 300, 0.5), (300, 400, -0.25)]
+        );
+        assert_eq!(split_segments(0.0, &changes, 200, 250), vec![(200, 250, 0.5)]);
+        assert_eq!(split_segments(0.0, &[(100, 0.5)], 100, 200), vec![(100, 200, 0.5)]);
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let mut request = RateDecisionRequest {
+            policy_rate_percent: None,
+            exchange_rate: None,
+            inflation_target_percent: None,
+            inflation_percent: None,
+            effective_at: 0,
+            note: None,
+        };
+        assert!(validate_request(&request).is_err());
+
+        request.policy_rate_percent = Some(6.25);
+        assert!(validate_request(&request).is_ok());
+
+        request.exchange_rate = Some(-1.0);
+        assert!(validate_request(&request).is_err());
+    }
+
+    #[test]
+    fn test_decisions_due_in_one_tick_keep_their_records() {
+        let mut cut = decision(Some(6.0), None);
+        cut.id = "rd_cut".to_string();
+        let mut hike = decision(Some(7.0), None);
+        hike.id = "rd_hike".to_string();
+        hike.effective_at = 150;
+        storage::set_rate_decision(&hike);
+        storage::set_rate_decision(&cut);
+
+        let applied = apply_due_decisions(200);
+        let ids: Vec<&str> = applied.iter().map(|decision| decision.id.as_str()).collect();
+        assert_eq!(ids, vec!["rd_cut", "rd_hike"]);
+
+        let records = get_rate_history(Some(200), Some(200));
+        let record_ids: Vec<Option<&str>> = records.iter().map(|record| record.decision_id.as_deref()).collect();
+        assert_eq!(record_ids, vec![Some("rd_cut"), Some("rd_hike")]);
+        assert_eq!(get_rate_at(200).policy_rate_percent, 7.0);
+        assert_eq!(storage::get_rate_decision("rd_cut").unwrap().status, RateDecisionStatus::Applied);
+    }
+}
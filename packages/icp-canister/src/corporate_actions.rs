@@ -0,0 +1,437 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::currency;
+use crate::savings;
+use crate::trading;
+use crate::orders;
+use crate::indices;
+use crate::market_sim;
+use crate::candles;
+use crate::stock_sse;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Due actions are applied by a sweep, so an ex-date takes effect within one interval
+const APPLY_INTERVAL_SECONDS: u64 = 15 * 60;
+const MAX_RATIO_TERM: u32 = 1_000;
+const RUPEE_DECIMALS: u8 = 2;
+
+thread_local! {
+    static ACTION_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_action_processor() {
+    ACTION_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(APPLY_INTERVAL_SECONDS), || {
+            let applied = apply_due_actions(ic_cdk::api::time());
+            if applied > 0 {
+                ic_cdk::println!("🏛️ Applied {} corporate actions", applied);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Add an action to a stock's calendar (controllers only)
+pub fn schedule_corporate_action(request: CorporateActionRequest) -> CanisterResult<CorporateAction> {
+    auth::verify_controller()?;
+    schedule(request, CorporateActionSource::Admin, ic_cdk::api::time())
+}
+
+// Calendar entry without the controller check, for the simulator
+pub fn schedule(request: CorporateActionRequest, source: CorporateActionSource, now: u64) -> CanisterResult<CorporateAction> {
+    let stock_id = trading::normalize_stock_id(&request.stock_id)?;
+    validate_kind(&request.kind)?;
+
+    let action = CorporateAction {
+        id: utils::generate_corporate_action_id(),
+        stock_id,
+        kind: request.kind,
+        ex_date: request.ex_date,
+        source,
+        status: CorporateActionStatus::Scheduled,
+        created_at: now,
+        applied_at: None,
+        holders_adjusted: 0,
+        cash_paid: 0.0,
+        unpaid_cash: Vec::new(),
+    };
+    storage::set_corporate_action(&action);
+
+    Ok(action)
+}
+
+// Withdraw an action before its ex-date (controllers only)
+pub fn cancel_corporate_action(action_id: String) -> CanisterResult<CorporateAction> {
+    auth::verify_controller()?;
+
+    let mut action = storage::get_corporate_action(&action_id)
+        .ok_or(CanisterError::NotFound("Corporate action not found".to_string()))?;
+    if action.status != CorporateActionStatus::Scheduled {
+        return Err(CanisterError::InvalidInput("Only scheduled actions can be cancelled".to_string()));
+    }
+
+    action.status = CorporateActionStatus::Cancelled;
+    storage::set_corporate_action(&action);
+
+    Ok(action)
+}
+
+// A stock's calendar, earliest ex-date first
+pub fn get_corporate_actions(stock_id: String) -> CanisterResult<Vec<CorporateAction>> {
+    let stock_id = trading::normalize_stock_id(&stock_id)?;
+    let mut actions = storage::get_corporate_actions_for_stock(&stock_id);
+    actions.sort_by_key(|action| action.ex_date);
+    Ok(actions)
+}
+
+pub fn has_scheduled_action(stock_id: &str) -> bool {
+    storage::get_corporate_actions_for_stock(stock_id)
+        .iter()
+        .any(|action| action.status == CorporateActionStatus::Scheduled)
+}
+
+// Apply every scheduled action whose ex-date has passed, after retrying cash
+// still owed from earlier actions. Returns the number applied.
+pub fn apply_due_actions(now: u64) -> usize {
+    retry_unpaid_cash(now);
+
+    let mut actions = storage::get_due_corporate_actions(now);
+    actions.sort_by_key(|action| action.ex_date);
+
+    actions
+        .into_iter()
+        .filter(|action| apply_action(action.clone(), now))
+        .count()
+}
+
+// Holders are adjusted one by one; a failure for one wallet is logged and
+// does not hold up the rest, and the action is never applied twice. Cash is
+// paid in rupees at the quoted price, so an action waits for the next sweep
+// if its rupee rate is stale or the stock has no real quote. Returns whether
+// the action was applied.
+fn apply_action(mut action: CorporateAction, now: u64) -> bool {
+    let fx_rate = match trading::rupee_rate(&action.stock_id, now) {
+        Ok((_, fx_rate)) => fx_rate,
+        Err(e) => {
+            ic_cdk::println!("⚠️ Corporate action {} deferred: {}", action.id, e);
+            return false;
+        }
+    };
+    let price_before = match stock_sse::get_quoted_stock(&action.stock_id) {
+        Ok(stock) => stock.current_price,
+        Err(e) => {
+            ic_cdk::println!("⚠️ Corporate action {} deferred: {}", action.id, e);
+            return false;
+        }
+    };
+
+    let price_factor = match share_ratio(&action.kind) {
+        Some((new_shares, old_shares)) => {
+            let ex_price = price_before * fx_rate * old_shares as f64 / new_shares as f64;
+            for holding in storage::get_holdings_for_stock(&action.stock_id) {
+                let cash_in_lieu = adjust_holder_for_ratio(holding.clone(), new_shares, old_shares, ex_price, now);
+                action.holders_adjusted += 1;
+                if cash_in_lieu > 0.0 {
+                    settle_cash(&mut action, &holding.wallet_address, cash_in_lieu, now);
+                }
+            }
+            adjust_open_orders(&action.stock_id, new_shares, old_shares, now);
+            indices::apply_share_ratio(&action.stock_id, new_shares, old_shares);
+            old_shares as f64 / new_shares as f64
+        }
+        None => {
+            let per_share = match action.kind {
+                CorporateActionKind::CashDividend { per_share } => per_share,
+                _ => 0.0,
+            };
+            for holding in storage::get_holdings_for_stock(&action.stock_id) {
+                match dividend_amount(&holding, per_share * fx_rate) {
+                    Ok(amount) => {
+                        action.holders_adjusted += 1;
+                        if amount > 0.0 {
+                            settle_cash(&mut action, &holding.wallet_address, amount, now);
+                        }
+                    }
+                    Err(e) => ic_cdk::println!("⚠️ Dividend {} failed for {}: {}", action.id, holding.wallet_address, e),
+                }
+            }
+            dividend_price_factor(price_before, per_share)
+        }
+    };
+
+    adjust_market_prices(&action.stock_id, action.ex_date, price_factor);
+
+    action.status = CorporateActionStatus::Applied;
+    action.applied_at = Some(now);
+    storage::set_corporate_action(&action);
+    true
+}
+
+// Stores the adjusted holding, with the fractional share realized at the
+// ex-price, and returns the cash owed in lieu of it
+fn adjust_holder_for_ratio(holding: Holding, new_shares: u32, old_shares: u32, ex_price: f64, now: u64) -> f64 {
+    let (mut holding, fraction) = split_holding(holding, new_shares, old_shares, now);
+    let cash_in_lieu = currency::round_down(fraction * ex_price, RUPEE_DECIMALS);
+
+    if cash_in_lieu > 0.0 {
+        holding.realized_pnl += cash_in_lieu - fraction * holding.average_cost;
+    }
+    storage::set_holding(&holding);
+
+    cash_in_lieu
+}
+
+// Pay a dividend or cash in lieu, or record it on the action to retry on the
+// next sweep
+fn settle_cash(action: &mut CorporateAction, wallet_address: &str, amount: f64, now: u64) {
+    let transaction_type = match action.kind {
+        CorporateActionKind::CashDividend { .. } => TransactionType::Dividend,
+        _ => TransactionType::StockSell,
+    };
+    match pay_cash(wallet_address, amount, transaction_type, now) {
+        Ok(()) => action.cash_paid += amount,
+        Err(e) => {
+            ic_cdk::println!("⚠️ Payout for {} on {} deferred: {}", wallet_address, action.id, e);
+            action.unpaid_cash.push(UnpaidCash {
+                wallet_address: wallet_address.to_string(),
+                amount,
+            });
+        }
+    }
+}
+
+fn retry_unpaid_cash(now: u64) {
+    for mut action in storage::get_corporate_actions_with_unpaid_cash() {
+        for unpaid in std::mem::take(&mut action.unpaid_cash) {
+            settle_cash(&mut action, &unpaid.wallet_address, unpaid.amount, now);
+        }
+        storage::set_corporate_action(&action);
+    }
+}
+
+// Dividend owed on a holding, in whole paise
+fn dividend_amount(holding: &Holding, per_share: f64) -> CanisterResult<f64> {
+    Ok(currency::round_down(utils::safe_multiply(per_share, holding.quantity as f64)?, RUPEE_DECIMALS))
+}
+
+fn pay_cash(wallet_address: &str, amount: f64, transaction_type: TransactionType, now: u64) -> CanisterResult<()> {
+    let mut user_data = storage::get_user_data(wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let mut writes = PendingWrites::default();
+    currency::credit(&mut user_data, &mut writes, currency::RUPEES, amount)?;
+    savings::sweep_incoming_credit(&mut user_data, &mut writes, amount)?;
+
+    record_cash(wallet_address, user_data, writes, amount, transaction_type, now)
+}
+
+fn record_cash(
+    wallet_address: &str,
+    user_data: UserData,
+    writes: PendingWrites,
+    amount: f64,
+    transaction_type: TransactionType,
+    now: u64,
+) -> CanisterResult<()> {
+    let transaction = Web3Transaction {
+        id: utils::generate_transaction_id(),
+        from: wallet_address.to_string(),
+        to: None,
+        amount,
+        transaction_type,
+        timestamp: now,
+        status: TransactionStatus::Confirmed,
+        hash: Some(utils::generate_transaction_hash()),
+    };
+
+    storage::commit_user_data(wallet_address, user_data, writes)?;
+    storage::append_transaction(wallet_address, transaction);
+
+    Ok(())
+}
+
+// Open orders follow the split: more shares at a proportionally lower trigger.
+// Buy reservations stay as they are, which still covers the adjusted order. An
+// order a reverse split leaves without a whole share is cancelled and its
+// reservation released.
+fn adjust_open_orders(stock_id: &str, new_shares: u32, old_shares: u32, now: u64) {
+    for mut order in storage::get_open_stock_orders(stock_id) {
+        let quantity = (order.quantity as u128 * new_shares as u128 / old_shares as u128) as u64;
+        if quantity == 0 {
+            if let Err(e) = orders::cancel_unfillable(order.clone(), "Reverse split left no whole shares", now) {
+                ic_cdk::println!("⚠️ Failed to cancel order {} after split: {}", order.id, e);
+            }
+            continue;
+        }
+        order.quantity = quantity;
+        order.trigger_price = order.trigger_price * old_shares as f64 / new_shares as f64;
+        storage::set_stock_order(&order);
+    }
+}
+
+// Scale cached bars and candles before the ex-date (and the current price, if
+// it predates the ex-date) so charts and returns stay continuous across the action
+fn adjust_market_prices(stock_id: &str, ex_date: u64, factor: f64) {
+    if (factor - 1.0).abs() < f64::EPSILON {
+        return;
+    }
+
+    if let Some(mut cache) = storage::get_stock_cache(stock_id) {
+        let stock = &mut cache.stock_data;
+        adjust_price_history(&mut stock.price_history, ex_date, factor);
+        if stock.last_update < ex_date {
+            stock.current_price *= factor;
+        }
+        storage::set_stock_cache(stock_id, &cache);
+    }
+
+    candles::rescale(stock_id, ex_date, factor);
+    market_sim::apply_price_factor(stock_id, factor);
+}
+
+fn validate_kind(kind: &CorporateActionKind) -> CanisterResult<()> {
+    match kind {
+        CorporateActionKind::Split { new_shares: a, old_shares: b } | CorporateActionKind::Bonus { bonus_shares: a, held_shares: b } => {
+            if *a == 0 || *b == 0 || *a > MAX_RATIO_TERM || *b > MAX_RATIO_TERM {
+                return Err(CanisterError::InvalidInput(format!("Ratio terms must be between 1 and {}", MAX_RATIO_TERM)));
+            }
+            if matches!(kind, CorporateActionKind::Split { .. }) && a == b {
+                return Err(CanisterError::InvalidInput("A 1:1 split changes nothing".to_string()));
+            }
+        }
+        CorporateActionKind::CashDividend { per_share } => {
+            if !per_share.is_finite() || *per_share <= 0.0 {
+                return Err(CanisterError::InvalidInput("Dividend per share must be positive".to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+// New shares per old shares for actions that change the share count
+fn share_ratio(kind: &CorporateActionKind) -> Option<(u32, u32)> {
+    match kind {
+        CorporateActionKind::Split { new_shares, old_shares } => Some((*new_shares, *old_shares)),
+        CorporateActionKind::Bonus { bonus_shares, held_shares } => Some((bonus_shares + held_shares, *held_shares)),
+        CorporateActionKind::CashDividend { .. } => None,
+    }
+}
+
+// Whole shares after the ratio, with the same total cost spread over the
+// entitlement; returns the fractional share left over for cash in lieu
+fn split_holding(mut holding: Holding, new_shares: u32, old_shares: u32, now: u64) -> (Holding, f64) {
+    let entitled = holding.quantity as u128 * new_shares as u128;
+    let whole = (entitled / old_shares as u128) as u64;
+    let fraction = (entitled % old_shares as u128) as f64 / old_shares as f64;
+
+    holding.average_cost = holding.average_cost * old_shares as f64 / new_shares as f64;
+    holding.quantity = whole;
+    holding.updated_at = now;
+
+    (holding, fraction)
+}
+
+// Price drops by the dividend on the ex-date; earlier prices are scaled to match
+fn dividend_price_factor(price_before: f64, per_share: f64) -> f64 {
+    if price_before <= per_share || price_before <= 0.0 {
+        return 1.0;
+    }
+    1.0 - per_share / price_before
+}
+
+fn adjust_price_history(history: &mut [StockPrice], ex_date: u64, factor: f64) {
+    for bar in history.iter_mut().filter(|bar| bar.timestamp < ex_date) {
+        bar.price *= factor;
+        bar.open *= factor;
+        bar.high *= factor;
+        bar.low *= factor;
+        bar.close *= factor;
+        if factor > 0.0 {
+            bar.volume = (bar.volume as f64 / factor).round() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(quantity: u64, average_cost: f64) -> Holding {
+        Holding {
+            wallet_address: "0xabc".to_string(),
+            stock_id: "TCS".to_string(),
+            quantity,
+            average_cost,
+            realized_pnl: 0.0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_split_and_bonus_ratios() {
+        let (split, fraction) = split_holding(holding(10, 300.0), 2, 1, 1);
+        assert_eq!(split.quantity, 20);
+        assert_eq!(split.average_cost, 150.0);
+        assert_eq!(fraction, 0.0);
+
+        // 1 bonus share for every 2 held: 5 shares entitle to 7.5
+        let ratio = share_ratio(&CorporateActionKind::Bonus { bonus_shares: 1, held_shares: 2 }).unwrap();
+        assert_eq!(ratio, (3, 2));
+        let (bonus, fraction) = split_holding(holding(5, 90.0), ratio.0, ratio.1, 1);
+        assert_eq!(bonus.quantity, 7);
+        assert_eq!(bonus.average_cost, 60.0);
+        assert_eq!(fraction, 0.5);
+        assert!(share_ratio(&CorporateActionKind::CashDividend { per_share: 5.0 }).is_none());
+    }
+
+    #[test]
+    fn test_holding_is_stored_before_cash_in_lieu() {
+        // 1 bonus for every 2 held: 5 shares become 7 plus half a share at 60
+        let cash = adjust_holder_for_ratio(holding(5, 90.0), 3, 2, 100.0, 1);
+        assert_eq!(cash, 50.0);
+
+        let stored = storage::get_holding("0xabc", "TCS").unwrap();
+        assert_eq!(stored.quantity, 7);
+        assert_eq!(stored.realized_pnl, 20.0);
+    }
+
+    #[test]
+    fn test_price_history_adjustment() {
+        let bar = |timestamp: u64, close: f64| StockPrice {
+            timestamp,
+            price: close,
+            volume: 1_000,
+            high: close,
+            low: close,
+            open: close,
+            close,
+        };
+        let mut history = vec![bar(1, 200.0), bar(2, 210.0), bar(3, 105.0)];
+
+        adjust_price_history(&mut history, 3, 0.5);
+        assert_eq!(history[0].close, 100.0);
+        assert_eq!(history[1].close, 105.0);
+        assert_eq!(history[1].volume, 2_000);
+        assert_eq!(history[2].close, 105.0);
+        assert_eq!(history[2].volume, 1_000);
+
+        assert!((dividend_price_factor(100.0, 2.0) - 0.98).abs() < 1e-12);
+        assert_eq!(dividend_price_factor(1.0, 2.0), 1.0);
+    }
+
+    #[test]
+    fn test_validate_kind() {
+        assert!(validate_kind(&CorporateActionKind::Split { new_shares: 2, old_shares: 1 }).is_ok());
+        assert!(validate_kind(&CorporateActionKind::Split { new_shares: 1, old_shares: 1 }).is_err());
+        assert!(validate_kind(&CorporateActionKind::Bonus { bonus_shares: 0, held_shares: 1 }).is_err());
+        assert!(validate_kind(&CorporateActionKind::CashDividend { per_share: -1.0 }).is_err());
+    }
+}
@@ -0,0 +1,481 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::savings;
+use crate::interest;
+use crate::oracle_peg;
+
+pub const RUPEES: &str = "rupees";
+pub const TOKENS: &str = "tokens";
+
+const MAX_SYMBOL_LENGTH: usize = 32;
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+const MAX_DECIMALS: u8 = 12;
+
+// Rupees and tokens are always present. Their balances live in
+// `UserData.dual_balance` and their mutual rate in the global settings.
+pub fn is_builtin(symbol: &str) -> bool {
+    symbol == RUPEES || symbol == TOKENS
+}
+
+pub fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_lowercase()
+}
+
+fn builtin_currencies(now: u64) -> Vec<CurrencyDefinition> {
+    vec![
+        CurrencyDefinition {
+            symbol: RUPEES.to_string(),
+            display_name: "Rupees".to_string(),
+            decimals: 2,
+            mintable: true,
+            exchange_pairs: Vec::new(), // live rate is filled in by list_currencies
+            enabled: true,
+            created_at: now,
+        },
+        CurrencyDefinition {
+            symbol: TOKENS.to_string(),
+            display_name: "Tokens".to_string(),
+            decimals: 8,
+            mintable: true,
+            exchange_pairs: Vec::new(),
+            enabled: true,
+            created_at: now,
+        },
+    ]
+}
+
+// Seed the registry with the built-in currencies if they are missing
+pub fn ensure_builtin_currencies() {
+    let now = ic_cdk::api::time();
+    for definition in builtin_currencies(now) {
+        if storage::get_currency(&definition.symbol).is_none() {
+            storage::set_currency(&definition);
+        }
+    }
+}
+
+// Register a new currency (controllers only)
+pub fn register_currency(definition: CurrencyDefinition) -> CanisterResult<CurrencyDefinition> {
+    auth::verify_controller()?;
+
+    let mut definition = definition;
+    definition.symbol = normalize_symbol(&definition.symbol);
+    validate_symbol(&definition.symbol)?;
+
+    if is_builtin(&definition.symbol) || storage::get_currency(&definition.symbol).is_some() {
+        return Err(CanisterError::InvalidInput(format!("Currency {} already exists", definition.symbol)));
+    }
+
+    definition.display_name = validate_display_name(&definition.display_name)?;
+    definition.exchange_pairs = validate_exchange_pairs(&definition.symbol, definition.exchange_pairs)?;
+    if definition.decimals > MAX_DECIMALS {
+        return Err(CanisterError::InvalidInput(format!("Decimals cannot exceed {}", MAX_DECIMALS)));
+    }
+    definition.created_at = ic_cdk::api::time();
+
+    storage::set_currency(&definition);
+
+    ic_cdk::println!("💱 Registered currency {}", definition.symbol);
+
+    Ok(definition)
+}
+
+// Replace a registered currency's exchange pairs (controllers only)
+pub fn set_exchange_pairs(symbol: String, exchange_pairs: Vec<ExchangePair>) -> CanisterResult<CurrencyDefinition> {
+    auth::verify_controller()?;
+
+    let mut definition = get_registered(&normalize_symbol(&symbol))?;
+    if is_builtin(&definition.symbol) {
+        return Err(CanisterError::InvalidInput("Built-in exchange rates follow the global settings".to_string()));
+    }
+
+    definition.exchange_pairs = validate_exchange_pairs(&definition.symbol, exchange_pairs)?;
+    storage::set_currency(&definition);
+
+    Ok(definition)
+}
+
+// Enable or retire a registered currency (controllers only)
+pub fn set_currency_enabled(symbol: String, enabled: bool) -> CanisterResult<CurrencyDefinition> {
+    auth::verify_controller()?;
+
+    let mut definition = get_registered(&normalize_symbol(&symbol))?;
+    if is_builtin(&definition.symbol) {
+        return Err(CanisterError::InvalidInput("Built-in currencies cannot be disabled".to_string()));
+    }
+
+    definition.enabled = enabled;
+    storage::set_currency(&definition);
+
+    Ok(definition)
+}
+
+// Issue units of a mintable currency to a player (controllers only)
+pub fn mint_currency(wallet_address: String, symbol: String, amount: f64) -> CanisterResult<f64> {
+    auth::verify_controller()?;
+
+    utils::validate_amount(amount)?;
+    let symbol = normalize_symbol(&symbol);
+    let definition = get_active(&symbol)?;
+    if !definition.mintable {
+        return Err(CanisterError::InvalidInput(format!("Currency {} is not mintable", symbol)));
+    }
+
+    let mut user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let now = ic_cdk::api::time();
+    let mut writes = PendingWrites::default();
+    let balance = credit(&mut user_data, &mut writes, &symbol, amount)?;
+    if symbol == RUPEES {
+        savings::sweep_incoming_credit(&mut user_data, &mut writes, amount)?;
+    }
+
+    let transaction = Web3Transaction {
+        id: utils::generate_transaction_id(),
+        from: wallet_address.clone(),
+        to: None,
+        amount,
+        transaction_type: TransactionType::Deposit,
+        timestamp: now,
+        status: TransactionStatus::Confirmed,
+        hash: Some(utils::generate_transaction_hash()),
+    };
+
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::append_transaction(&wallet_address, transaction);
+
+    Ok(balance)
+}
+
+pub fn list_currencies() -> Vec<CurrencyDefinition> {
+    let mut currencies = storage::get_all_currencies();
+    for definition in currencies.iter_mut().filter(|d| d.symbol == RUPEES) {
+        definition.exchange_pairs = vec![ExchangePair {
+            quote: TOKENS.to_string(),
+            rate: exchange_rate(RUPEES, TOKENS, ic_cdk::api::time())
+                .map(|(rate, _)| rate)
+                .unwrap_or_else(|_| storage::get_exchange_rate()),
+        }];
+    }
+    currencies
+}
+
+// Every balance held by a wallet; rupees and tokens are always listed first
+pub fn get_balances(wallet_address: String) -> CanisterResult<Vec<AssetBalance>> {
+    let user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let mut symbols = vec![RUPEES.to_string(), TOKENS.to_string()];
+    symbols.extend(
+        storage::get_asset_balances_for_wallet(&wallet_address)
+            .into_iter()
+            .map(|(symbol, _)| symbol),
+    );
+
+    Ok(symbols
+        .into_iter()
+        .map(|symbol| {
+            let (display_name, decimals) = match storage::get_currency(&symbol) {
+                Some(definition) => (definition.display_name, definition.decimals),
+                None => (symbol.clone(), 2),
+            };
+            AssetBalance {
+                balance: balance_of(&user_data, &symbol),
+                symbol,
+                display_name,
+                decimals,
+            }
+        })
+        .collect())
+}
+
+// Check that a currency exists and can currently be moved
+pub fn validate_currency(symbol: &str) -> CanisterResult<()> {
+    let symbol = normalize_symbol(symbol);
+    if is_builtin(&symbol) {
+        return Ok(());
+    }
+    get_active(&symbol).map(|_| ())
+}
+
+pub fn balance_of(user_data: &UserData, symbol: &str) -> f64 {
+    match symbol {
+        RUPEES => user_data.dual_balance.rupees_balance,
+        TOKENS => user_data.dual_balance.token_balance,
+        _ => storage::get_asset_balance(&user_data.wallet_address, symbol),
+    }
+}
+
+// Balance including changes not yet committed
+fn pending_balance_of(user_data: &UserData, writes: &PendingWrites, symbol: &str) -> f64 {
+    writes.asset_balances
+        .iter()
+        .find(|(pending_symbol, _)| pending_symbol == symbol)
+        .map(|(_, balance)| *balance)
+        .unwrap_or_else(|| balance_of(user_data, symbol))
+}
+
+// Add to a balance. Registry balances and accrued interest go into `writes`,
+// which the caller persists with `storage::commit_user_data`. Returns the new balance.
+pub fn credit(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, amount: f64) -> CanisterResult<f64> {
+    credit_at(user_data, writes, symbol, amount, ic_cdk::api::time())
+}
+
+// Subtract from a balance, failing if it would go negative. Returns the new balance.
+pub fn debit(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, amount: f64) -> CanisterResult<f64> {
+    debit_at(user_data, writes, symbol, amount, ic_cdk::api::time())
+}
+
+fn credit_at(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, amount: f64, now: u64) -> CanisterResult<f64> {
+    checkpoint(user_data, writes, symbol, now)?;
+    let balance = utils::safe_add(pending_balance_of(user_data, writes, symbol), amount)?;
+    set_balance(user_data, writes, symbol, balance);
+    Ok(balance)
+}
+
+fn debit_at(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, amount: f64, now: u64) -> CanisterResult<f64> {
+    checkpoint(user_data, writes, symbol, now)?;
+    let balance = utils::safe_subtract(pending_balance_of(user_data, writes, symbol), amount)?;
+    set_balance(user_data, writes, symbol, balance);
+    Ok(balance)
+}
+
+// Built-in balances share the `DualBalance.last_updated` checkpoint, so
+// interest on the old rupee balance is settled before either one changes
+fn checkpoint(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, now: u64) -> CanisterResult<()> {
+    if is_builtin(symbol) {
+        interest::accrue(user_data, writes, now)?;
+    }
+    Ok(())
+}
+
+fn set_balance(user_data: &mut UserData, writes: &mut PendingWrites, symbol: &str, balance: f64) {
+    match symbol {
+        RUPEES => user_data.dual_balance.rupees_balance = balance,
+        TOKENS => user_data.dual_balance.token_balance = balance,
+        _ => match writes.asset_balances.iter_mut().find(|(pending_symbol, _)| pending_symbol == symbol) {
+            Some((_, pending_balance)) => *pending_balance = balance,
+            None => writes.asset_balances.push((symbol.to_string(), balance)),
+        },
+    }
+}
+
+// Units of `to` received per unit of `from`, and where that rate came from.
+// Rupee/token rates follow the peg mode; other pairs may be declared on either
+// currency, with the reverse direction using the inverse rate.
+pub fn exchange_rate(from: &str, to: &str, now: u64) -> CanisterResult<(f64, RateSource)> {
+    if from == RUPEES && to == TOKENS {
+        return oracle_peg::rupee_token_rate(true, now);
+    }
+    if from == TOKENS && to == RUPEES {
+        return oracle_peg::rupee_token_rate(false, now);
+    }
+
+    let direct = storage::get_currency(from)
+        .and_then(|definition| find_pair_rate(&definition, to));
+    if let Some(rate) = direct {
+        return Ok((rate, RateSource::Registry));
+    }
+
+    let inverse = storage::get_currency(to)
+        .and_then(|definition| find_pair_rate(&definition, from));
+    match inverse {
+        Some(rate) => Ok((1.0 / rate, RateSource::Registry)),
+        None => Err(CanisterError::InvalidInput(format!("No exchange pair between {} and {}", from, to))),
+    }
+}
+
+fn find_pair_rate(definition: &CurrencyDefinition, quote: &str) -> Option<f64> {
+    definition.exchange_pairs
+        .iter()
+        .find(|pair| pair.quote == quote)
+        .map(|pair| pair.rate)
+}
+
+// Truncate an amount to a currency's precision so rounding never creates value
+pub fn round_down(amount: f64, decimals: u8) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    (amount * factor).floor() / factor
+}
+
+pub fn decimals_of(symbol: &str) -> u8 {
+    match storage::get_currency(symbol) {
+        Some(definition) => definition.decimals,
+        None if symbol == RUPEES => 2,
+        None => 8,
+    }
+}
+
+fn get_registered(symbol: &str) -> CanisterResult<CurrencyDefinition> {
+    storage::get_currency(symbol)
+        .ok_or(CanisterError::InvalidCurrency)
+}
+
+fn get_active(symbol: &str) -> CanisterResult<CurrencyDefinition> {
+    let definition = get_registered(symbol)?;
+    if !definition.enabled {
+        return Err(CanisterError::InvalidCurrency);
+    }
+    Ok(definition)
+}
+
+fn validate_symbol(symbol: &str) -> CanisterResult<()> {
+    if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LENGTH {
+        return Err(CanisterError::InvalidInput("Invalid currency symbol length".to_string()));
+    }
+    if !symbol.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        return Err(CanisterError::InvalidInput("Currency symbol may only contain a-z, 0-9 and _".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_display_name(name: &str) -> CanisterResult<String> {
+    let name = utils::sanitize_string(name.trim());
+    if name.is_empty() {
+        return Err(CanisterError::InvalidInput("Display name cannot be empty".to_string()));
+    }
+    Ok(name.chars().take(MAX_DISPLAY_NAME_LENGTH).collect())
+}
+
+fn validate_exchange_pairs(symbol: &str, pairs: Vec<ExchangePair>) -> CanisterResult<Vec<ExchangePair>> {
+    let mut validated: Vec<ExchangePair> = Vec::new();
+
+    for pair in pairs {
+        let quote = normalize_symbol(&pair.quote);
+        if quote == symbol {
+            return Err(CanisterError::InvalidInput("A currency cannot pair with itself".to_string()));
+        }
+        if !is_builtin(&quote) && storage::get_currency(&quote).is_none() {
+            return Err(CanisterError::InvalidInput(format!("Unknown quote currency {}", quote)));
+        }
+        if pair.rate <= 0.0 || !pair.rate.is_finite() {
+            return Err(CanisterError::ExchangeRateFailed);
+        }
+        if validated.iter().any(|p| p.quote == quote) {
+            return Err(CanisterError::InvalidInput(format!("Duplicate pair for {}", quote)));
+        }
+        validated.push(ExchangePair { quote, rate: pair.rate });
+    }
+
+    Ok(validated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn festival_currency(symbol: &str, pairs: Vec<ExchangePair>) -> CurrencyDefinition {
+        CurrencyDefinition {
+            symbol: symbol.to_string(),
+            display_name: "Diya Coins".to_string(),
+            decimals: 0,
+            mintable: true,
+            exchange_pairs: pairs,
+            enabled: true,
+            created_at: 0,
+        }
+    }
+
+    fn player(wallet_address: &str, rupees_balance: f64) -> UserData {
+        UserData {
+            wallet_address: wallet_address.to_string(),
+            dual_balance: DualBalance {
+                rupees_balance,
+                token_balance: 0.0,
+                last_updated: 0,
+            },
+            staking_pools: Vec::new(),
+            achievements: Vec::new(),
+            transactions: Vec::new(),
+            created_at: 0,
+            last_activity: 0,
+        }
+    }
+
+    #[test]
+    fn test_exchange_rate_direct_and_inverse() {
+        storage::set_currency(&festival_currency(
+            "diya_coins",
+            vec![ExchangePair { quote: RUPEES.to_string(), rate: 5.0 }],
+        ));
+
+        assert_eq!(exchange_rate("diya_coins", RUPEES, 0).unwrap(), (5.0, RateSource::Registry));
+        assert_eq!(exchange_rate(RUPEES, "diya_coins", 0).unwrap(), (0.2, RateSource::Registry));
+        assert!(exchange_rate("diya_coins", TOKENS, 0).is_err());
+        assert_eq!(
+            exchange_rate(RUPEES, TOKENS, 0).unwrap(),
+            (storage::get_exchange_rate(), RateSource::Fixed)
+        );
+    }
+
+    #[test]
+    fn test_validate_currency_respects_enabled_flag() {
+        let mut definition = festival_currency("holi_colours", Vec::new());
+        storage::set_currency(&definition);
+        assert!(validate_currency("HOLI_COLOURS").is_ok());
+
+        definition.enabled = false;
+        storage::set_currency(&definition);
+        assert!(validate_currency("holi_colours").is_err());
+        assert!(validate_currency("tokens").is_ok());
+    }
+
+    #[test]
+    fn test_registry_credit_waits_for_commit() {
+        let wallet_address = "0xpending";
+        storage::STATE.with(|state| {
+            state.borrow_mut().users.insert(wallet_address.to_string(), player(wallet_address, 0.0));
+        });
+        storage::set_asset_balance(wallet_address, "diya_coins", 5.0);
+
+        let mut user_data = storage::get_user_data(wallet_address).unwrap();
+        let mut writes = PendingWrites::default();
+        assert_eq!(credit_at(&mut user_data, &mut writes, "diya_coins", 3.0, 0).unwrap(), 8.0);
+        assert_eq!(debit_at(&mut user_data, &mut writes, "diya_coins", 1.0, 0).unwrap(), 7.0);
+        assert!(debit_at(&mut user_data, &mut writes, "diya_coins", 10.0, 0).is_err());
+        assert_eq!(storage::get_asset_balance(wallet_address, "diya_coins"), 5.0);
+
+        storage::commit_user_data(wallet_address, user_data, writes).unwrap();
+        assert_eq!(storage::get_asset_balance(wallet_address, "diya_coins"), 7.0);
+    }
+
+    #[test]
+    fn test_failed_debit_does_not_accrue_interest() {
+        let wallet_address = "0xaccrue_once";
+        storage::STATE.with(|state| {
+            state.borrow_mut().users.insert(wallet_address.to_string(), player(wallet_address, 36_500.0));
+        });
+        let thirty_days = 30 * utils::NANOS_PER_DAY;
+
+        // An oversized purchase fails after the checkpoint; its user record is dropped
+        let mut attempt = storage::get_user_data(wallet_address).unwrap();
+        assert!(debit_at(&mut attempt, &mut PendingWrites::default(), RUPEES, 1_000_000.0, thirty_days).is_err());
+        assert!(storage::get_interest_account(wallet_address).is_none());
+
+        for _ in 0..2 {
+            let mut user_data = storage::get_user_data(wallet_address).unwrap();
+            let mut writes = PendingWrites::default();
+            debit_at(&mut user_data, &mut writes, RUPEES, 100.0, thirty_days).unwrap();
+            storage::commit_user_data(wallet_address, user_data, writes).unwrap();
+        }
+
+        // 36,500 at 3.5% for 30 days, accrued once
+        let account = storage::get_interest_account(wallet_address).unwrap();
+        assert!((account.accrued_interest - 105.0).abs() < 1e-9);
+        assert_eq!(storage::get_user_data(wallet_address).unwrap().dual_balance.last_updated, thirty_days);
+    }
+
+    #[test]
+    fn test_symbol_and_rounding() {
+        assert!(validate_symbol("diya_coins").is_ok());
+        assert!(validate_symbol("Diya Coins").is_err());
+        assert!(validate_symbol("").is_err());
+
+        assert_eq!(round_down(12.349, 2), 12.34);
+        assert_eq!(round_down(7.9, 0), 7.0);
+    }
+}
@@ -24,6 +24,7 @@ pub enum CanisterError {
     ExchangeRateFailed,
     InvalidCurrency,
     NegativeAmount,
+    StaleExchangeRate(String),
     
     // Achievement Errors
     AchievementNotFound,
@@ -35,6 +36,7 @@ pub enum CanisterError {
     TransactionNotFound,
     InvalidTransactionType,
     TransactionFailed,
+    MarketClosed(String),
     
     // General Errors
     UserNotFound,
@@ -71,6 +73,7 @@ impl fmt::Display for CanisterError {
             // staking errors removed
             CanisterError::InvalidCurrency => write!(f, "Invalid currency type"),
             CanisterError::NegativeAmount => write!(f, "Amount cannot be negative"),
+            CanisterError::StaleExchangeRate(pair) => write!(f, "No fresh exchange rate for {}", pair),
             
             // Achievement Errors
             CanisterError::AchievementNotFound => write!(f, "Achievement not found"),
@@ -82,6 +85,7 @@ impl fmt::Display for CanisterError {
             CanisterError::TransactionNotFound => write!(f, "Transaction not found"),
             CanisterError::InvalidTransactionType => write!(f, "Invalid transaction type"),
             CanisterError::TransactionFailed => write!(f, "Transaction failed to process"),
+            CanisterError::MarketClosed(msg) => write!(f, "Market closed: {}", msg),
             
             // General Errors
             CanisterError::UserNotFound => write!(f, "User not found"),
@@ -131,7 +135,9 @@ impl CanisterError {
             CanisterError::SessionExpired |
             CanisterError::WalletConnectionFailed |
             CanisterError::ExchangeRateFailed |
+            CanisterError::StaleExchangeRate(_) |
             CanisterError::TransactionFailed |
+            CanisterError::MarketClosed(_) |
             CanisterError::InternalError(_) |
             CanisterError::StateNotInitialized |
             CanisterError::SerializationError |
@@ -172,6 +178,7 @@ impl CanisterError {
             // Staking error codes removed (1204, 1205)
             CanisterError::InvalidCurrency => 1204,
             CanisterError::NegativeAmount => 1205,
+            CanisterError::StaleExchangeRate(_) => 1206,
             
             // Achievement Errors (1300-1399)
             CanisterError::AchievementNotFound => 1301,
@@ -183,6 +190,7 @@ impl CanisterError {
             CanisterError::TransactionNotFound => 1401,
             CanisterError::InvalidTransactionType => 1402,
             CanisterError::TransactionFailed => 1403,
+            CanisterError::MarketClosed(_) => 1404,
             
             // General Errors (1500-1599)
             CanisterError::UserNotFound => 1501,
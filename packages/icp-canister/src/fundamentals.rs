@@ -0,0 +1,48 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::trading;
+use crate::market_data;
+use crate::utils::NANOS_PER_DAY;
+
+// Statements change quarterly, so a day-old copy saves an outcall per refresh
+const FUNDAMENTALS_TTL: u64 = NANOS_PER_DAY;
+
+// Stored statements for a symbol, refetched once stale. A failed fetch falls
+// back to whatever is stored, however old; None means metrics stay estimated.
+pub async fn latest(symbol: &str) -> Option<Fundamentals> {
+    let cached = storage::get_fundamentals(symbol);
+    let now = ic_cdk::api::time();
+    if cached.as_ref().is_some_and(|f| now.saturating_sub(f.fetched_at) < FUNDAMENTALS_TTL) {
+        return cached;
+    }
+
+    match market_data::stock_fundamentals(symbol).await {
+        Ok(fundamentals) => {
+            storage::set_fundamentals(&fundamentals);
+            Some(fundamentals)
+        }
+        Err(e) => {
+            ic_cdk::println!("⚠️ No financial statements for {}: {}", symbol, e);
+            cached
+        }
+    }
+}
+
+// Fetch a symbol's statements now, ignoring the stored copy (controllers only)
+pub async fn refresh_fundamentals(symbol: String) -> CanisterResult<Fundamentals> {
+    auth::verify_controller()?;
+    let symbol = trading::normalize_stock_id(&symbol)?;
+
+    let fundamentals = market_data::stock_fundamentals(&symbol).await
+        .map_err(CanisterError::internal_error)?;
+    storage::set_fundamentals(&fundamentals);
+    Ok(fundamentals)
+}
+
+pub fn get_fundamentals(symbol: String) -> CanisterResult<Fundamentals> {
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    storage::get_fundamentals(&symbol)
+        .ok_or_else(|| CanisterError::NotFound(format!("No financial statements stored for {}", symbol)))
+}
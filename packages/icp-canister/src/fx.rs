@@ -0,0 +1,257 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::price_feed;
+use crate::market_data;
+use crate::instruments::INR;
+use crate::utils::NANOS_PER_SECOND;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Older rates are refused rather than used to value trades
+const MAX_RATE_AGE_SECONDS: u64 = 6 * 60 * 60;
+const REFRESH_INTERVAL_SECONDS: u64 = 60 * 60;
+// Pairs the refresher keeps current even if nothing has been stored yet
+const DEFAULT_PAIRS: [(&str, &str); 1] = [("USD", INR)];
+
+thread_local! {
+    static REFRESH_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_rate_refresher() {
+    REFRESH_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(REFRESH_INTERVAL_SECONDS), || {
+            ic_cdk::spawn(async {
+                for (base, quote) in refreshed_pairs() {
+                    if let Err(e) = refresh(&base, &quote, ic_cdk::api::time()).await {
+                        ic_cdk::println!("⚠️ FX refresh failed for {}/{}: {}", base, quote, e);
+                    }
+                }
+            });
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Default pairs plus any pair last set by the provider; oracle-fed pairs are
+// left to the oracle
+fn refreshed_pairs() -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = DEFAULT_PAIRS
+        .iter()
+        .map(|(base, quote)| (base.to_string(), quote.to_string()))
+        .collect();
+    for rate in storage::get_all_fx_rates() {
+        if rate.source == FxRateSource::Provider && !pairs.iter().any(|(b, q)| *b == rate.base && *q == rate.quote) {
+            pairs.push((rate.base, rate.quote));
+        }
+    }
+    pairs
+}
+
+// ISO 4217-style three-letter code, upper-cased
+pub fn normalize_currency(code: &str) -> CanisterResult<String> {
+    let code = code.trim().to_uppercase();
+    if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(CanisterError::InvalidInput(format!("Invalid currency code: {}", code)));
+    }
+    Ok(code)
+}
+
+// An optional currency code on a listing; blank means unset
+pub fn normalize_optional_currency(code: Option<String>) -> CanisterResult<Option<String>> {
+    code.filter(|code| !code.trim().is_empty())
+        .map(|code| normalize_currency(&code))
+        .transpose()
+}
+
+// Record a rate pushed by the oracle or a controller
+pub fn submit_fx_rate(base: String, quote: String, rate: f64) -> CanisterResult<FxRate> {
+    if ic_cdk::caller().to_string() != price_feed::ORACLE_PRINCIPAL {
+        auth::verify_controller()?;
+    }
+
+    let fx_rate = FxRate {
+        base: normalize_currency(&base)?,
+        quote: normalize_currency(&quote)?,
+        rate,
+        source: FxRateSource::Oracle,
+        updated_at: ic_cdk::api::time(),
+    };
+    store(fx_rate)
+}
+
+// Fetch a pair from the configured provider now (controllers only)
+pub async fn refresh_fx_rate(base: String, quote: String) -> CanisterResult<FxRate> {
+    auth::verify_controller()?;
+    refresh(&normalize_currency(&base)?, &normalize_currency(&quote)?, ic_cdk::api::time()).await
+}
+
+async fn refresh(base: &str, quote: &str, now: u64) -> CanisterResult<FxRate> {
+    let rate = market_data::fx_rate(base, quote).await
+        .map_err(CanisterError::internal_error)?;
+
+    store(FxRate {
+        base: base.to_string(),
+        quote: quote.to_string(),
+        rate,
+        source: FxRateSource::Provider,
+        updated_at: now,
+    })
+}
+
+fn store(fx_rate: FxRate) -> CanisterResult<FxRate> {
+    if fx_rate.base == fx_rate.quote {
+        return Err(CanisterError::InvalidInput("Base and quote currencies must differ".to_string()));
+    }
+    if !fx_rate.rate.is_finite() || fx_rate.rate <= 0.0 {
+        return Err(CanisterError::InvalidAmount);
+    }
+
+    storage::set_fx_rate(&fx_rate);
+    Ok(fx_rate)
+}
+
+// The most recent rate for a pair, whether stored directly, stored the other
+// way round, or fed by the oracle as a "USDINR"-style price. Errors if that
+// rate is older than MAX_RATE_AGE_SECONDS.
+pub fn rate(base: &str, quote: &str, now: u64) -> CanisterResult<FxRate> {
+    freshest(candidates(base, quote), now)
+        .ok_or_else(|| CanisterError::StaleExchangeRate(format!("{}/{}", base, quote)))
+}
+
+// Rupees for one unit of `currency`
+pub fn rupees_per_unit(currency: &str, now: u64) -> CanisterResult<f64> {
+    if currency == INR {
+        return Ok(1.0);
+    }
+    Ok(rate(currency, INR, now)?.rate)
+}
+
+pub fn get_fx_rate(base: String, quote: String) -> CanisterResult<FxRate> {
+    rate(&normalize_currency(&base)?, &normalize_currency(&quote)?, ic_cdk::api::time())
+}
+
+pub fn list_fx_rates() -> Vec<FxRate> {
+    storage::get_all_fx_rates()
+}
+
+pub fn convert(amount: f64, from: String, to: String) -> CanisterResult<FxConversion> {
+    if !amount.is_finite() || amount < 0.0 {
+        return Err(CanisterError::InvalidAmount);
+    }
+    let from = normalize_currency(&from)?;
+    let to = normalize_currency(&to)?;
+    let now = ic_cdk::api::time();
+
+    let (rate, rate_updated_at) = if from == to {
+        (1.0, now)
+    } else {
+        let fx_rate = rate(&from, &to, now)?;
+        (fx_rate.rate, fx_rate.updated_at)
+    };
+
+    Ok(FxConversion {
+        from,
+        to,
+        amount,
+        converted: amount * rate,
+        rate,
+        rate_updated_at,
+    })
+}
+
+fn candidates(base: &str, quote: &str) -> Vec<FxRate> {
+    let price_feed_rate = |base: &str, quote: &str| {
+        let symbol = format!("{}{}", base, quote);
+        Some(FxRate {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            rate: storage::get_price_feed(&symbol)?,
+            source: FxRateSource::Oracle,
+            updated_at: storage::get_price_feed_updated_at(&symbol)?,
+        })
+    };
+
+    let direct = [storage::get_fx_rate(base, quote), price_feed_rate(base, quote)];
+    let inverse = [storage::get_fx_rate(quote, base), price_feed_rate(quote, base)];
+
+    direct
+        .into_iter()
+        .flatten()
+        .chain(inverse.into_iter().flatten().filter_map(|rate| invert(&rate)))
+        .collect()
+}
+
+fn invert(rate: &FxRate) -> Option<FxRate> {
+    if !rate.rate.is_finite() || rate.rate <= 0.0 {
+        return None;
+    }
+    Some(FxRate {
+        base: rate.quote.clone(),
+        quote: rate.base.clone(),
+        rate: 1.0 / rate.rate,
+        source: rate.source,
+        updated_at: rate.updated_at,
+    })
+}
+
+fn freshest(candidates: Vec<FxRate>, now: u64) -> Option<FxRate> {
+    candidates
+        .into_iter()
+        .filter(|rate| rate.rate.is_finite() && rate.rate > 0.0)
+        .filter(|rate| now.saturating_sub(rate.updated_at) <= MAX_RATE_AGE_SECONDS * NANOS_PER_SECOND)
+        .max_by_key(|rate| rate.updated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 60 * 60 * NANOS_PER_SECOND;
+
+    fn usd_inr(rate: f64, updated_at: u64) -> FxRate {
+        FxRate {
+            base: "USD".to_string(),
+            quote: "INR".to_string(),
+            rate,
+            source: FxRateSource::Provider,
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_freshest_skips_stale_rates() {
+        let now = 100 * HOUR;
+        let picked = freshest(vec![usd_inr(82.0, now - 7 * HOUR), usd_inr(83.0, now - HOUR)], now).unwrap();
+        assert_eq!(picked.rate, 83.0);
+
+        assert!(freshest(vec![usd_inr(82.0, now - 7 * HOUR)], now).is_none());
+        assert!(freshest(vec![usd_inr(0.0, now)], now).is_none());
+    }
+
+    #[test]
+    fn test_invert() {
+        let inverted = invert(&usd_inr(80.0, 5)).unwrap();
+        assert_eq!(inverted.base, "INR");
+        assert_eq!(inverted.quote, "USD");
+        assert_eq!(inverted.rate, 0.0125);
+        assert_eq!(inverted.updated_at, 5);
+        assert!(invert(&usd_inr(0.0, 5)).is_none());
+    }
+
+    #[test]
+    fn test_normalize_currency() {
+        assert_eq!(normalize_currency(" usd ").unwrap(), "USD");
+        assert!(normalize_currency("US").is_err());
+        assert!(normalize_currency("U5D").is_err());
+        assert_eq!(normalize_optional_currency(Some(" ".to_string())).unwrap(), None);
+        assert_eq!(normalize_optional_currency(Some("inr".to_string())).unwrap(), Some("INR".to_string()));
+        assert!(normalize_optional_currency(Some("rupee".to_string())).is_err());
+    }
+}
@@ -14,6 +14,9 @@ use std::collections::HashMap;
 const POLYGON_API_BASE: &str = "https://api.polygon.io/v2";
 const POLYGON_API_KEY: &str = "8gQaSj2WkVK0iwh2fHDJxl4DhM02QrBl";
 
+// Yahoo Finance chart API (NSE/BSE listings, no key required)
+const YAHOO_CHART_API_BASE: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
+
 // CoinGecko API configuration (backup for crypto)
 const COINGECKO_API_BASE: &str = "https://api.coingecko.com/api/v3";
 
@@ -121,6 +124,7 @@ pub async fn fetch_price(token_ids: &str) -> Result<Vec<(String, f64)>, String>
 pub async fn fetch_polygon_historical(
     symbol: &str,
     days: u32,
+    now_ns: u64,
 ) -> Result<Vec<crate::types::StockPrice>, String> {
     use crate::types::StockPrice;
     
@@ -129,7 +133,7 @@ pub async fn fetch_polygon_historical(
     }
     
     // Calculate date range in milliseconds
-    let now_ms = (ic_cdk::api::time() / 1_000_000) as i64; // Convert nanoseconds to milliseconds
+    let now_ms = (now_ns / 1_000_000) as i64; // Convert nanoseconds to milliseconds
     let from_ms = now_ms - (days as i64 * 24 * 60 * 60 * 1000);
     
     let url = format!(
@@ -292,6 +296,205 @@ pub async fn fetch_polygon_stock_details(symbol: &str) -> Result<StockDetails, S
     }
 }
 
+/// Fetch the latest trailing-twelve-month financial statements from Polygon.io
+/// Endpoint: GET /vX/reference/financials?ticker={ticker}&timeframe=ttm
+pub async fn fetch_polygon_financials(symbol: &str, now_ns: u64) -> Result<crate::types::Fundamentals, String> {
+    if POLYGON_API_KEY.is_empty() {
+        return Err("Polygon API key not configured".to_string());
+    }
+    
+    let url = format!(
+        "https://api.polygon.io/vX/reference/financials?ticker={}&timeframe=ttm&order=desc&limit=1&apiKey={}",
+        symbol,
+        POLYGON_API_KEY
+    );
+    
+    let request = CanisterHttpRequestArgument {
+        url,
+        method: HttpMethod::GET,
+        body: None,
+        max_response_bytes: Some(100_000), // statements carry many line items
+        transform: Some(TransformContext::from_name("transform_response".to_string(), vec![])),
+        headers: vec![
+            HttpHeader {
+                name: "User-Agent".to_string(),
+                value: "Dhaniverse/1.0".to_string(),
+            },
+        ],
+    };
+    
+    match http_request(request, 25_000_000_000).await {
+        Ok((response,)) => {
+            if response.status != candid::Nat::from(200u8) {
+                return Err(format!("HTTP {}: {}", response.status, String::from_utf8_lossy(&response.body)));
+            }
+            
+            let body_str = String::from_utf8(response.body)
+                .map_err(|e| format!("UTF-8 decode error: {}", e))?;
+            
+            let json: Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("JSON parse error: {}", e))?;
+            
+            parse_polygon_financials(symbol, &json, now_ns)
+        }
+        Err((code, msg)) => {
+            Err(format!("HTTP request failed for {}: {:?} - {}", symbol, code, msg))
+        }
+    }
+}
+
+/// Pull earnings, debt, equity and share count out of a Polygon financials
+/// response. Debt prefers reported borrowings and falls back to total liabilities.
+fn parse_polygon_financials(symbol: &str, json: &Value, now_ns: u64) -> Result<crate::types::Fundamentals, String> {
+    let report = json.get("results")
+        .and_then(|r| r.as_array())
+        .and_then(|r| r.first())
+        .ok_or_else(|| format!("No financial statements for {}", symbol))?;
+    
+    let line_item = |statement: &str, field: &str| -> Option<f64> {
+        report.get("financials")?
+            .get(statement)?
+            .get(field)?
+            .get("value")?
+            .as_f64()
+            .filter(|v| v.is_finite())
+    };
+    
+    let net_income = line_item("income_statement", "net_income_loss_attributable_to_parent")
+        .or_else(|| line_item("income_statement", "net_income_loss"));
+    let eps = line_item("income_statement", "diluted_earnings_per_share")
+        .or_else(|| line_item("income_statement", "basic_earnings_per_share"));
+    let shares_outstanding = line_item("income_statement", "diluted_average_shares")
+        .or_else(|| line_item("income_statement", "basic_average_shares"))
+        .filter(|shares| *shares > 0.0)
+        .map(|shares| shares.round() as u64);
+    
+    let borrowings: Vec<f64> = ["long_term_debt", "current_debt"]
+        .iter()
+        .filter_map(|field| line_item("balance_sheet", field))
+        .collect();
+    let total_debt = if borrowings.is_empty() {
+        line_item("balance_sheet", "liabilities")
+    } else {
+        Some(borrowings.iter().sum())
+    };
+    let shareholder_equity = line_item("balance_sheet", "equity_attributable_to_parent")
+        .or_else(|| line_item("balance_sheet", "equity"));
+    
+    let field = |name: &str| report.get(name).and_then(|v| v.as_str()).unwrap_or("");
+    let period = format!("{} {}", field("fiscal_period"), field("end_date")).trim().to_string();
+    
+    Ok(crate::types::Fundamentals {
+        symbol: symbol.to_string(),
+        period,
+        net_income,
+        eps,
+        total_debt,
+        shareholder_equity,
+        shares_outstanding,
+        source: "Polygon.io".to_string(),
+        fetched_at: now_ns,
+    })
+}
+
+/// Fetch recent news articles for a ticker from Polygon.io
+/// Endpoint: GET /v2/reference/news?ticker={ticker}
+pub async fn fetch_polygon_news(symbol: &str, limit: u32) -> Result<Vec<crate::types::NewsItemRequest>, String> {
+    if POLYGON_API_KEY.is_empty() {
+        return Err("Polygon API key not configured".to_string());
+    }
+    
+    let url = format!(
+        "{}/reference/news?ticker={}&order=desc&limit={}&apiKey={}",
+        POLYGON_API_BASE, symbol, limit, POLYGON_API_KEY
+    );
+    
+    let request = CanisterHttpRequestArgument {
+        url,
+        method: HttpMethod::GET,
+        body: None,
+        max_response_bytes: Some(100_000), // articles carry descriptions and keywords
+        transform: Some(TransformContext::from_name("transform_response".to_string(), vec![])),
+        headers: vec![
+            HttpHeader {
+                name: "User-Agent".to_string(),
+                value: "Dhaniverse/1.0".to_string(),
+            },
+        ],
+    };
+    
+    match http_request(request, 25_000_000_000).await {
+        Ok((response,)) => {
+            if response.status != candid::Nat::from(200u8) {
+                return Err(format!("HTTP {}: {}", response.status, String::from_utf8_lossy(&response.body)));
+            }
+            
+            let body_str = String::from_utf8(response.body)
+                .map_err(|e| format!("UTF-8 decode error: {}", e))?;
+            
+            let json: Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("JSON parse error: {}", e))?;
+            
+            Ok(parse_polygon_news(symbol, &json))
+        }
+        Err((code, msg)) => {
+            Err(format!("HTTP request failed for {}: {:?} - {}", symbol, code, msg))
+        }
+    }
+}
+
+/// Map Polygon news results to news items. Sentiment comes from the article's
+/// insight for this ticker; articles without a title or publish time are skipped.
+fn parse_polygon_news(symbol: &str, json: &Value) -> Vec<crate::types::NewsItemRequest> {
+    use crate::types::{NewsItemRequest, NewsSentiment};
+    
+    let Some(results) = json.get("results").and_then(|r| r.as_array()) else {
+        return Vec::new();
+    };
+    
+    results
+        .iter()
+        .filter_map(|article| {
+            let headline = article.get("title").and_then(|t| t.as_str())?.to_string();
+            let published_at = article.get("published_utc")
+                .and_then(|p| p.as_str())
+                .and_then(crate::utils::parse_utc_timestamp)?;
+            
+            let sentiment = article.get("insights")
+                .and_then(|i| i.as_array())
+                .and_then(|insights| insights.iter().find(|insight| {
+                    insight.get("ticker").and_then(|t| t.as_str()) == Some(symbol)
+                }))
+                .and_then(|insight| insight.get("sentiment").and_then(|s| s.as_str()))
+                .map(|sentiment| match sentiment {
+                    "positive" => NewsSentiment::Positive,
+                    "negative" => NewsSentiment::Negative,
+                    _ => NewsSentiment::Neutral,
+                })
+                .unwrap_or(NewsSentiment::Neutral);
+            
+            let tags = article.get("keywords")
+                .and_then(|k| k.as_array())
+                .map(|keywords| keywords.iter().filter_map(|k| k.as_str().map(str::to_string)).collect())
+                .unwrap_or_default();
+            
+            Some(NewsItemRequest {
+                symbol: symbol.to_string(),
+                headline,
+                body: article.get("description").and_then(|d| d.as_str()).unwrap_or("").to_string(),
+                source: article.get("publisher")
+                    .and_then(|p| p.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("Polygon.io")
+                    .to_string(),
+                published_at,
+                sentiment,
+                tags,
+            })
+        })
+        .collect()
+}
+
 /// Stock details from Polygon.io ticker endpoint
 #[derive(Debug, Clone)]
 pub struct StockDetails {
@@ -408,44 +611,168 @@ async fn fetch_polygon_prev_close(symbol: &str) -> Result<f64, String> {
     }
 }
 
+/// Previous close of a currency pair, e.g. ("USD", "INR") -> rupees per dollar
+/// Endpoint: GET /v2/aggs/ticker/C:{BASE}{QUOTE}/prev
+pub async fn fetch_polygon_fx_rate(base: &str, quote: &str) -> Result<f64, String> {
+    if POLYGON_API_KEY.is_empty() {
+        return Err("Polygon API key not configured".to_string());
+    }
+    
+    fetch_polygon_prev_close(&format!("C:{}{}", base, quote)).await
+}
+
 /// Fallback function for when Polygon API fails
 /// Returns reasonable prices based on known stock data
 fn fetch_fallback_stock_prices(symbols: &[&str]) -> Result<Vec<(String, f64)>, String> {
     ic_cdk::println!("⚠️  Using fallback prices");
     
-    let fallback_prices: HashMap<&str, f64> = [
-        // Indian stocks (approximate prices in INR)
-        ("RELIANCE", 2500.0),
-        ("TCS", 3500.0),
-        ("HDFCBANK", 1650.0),
-        ("INFY", 1450.0),
-        ("ICICIBANK", 950.0),
-        ("HINDUNILVR", 2600.0),
-        ("ITC", 425.0),
-        ("SBIN", 575.0),
-        ("BHARTIARTL", 1500.0),
-        ("BAJFINANCE", 6800.0),
-        
-        // US stocks (in USD)
-        ("AAPL", 175.0),
-        ("GOOGL", 140.0),
-        ("MSFT", 370.0),
-        ("AMZN", 145.0),
-        ("TSLA", 250.0),
-        ("NVDA", 480.0),
-        ("META", 320.0),
-    ].iter().cloned().collect();
-    
     let mut results = Vec::new();
     for symbol in symbols {
         let symbol_upper = symbol.to_uppercase();
-        let price = fallback_prices.get(symbol_upper.as_str()).copied().unwrap_or(100.0);
+        let price = fallback_stock_price(&symbol_upper);
         results.push((symbol_upper, price));
     }
     
     Ok(results)
 }
 
+/// Approximate reference price for a known stock (100.0 for unknown symbols)
+pub fn fallback_stock_price(symbol: &str) -> f64 {
+    match symbol {
+        // Indian stocks (approximate prices in INR)
+        "RELIANCE" => 2500.0,
+        "TCS" => 3500.0,
+        "HDFCBANK" => 1650.0,
+        "INFY" => 1450.0,
+        "ICICIBANK" => 950.0,
+        "HINDUNILVR" => 2600.0,
+        "ITC" => 425.0,
+        "SBIN" => 575.0,
+        "BHARTIARTL" => 1500.0,
+        "BAJFINANCE" => 6800.0,
+        
+        // US stocks (in USD)
+        "AAPL" => 175.0,
+        "GOOGL" => 140.0,
+        "MSFT" => 370.0,
+        "AMZN" => 145.0,
+        "TSLA" => 250.0,
+        "NVDA" => 480.0,
+        "META" => 320.0,
+        _ => 100.0,
+    }
+}
+
+// ============================================================================
+// INDIAN EXCHANGE (NSE/BSE) QUOTES
+// ============================================================================
+
+/// Daily bars and the latest price for an NSE/BSE listing, in its quote currency
+#[derive(Debug, Clone)]
+pub struct ExchangeChart {
+    pub currency: String,
+    pub price: f64,
+    pub history: Vec<crate::types::StockPrice>,
+}
+
+/// Fetch daily bars for an NSE (".NS") or BSE (".BO") ticker from Yahoo Finance,
+/// which covers Indian listings that Polygon does not
+/// Endpoint: GET /v8/finance/chart/{ticker}?range={days}d&interval=1d
+pub async fn fetch_exchange_chart(feed_symbol: &str, days: u32) -> Result<ExchangeChart, String> {
+    let url = format!(
+        "{}/{}?range={}d&interval=1d",
+        YAHOO_CHART_API_BASE,
+        feed_symbol,
+        days.max(1)
+    );
+    
+    let request = CanisterHttpRequestArgument {
+        url,
+        method: HttpMethod::GET,
+        body: None,
+        max_response_bytes: Some(50_000), // 50KB for daily bars
+        transform: Some(TransformContext::from_name("transform_response".to_string(), vec![])),
+        headers: vec![
+            HttpHeader {
+                name: "User-Agent".to_string(),
+                value: "Dhaniverse/1.0".to_string(),
+            },
+        ],
+    };
+    
+    match http_request(request, 25_000_000_000).await {
+        Ok((response,)) => {
+            if response.status != candid::Nat::from(200u8) {
+                return Err(format!("HTTP {}: {}", response.status, String::from_utf8_lossy(&response.body)));
+            }
+            
+            let body_str = String::from_utf8(response.body)
+                .map_err(|e| format!("UTF-8 decode error: {}", e))?;
+            
+            let json: Value = serde_json::from_str(&body_str)
+                .map_err(|e| format!("JSON parse error: {}", e))?;
+            
+            parse_exchange_chart(feed_symbol, &json)
+        }
+        Err((code, msg)) => {
+            Err(format!("HTTP request failed for {}: {:?} - {}", feed_symbol, code, msg))
+        }
+    }
+}
+
+/// Parse a Yahoo chart response. Bars with a missing close (holidays,
+/// suspended sessions) are skipped.
+fn parse_exchange_chart(feed_symbol: &str, json: &Value) -> Result<ExchangeChart, String> {
+    use crate::types::StockPrice;
+    
+    let result = json.get("chart")
+        .and_then(|c| c.get("result"))
+        .and_then(|r| r.as_array())
+        .and_then(|r| r.first())
+        .ok_or_else(|| format!("Invalid response structure for {}", feed_symbol))?;
+    
+    let meta = result.get("meta");
+    let currency = meta
+        .and_then(|m| m.get("currency"))
+        .and_then(|c| c.as_str())
+        .ok_or_else(|| format!("No currency reported for {}", feed_symbol))?
+        .to_string();
+    
+    let timestamps = result.get("timestamp").and_then(|t| t.as_array()).cloned().unwrap_or_default();
+    let quote = result.get("indicators")
+        .and_then(|i| i.get("quote"))
+        .and_then(|q| q.as_array())
+        .and_then(|q| q.first());
+    let series = |field: &str, i: usize| -> Option<f64> {
+        quote?.get(field)?.as_array()?.get(i)?.as_f64()
+    };
+    
+    let mut history = Vec::new();
+    for (i, timestamp) in timestamps.iter().enumerate() {
+        let (Some(timestamp), Some(close)) = (timestamp.as_u64(), series("close", i)) else {
+            continue;
+        };
+        history.push(StockPrice {
+            timestamp: timestamp * 1_000_000_000, // seconds to nanoseconds
+            price: close,
+            volume: series("volume", i).map(|v| v as u64).unwrap_or(0),
+            high: series("high", i).unwrap_or(close),
+            low: series("low", i).unwrap_or(close),
+            open: series("open", i).unwrap_or(close),
+            close,
+        });
+    }
+    
+    let price = meta
+        .and_then(|m| m.get("regularMarketPrice"))
+        .and_then(|p| p.as_f64())
+        .or_else(|| history.last().map(|bar| bar.close))
+        .filter(|p| *p > 0.0)
+        .ok_or_else(|| format!("No price data for {}", feed_symbol))?;
+    
+    Ok(ExchangeChart { currency, price, history })
+}
+
 // ============================================================================
 // CACHING FOR CYCLE OPTIMIZATION
 // ============================================================================
@@ -518,24 +845,72 @@ pub fn get_cache_stats() -> (usize, usize) {
 // METRICS CALCULATION FROM REAL DATA
 // ============================================================================
 
-/// Calculate financial metrics from real Polygon.io data
+/// Calculate financial metrics from real Polygon.io data. P/E, EPS and D/E
+/// come from the company's statements when available and fall back to sector
+/// estimates otherwise; `provenance` records which.
 pub fn calculate_metrics(
     symbol: &str,
-    _current_price: f64,
+    current_price: f64,
     price_history: &[crate::types::StockPrice],
     details: &StockDetails,
+    fundamentals: Option<&crate::types::Fundamentals>,
 ) -> crate::types::StockMetrics {
-    use crate::types::StockMetrics;
+    use crate::types::{MetricProvenance, MetricSource, StockMetrics};
     
-    // P/E Ratio: Estimate based on sector averages (or use real if available)
-    let pe_ratio = estimate_pe_from_sector(symbol);
+    let mut provenance = MetricProvenance::estimated();
+    provenance.period = fundamentals.map(|f| f.period.clone());
     
-    // EPS: Calculate from market cap and P/E ratio
-    // EPS = (Market Cap / Outstanding Shares) / P/E Ratio
-    let eps = if details.outstanding_shares > 0 && pe_ratio > 0.0 {
-        (details.market_cap / details.outstanding_shares as f64) / pe_ratio
+    // Outstanding shares: ticker details, then the statements' share count
+    let outstanding_shares = if details.outstanding_shares > 0 {
+        provenance.outstanding_shares = MetricSource::Sourced;
+        details.outstanding_shares
+    } else if let Some(shares) = fundamentals.and_then(|f| f.shares_outstanding) {
+        provenance.outstanding_shares = MetricSource::Sourced;
+        shares
     } else {
-        0.0
+        0
+    };
+    
+    // Market cap: as reported, else price times the share count; zero when
+    // neither is known (the NSE/BSE chart feed reports no share count)
+    let market_cap = if details.market_cap > 0.0 {
+        details.market_cap
+    } else {
+        current_price * outstanding_shares as f64
+    };
+    
+    // EPS: reported, or net income over shares
+    let sourced_eps = fundamentals.and_then(|f| {
+        f.eps.or_else(|| {
+            let shares = f.shares_outstanding.filter(|s| *s > 0)?;
+            Some(f.net_income? / shares as f64)
+        })
+    });
+    
+    // P/E Ratio: price over sourced EPS; loss-making companies have no
+    // meaningful P/E, so it is left at 0 rather than estimated
+    let pe_ratio = match sourced_eps {
+        Some(eps) if eps > 0.0 && current_price > 0.0 => {
+            provenance.pe_ratio = MetricSource::Sourced;
+            current_price / eps
+        }
+        Some(eps) if eps <= 0.0 => {
+            provenance.pe_ratio = MetricSource::Unavailable;
+            0.0
+        }
+        _ => estimate_pe_from_sector(symbol),
+    };
+    
+    let eps = match sourced_eps {
+        Some(eps) => {
+            provenance.eps = MetricSource::Sourced;
+            eps
+        }
+        // EPS = (Market Cap / Outstanding Shares) / P/E Ratio
+        None if outstanding_shares > 0 && pe_ratio > 0.0 => {
+            (market_cap / outstanding_shares as f64) / pe_ratio
+        }
+        None => 0.0,
     };
     
     // Volatility: Calculate standard deviation from price history
@@ -554,21 +929,38 @@ pub fn calculate_metrics(
         0.0
     };
     
-    // Debt/Equity: Use industry average estimates
-    let debt_equity_ratio = estimate_debt_equity(symbol);
+    // Debt/Equity: from the balance sheet; with no positive equity the ratio
+    // is meaningless, so it is left at 0 rather than estimated
+    let equity = fundamentals.and_then(|f| f.shareholder_equity);
+    let sourced_debt_equity = fundamentals.and_then(|f| {
+        let equity = equity.filter(|e| *e > 0.0)?;
+        Some(f.total_debt? / equity)
+    });
+    let debt_equity_ratio = match (sourced_debt_equity, equity) {
+        (Some(ratio), _) => {
+            provenance.debt_equity_ratio = MetricSource::Sourced;
+            ratio
+        }
+        (None, Some(equity)) if equity <= 0.0 => {
+            provenance.debt_equity_ratio = MetricSource::Unavailable;
+            0.0
+        }
+        (None, _) => estimate_debt_equity(symbol),
+    };
     
-    // Industry Average P/E
+    // Industry Average P/E (no peer statements, so always a sector estimate)
     let industry_avg_pe = get_industry_avg_pe(symbol);
     
     StockMetrics {
-        market_cap: details.market_cap,
+        market_cap,
         pe_ratio,
         eps,
         debt_equity_ratio,
         business_growth,
         industry_avg_pe,
-        outstanding_shares: details.outstanding_shares,
+        outstanding_shares,
         volatility,
+        provenance: Some(provenance),
     }
 }
 
@@ -589,6 +981,11 @@ fn calculate_volatility(price_history: &[crate::types::StockPrice]) -> f64 {
         }
     }
     
+    annualized_volatility(&returns, 252.0) // 252 trading days per year
+}
+
+/// Annualized standard deviation of per-period returns
+pub fn annualized_volatility(returns: &[f64], periods_per_year: f64) -> f64 {
     if returns.is_empty() {
         return 0.0;
     }
@@ -604,8 +1001,7 @@ fn calculate_volatility(price_history: &[crate::types::StockPrice]) -> f64 {
         })
         .sum::<f64>() / returns.len() as f64;
     
-    // Return standard deviation (annualized)
-    variance.sqrt() * (252.0_f64).sqrt() // 252 trading days per year
+    variance.sqrt() * periods_per_year.sqrt()
 }
 
 /// Estimate P/E ratio based on sector
@@ -987,3 +1383,137 @@ pub async fn fetch_coin_history(coin_id: &str, date: &str) -> Result<HashMap<Str
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Fundamentals, MetricSource};
+
+    fn details(outstanding_shares: u64) -> StockDetails {
+        StockDetails {
+            name: "Apple Inc.".to_string(),
+            market_cap: 3_000_000_000_000.0,
+            outstanding_shares,
+            description: String::new(),
+        }
+    }
+
+    fn fundamentals() -> Fundamentals {
+        Fundamentals {
+            symbol: "AAPL".to_string(),
+            period: "TTM 2024-06-29".to_string(),
+            net_income: Some(100.0e9),
+            eps: None,
+            total_debt: Some(100.0e9),
+            shareholder_equity: Some(50.0e9),
+            shares_outstanding: Some(16_000_000_000),
+            source: "Polygon.io".to_string(),
+            fetched_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_polygon_financials() {
+        let json: Value = serde_json::json!({
+            "results": [{
+                "fiscal_period": "TTM",
+                "end_date": "2024-06-29",
+                "financials": {
+                    "income_statement": {
+                        "basic_earnings_per_share": { "value": 6.57 },
+                        "net_income_loss": { "value": 101.956e9 },
+                        "basic_average_shares": { "value": 15.5e9 }
+                    },
+                    "balance_sheet": {
+                        "long_term_debt": { "value": 86.2e9 },
+                        "liabilities": { "value": 264.9e9 },
+                        "equity": { "value": 66.7e9 }
+                    }
+                }
+            }]
+        });
+
+        let parsed = parse_polygon_financials("AAPL", &json, 42).unwrap();
+        assert_eq!(parsed.period, "TTM 2024-06-29");
+        assert_eq!(parsed.eps, Some(6.57));
+        assert_eq!(parsed.net_income, Some(101.956e9));
+        assert_eq!(parsed.shares_outstanding, Some(15_500_000_000));
+        assert_eq!(parsed.total_debt, Some(86.2e9)); // borrowings, not total liabilities
+        assert_eq!(parsed.shareholder_equity, Some(66.7e9));
+        assert_eq!(parsed.fetched_at, 42);
+
+        assert!(parse_polygon_financials("AAPL", &serde_json::json!({ "results": [] }), 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_exchange_chart() {
+        let json: Value = serde_json::json!({
+            "chart": { "result": [{
+                "meta": { "currency": "INR", "regularMarketPrice": 2951.4 },
+                "timestamp": [1718000000, 1718086400, 1718172800],
+                "indicators": { "quote": [{
+                    "open": [2900.0, null, 2940.0],
+                    "high": [2960.0, null, 2970.0],
+                    "low": [2890.0, null, 2930.0],
+                    "close": [2950.0, null, 2951.4],
+                    "volume": [5000000, null, 4200000]
+                }] }
+            }] }
+        });
+
+        let chart = parse_exchange_chart("RELIANCE.NS", &json).unwrap();
+        assert_eq!(chart.currency, "INR");
+        assert_eq!(chart.price, 2951.4);
+        assert_eq!(chart.history.len(), 2); // the holiday bar is skipped
+        assert_eq!(chart.history[1].timestamp, 1_718_172_800_000_000_000);
+        assert_eq!(chart.history[1].volume, 4_200_000);
+
+        let no_currency = serde_json::json!({ "chart": { "result": [{ "meta": {} }] } });
+        assert!(parse_exchange_chart("RELIANCE.NS", &no_currency).is_err());
+    }
+
+    #[test]
+    fn test_calculate_metrics_from_statements() {
+        let metrics = calculate_metrics("AAPL", 125.0, &[], &details(0), Some(&fundamentals()));
+        let provenance = metrics.provenance.unwrap();
+
+        assert_eq!(metrics.outstanding_shares, 16_000_000_000);
+        assert_eq!(metrics.eps, 6.25);
+        assert_eq!(metrics.pe_ratio, 20.0);
+        assert_eq!(metrics.debt_equity_ratio, 2.0);
+        assert_eq!(provenance.pe_ratio, MetricSource::Sourced);
+        assert_eq!(provenance.eps, MetricSource::Sourced);
+        assert_eq!(provenance.debt_equity_ratio, MetricSource::Sourced);
+        assert_eq!(provenance.industry_avg_pe, MetricSource::Estimated);
+        assert_eq!(provenance.period.as_deref(), Some("TTM 2024-06-29"));
+    }
+
+    #[test]
+    fn test_calculate_metrics_leaves_losses_unavailable() {
+        let mut loss_making = fundamentals();
+        loss_making.net_income = Some(-5.0e9);
+        loss_making.shareholder_equity = Some(-1.0e9);
+
+        let metrics = calculate_metrics("AAPL", 125.0, &[], &details(15_000_000_000), Some(&loss_making));
+        let provenance = metrics.provenance.unwrap();
+        assert_eq!(metrics.pe_ratio, 0.0);
+        assert_eq!(metrics.debt_equity_ratio, 0.0);
+        assert_eq!(provenance.pe_ratio, MetricSource::Unavailable);
+        assert_eq!(provenance.eps, MetricSource::Sourced); // the loss itself is reported
+        assert!(metrics.eps < 0.0);
+        assert_eq!(provenance.debt_equity_ratio, MetricSource::Unavailable);
+    }
+
+    #[test]
+    fn test_calculate_metrics_falls_back_to_estimates() {
+
+        let unsourced = calculate_metrics("AAPL", 125.0, &[], &details(15_000_000_000), None);
+        let provenance = unsourced.provenance.unwrap();
+        assert_eq!(unsourced.pe_ratio, estimate_pe_from_sector("AAPL"));
+        assert_eq!(unsourced.debt_equity_ratio, estimate_debt_equity("AAPL"));
+        assert_eq!(provenance.pe_ratio, MetricSource::Estimated);
+        assert_eq!(provenance.debt_equity_ratio, MetricSource::Estimated);
+        assert_eq!(provenance.eps, MetricSource::Estimated);
+        assert!(provenance.period.is_none());
+    }
+}
@@ -0,0 +1,312 @@
+use crate::types::*;
+use crate::error::*;
+use crate::stock_sse;
+use crate::market_data;
+
+// Indicator parameters
+const MIN_WINDOW: u32 = 2;
+const MAX_WINDOW: u32 = 200;
+const MACD_FAST: usize = 12;
+const MACD_SLOW: usize = 26;
+const MACD_SIGNAL: usize = 9;
+const BOLLINGER_STD_DEVS: f64 = 2.0;
+const MAX_OHLC_DAYS: u32 = 365;
+const NANOS_PER_MILLI: u64 = 1_000_000;
+
+// A series aligned with the input bars, None until the indicator has warmed up
+type Series = Vec<Option<f64>>;
+
+// One price bar; volume is missing for sources that do not report it
+#[derive(Debug, Clone)]
+struct Bar {
+    timestamp: u64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: Option<f64>,
+}
+
+// Indicators over the cached daily price history of a stock
+pub fn get_stock_indicators(symbol: String, window: u32) -> CanisterResult<IndicatorSeries> {
+    validate_window(window)?;
+
+    let stock = stock_sse::get_cached_stock_data(&symbol.trim().to_uppercase())?;
+    let bars: Vec<Bar> = stock.price_history
+        .iter()
+        .map(|price| Bar {
+            timestamp: price.timestamp,
+            high: price.high,
+            low: price.low,
+            close: price.close,
+            volume: Some(price.volume as f64),
+        })
+        .collect();
+
+    Ok(compute(stock.symbol, window, &bars))
+}
+
+// Indicators over CoinGecko OHLC candles (which carry no volume, so no VWAP)
+pub async fn get_crypto_indicators(coin_id: String, vs_currency: String, days: u32, window: u32) -> CanisterResult<IndicatorSeries> {
+    validate_window(window)?;
+    if days == 0 || days > MAX_OHLC_DAYS {
+        return Err(CanisterError::InvalidInput(format!("Days must be between 1 and {}", MAX_OHLC_DAYS)));
+    }
+
+    let ohlc = market_data::crypto_ohlc(&coin_id, &vs_currency, days).await
+        .map_err(|e| CanisterError::internal_error(format!("HTTP outcall failed: {}", e)))?;
+    let bars: Vec<Bar> = ohlc
+        .into_iter()
+        .map(|(timestamp_ms, _open, high, low, close)| Bar {
+            timestamp: timestamp_ms.saturating_mul(NANOS_PER_MILLI),
+            high,
+            low,
+            close,
+            volume: None,
+        })
+        .collect();
+
+    Ok(compute(coin_id, window, &bars))
+}
+
+fn validate_window(window: u32) -> CanisterResult<()> {
+    if !(MIN_WINDOW..=MAX_WINDOW).contains(&window) {
+        return Err(CanisterError::InvalidInput(format!("Window must be between {} and {}", MIN_WINDOW, MAX_WINDOW)));
+    }
+    Ok(())
+}
+
+fn compute(symbol: String, window: u32, bars: &[Bar]) -> IndicatorSeries {
+    let period = window as usize;
+    let closes: Vec<f64> = bars.iter().map(|bar| bar.close).collect();
+    let (macd, macd_signal, macd_histogram) = macd(&closes);
+    let (bollinger_upper, bollinger_middle, bollinger_lower) = bollinger(&closes, period, BOLLINGER_STD_DEVS);
+
+    IndicatorSeries {
+        symbol,
+        window,
+        timestamps: bars.iter().map(|bar| bar.timestamp).collect(),
+        sma: sma(&closes, period),
+        ema: ema(&closes, period),
+        rsi: rsi(&closes, period),
+        macd,
+        macd_signal,
+        macd_histogram,
+        bollinger_upper,
+        bollinger_middle,
+        bollinger_lower,
+        atr: atr(bars, period),
+        vwap: vwap(bars),
+        close: closes,
+    }
+}
+
+fn sma(values: &[f64], period: usize) -> Series {
+    (0..values.len())
+        .map(|i| {
+            (i + 1 >= period).then(|| values[i + 1 - period..=i].iter().sum::<f64>() / period as f64)
+        })
+        .collect()
+}
+
+// Seeded with the SMA of the first `period` values
+fn ema(values: &[f64], period: usize) -> Series {
+    let mut result = vec![None; values.len()];
+    if period == 0 || values.len() < period {
+        return result;
+    }
+
+    let alpha = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+    for (value, slot) in values.iter().zip(result.iter_mut()).skip(period) {
+        current += alpha * (value - current);
+        *slot = Some(current);
+    }
+
+    result
+}
+
+// EMA of a series that is None during its own warm-up
+fn ema_of_defined(values: &[Option<f64>], period: usize) -> Series {
+    let Some(start) = values.iter().position(|value| value.is_some()) else {
+        return vec![None; values.len()];
+    };
+
+    let defined: Vec<f64> = values[start..].iter().map(|value| value.unwrap_or(0.0)).collect();
+    let mut result = vec![None; start];
+    result.extend(ema(&defined, period));
+    result
+}
+
+// Wilder's RSI
+fn rsi(closes: &[f64], period: usize) -> Series {
+    let mut result = vec![None; closes.len()];
+    if period == 0 || closes.len() <= period {
+        return result;
+    }
+
+    let change = |i: usize| closes[i] - closes[i - 1];
+    let mut avg_gain = (1..=period).map(|i| change(i).max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = (1..=period).map(|i| (-change(i)).max(0.0)).sum::<f64>() / period as f64;
+    result[period] = Some(rsi_value(avg_gain, avg_loss));
+
+    for (i, slot) in result.iter_mut().enumerate().skip(period + 1) {
+        avg_gain = (avg_gain * (period as f64 - 1.0) + change(i).max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period as f64 - 1.0) + (-change(i)).max(0.0)) / period as f64;
+        *slot = Some(rsi_value(avg_gain, avg_loss));
+    }
+
+    result
+}
+
+fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        return if avg_gain == 0.0 { 50.0 } else { 100.0 };
+    }
+    100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+}
+
+// MACD line (12/26 EMA difference), its 9-period signal and the histogram
+fn macd(closes: &[f64]) -> (Series, Series, Series) {
+    let fast = ema(closes, MACD_FAST);
+    let slow = ema(closes, MACD_SLOW);
+    let line: Series = fast.iter().zip(&slow)
+        .map(|(fast, slow)| Some((*fast)? - (*slow)?))
+        .collect();
+    let signal = ema_of_defined(&line, MACD_SIGNAL);
+    let histogram = line.iter().zip(&signal)
+        .map(|(line, signal)| Some((*line)? - (*signal)?))
+        .collect();
+
+    (line, signal, histogram)
+}
+
+// Upper, middle and lower bands at `std_devs` population standard deviations
+fn bollinger(closes: &[f64], period: usize, std_devs: f64) -> (Series, Series, Series) {
+    let middle = sma(closes, period);
+    let mut upper = vec![None; closes.len()];
+    let mut lower = vec![None; closes.len()];
+
+    for (i, mean) in middle.iter().enumerate() {
+        if let Some(mean) = mean {
+            let window = &closes[i + 1 - period..=i];
+            let variance = window.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / period as f64;
+            let width = std_devs * variance.sqrt();
+            upper[i] = Some(mean + width);
+            lower[i] = Some(mean - width);
+        }
+    }
+
+    (upper, middle, lower)
+}
+
+// Wilder's average true range
+fn atr(bars: &[Bar], period: usize) -> Series {
+    let mut result = vec![None; bars.len()];
+    if period == 0 || bars.len() < period {
+        return result;
+    }
+
+    let true_range: Vec<f64> = bars.iter().enumerate()
+        .map(|(i, bar)| match i.checked_sub(1).map(|prev| bars[prev].close) {
+            Some(prev_close) => (bar.high - bar.low)
+                .max((bar.high - prev_close).abs())
+                .max((bar.low - prev_close).abs()),
+            None => bar.high - bar.low,
+        })
+        .collect();
+
+    let mut current = true_range[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+    for (range, slot) in true_range.iter().zip(result.iter_mut()).skip(period) {
+        current = (current * (period as f64 - 1.0) + range) / period as f64;
+        *slot = Some(current);
+    }
+
+    result
+}
+
+// Cumulative volume-weighted typical price from the first bar
+fn vwap(bars: &[Bar]) -> Series {
+    let mut price_volume = 0.0;
+    let mut total_volume = 0.0;
+
+    bars.iter()
+        .map(|bar| {
+            let volume = bar.volume?;
+            price_volume += (bar.high + bar.low + bar.close) / 3.0 * volume;
+            total_volume += volume;
+            (total_volume > 0.0).then(|| price_volume / total_volume)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(close: f64, volume: Option<f64>) -> Bar {
+        Bar { timestamp: 0, high: close + 1.0, low: close - 1.0, close, volume }
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        assert!((actual.unwrap() - expected).abs() < 1e-9, "{:?} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_moving_averages() {
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let sma = sma(&values, 3);
+        assert_eq!(sma[..2], [None, None]);
+        assert_close(sma[2], 2.0);
+        assert_close(sma[4], 4.0);
+
+        // alpha = 0.5, seeded at 2.0
+        let ema = ema(&values, 3);
+        assert_eq!(ema[1], None);
+        assert_close(ema[2], 2.0);
+        assert_close(ema[3], 3.0);
+        assert_close(ema[4], 4.0);
+    }
+
+    #[test]
+    fn test_rsi_extremes() {
+        let rising: Vec<f64> = (0..20).map(|i| 100.0 + i as f64).collect();
+        let rsi = rsi(&rising, 14);
+        assert_eq!(rsi[13], None);
+        assert_close(rsi[14], 100.0);
+
+        let flat = vec![50.0; 20];
+        assert_close(super::rsi(&flat, 14)[19], 50.0);
+    }
+
+    #[test]
+    fn test_macd_alignment() {
+        let closes: Vec<f64> = (0..40).map(|i| 100.0 + (i as f64).sin()).collect();
+        let (line, signal, histogram) = macd(&closes);
+
+        assert_eq!(line.len(), closes.len());
+        assert_eq!(line.iter().position(|value| value.is_some()), Some(MACD_SLOW - 1));
+        assert_eq!(signal.iter().position(|value| value.is_some()), Some(MACD_SLOW + MACD_SIGNAL - 2));
+        assert_close(histogram[39], line[39].unwrap() - signal[39].unwrap());
+    }
+
+    #[test]
+    fn test_bollinger_atr_and_vwap() {
+        let flat: Vec<Bar> = (0..5).map(|_| bar(10.0, Some(100.0))).collect();
+        let closes: Vec<f64> = flat.iter().map(|bar| bar.close).collect();
+
+        let (upper, middle, lower) = bollinger(&closes, 3, 2.0);
+        assert_close(upper[4], 10.0);
+        assert_close(middle[4], 10.0);
+        assert_close(lower[4], 10.0);
+
+        // Every bar spans 2.0 with no gaps
+        assert_close(atr(&flat, 3)[4], 2.0);
+
+        let bars = vec![bar(10.0, Some(100.0)), bar(20.0, Some(300.0))];
+        assert_close(vwap(&bars)[1], 17.5);
+        assert!(vwap(&[bar(10.0, None)]).iter().all(|value| value.is_none()));
+    }
+}
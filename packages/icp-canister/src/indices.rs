@@ -0,0 +1,471 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::trading;
+use crate::orders;
+use crate::instruments;
+use crate::market_calendar;
+use crate::stock_sse;
+use crate::utils::NANOS_PER_DAY;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+const PROCESS_INTERVAL_SECONDS: u64 = 15 * 60;
+const MAX_CONSTITUENTS: usize = 50;
+const MAX_INDEX_NAME_LEN: usize = 60;
+const MAX_REBALANCE_INTERVAL_DAYS: u32 = 366;
+
+const DHANIVERSE_10: &str = "DHANI10";
+const DHANIVERSE_10_NAME: &str = "Dhaniverse 10";
+const DHANIVERSE_10_CONSTITUENTS: [&str; 10] = [
+    "RELIANCE", "TCS", "HDFC", "ICICI", "INFY",
+    "AAPL", "MSFT", "NVDA", "GOOGL", "AMZN",
+];
+const DHANIVERSE_10_BASE_VALUE: f64 = 1_000.0;
+const DHANIVERSE_10_REBALANCE_DAYS: u32 = 30;
+
+thread_local! {
+    static INDEX_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+// Seed the Dhaniverse 10 on first install. It is established, fixing its
+// base date, the first time all ten constituents can be priced.
+pub fn ensure_default_indices() {
+    if storage::get_index(DHANIVERSE_10).is_some() {
+        return;
+    }
+
+    let constituents = DHANIVERSE_10_CONSTITUENTS
+        .iter()
+        .map(|symbol| IndexConstituent {
+            symbol: symbol.to_string(),
+            target_weight: 0.0,
+            units: 0.0,
+        })
+        .collect();
+
+    storage::set_index(&IndexDefinition {
+        id: DHANIVERSE_10.to_string(),
+        name: DHANIVERSE_10_NAME.to_string(),
+        weighting: IndexWeighting::MarketCap,
+        constituents,
+        base_value: DHANIVERSE_10_BASE_VALUE,
+        base_date: None,
+        rebalance_interval_days: DHANIVERSE_10_REBALANCE_DAYS,
+        last_rebalanced_at: None,
+        created_at: ic_cdk::api::time(),
+    });
+}
+
+pub fn start_index_processor() {
+    INDEX_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(PROCESS_INTERVAL_SECONDS), || {
+            let now = ic_cdk::api::time();
+            let rebalanced = rebalance_due_indices(now);
+            if rebalanced > 0 {
+                ic_cdk::println!("⚖️ Rebalanced {} indices", rebalanced);
+            }
+            let _ = stock_sse::broadcast_index_values(&list_index_values());
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Establish indices that could not be priced yet and rebalance those whose
+// interval has passed. Returns how many were (re)weighted.
+pub fn rebalance_due_indices(now: u64) -> usize {
+    let mut rebalanced = 0;
+    for mut index in storage::get_all_indices() {
+        if !is_rebalance_due(&index, now) {
+            continue;
+        }
+        match rebalance(&mut index, now) {
+            Ok(()) => {
+                storage::set_index(&index);
+                rebalanced += 1;
+            }
+            Err(e) => ic_cdk::println!("⚠️ Could not rebalance {}: {}", index.id, e),
+        }
+    }
+    rebalanced
+}
+
+// Define a new index or basket (controllers only). Its id becomes the stock
+// id baskets are held under, so it may not clash with a listed stock.
+pub fn create_index(request: IndexDefinitionRequest) -> CanisterResult<IndexDefinition> {
+    auth::verify_controller()?;
+    let now = ic_cdk::api::time();
+    let mut index = validate_request(request, now)?;
+
+    if storage::get_index(&index.id).is_some() {
+        return Err(CanisterError::InvalidInput(format!("Index {} already exists", index.id)));
+    }
+    if instruments::is_listed(&index.id) || storage::get_universe_stock(&index.id).is_some() {
+        return Err(CanisterError::InvalidInput(format!("{} is already a stock symbol", index.id)));
+    }
+
+    if let Err(e) = rebalance(&mut index, now) {
+        ic_cdk::println!("⏳ {} not established yet: {}", index.id, e);
+    }
+    storage::set_index(&index);
+    Ok(index)
+}
+
+// Re-weight an index now, keeping its value (controllers only)
+pub fn rebalance_index(index_id: String) -> CanisterResult<IndexDefinition> {
+    auth::verify_controller()?;
+    let mut index = find(&index_id)?;
+    rebalance(&mut index, ic_cdk::api::time())?;
+    storage::set_index(&index);
+    Ok(index)
+}
+
+pub fn get_index(index_id: String) -> CanisterResult<IndexDefinition> {
+    find(&index_id)
+}
+
+pub fn list_indices() -> Vec<IndexDefinition> {
+    storage::get_all_indices()
+}
+
+pub fn get_index_value(index_id: String) -> CanisterResult<IndexValue> {
+    value(&find(&index_id)?, ic_cdk::api::time())
+}
+
+// Current values of every established index that can be priced
+pub fn list_index_values() -> Vec<IndexValue> {
+    let now = ic_cdk::api::time();
+    storage::get_all_indices()
+        .iter()
+        .filter_map(|index| value(index, now).ok())
+        .collect()
+}
+
+pub fn is_index(stock_id: &str) -> bool {
+    storage::get_index(stock_id).is_some()
+}
+
+// Baskets trade through buy_index/sell_index, never as a plain stock
+pub fn ensure_not_index(stock_id: &str) -> CanisterResult<()> {
+    if is_index(stock_id) {
+        return Err(CanisterError::InvalidInput(format!("{} is an index basket; trade it with buy_index or sell_index", stock_id)));
+    }
+    Ok(())
+}
+
+// An index's rupee value, which is also the price of one basket unit
+pub fn index_price(index_id: &str, now: u64) -> CanisterResult<f64> {
+    Ok(value(&find(index_id)?, now)?.value)
+}
+
+// Buy `units` of a basket at its current value, held as a single position
+pub fn buy_index(wallet_address: String, index_id: String, units: u64) -> CanisterResult<StockTrade> {
+    auth::verify_session(&wallet_address)?;
+    let index = find(&index_id)?;
+    let now = ic_cdk::api::time();
+    ensure_trading(&index, now)?;
+
+    let price = value(&index, now)?.value;
+    trading::execute_buy(&wallet_address, &index.id, units, price)
+}
+
+pub fn sell_index(wallet_address: String, index_id: String, units: u64) -> CanisterResult<StockTrade> {
+    auth::verify_session(&wallet_address)?;
+    let index = find(&index_id)?;
+    let now = ic_cdk::api::time();
+    ensure_trading(&index, now)?;
+
+    let available = orders::available_shares(&wallet_address, &index.id);
+    if units > available {
+        return Err(CanisterError::InvalidInput(format!(
+            "Cannot sell {} units of {}, only {} held", units, index.id, available
+        )));
+    }
+    let price = value(&index, now)?.value;
+    trading::execute_sell(&wallet_address, &index.id, units, price)
+}
+
+// A split or bonus multiplies a constituent's share count by new/old shares;
+// holding that many more units keeps each index's value through the ex-date
+// price drop
+pub fn apply_share_ratio(symbol: &str, new_shares: u32, old_shares: u32) {
+    let factor = new_shares as f64 / old_shares as f64;
+    for mut index in storage::get_all_indices() {
+        if scale_units(&mut index.constituents, symbol, factor) {
+            storage::set_index(&index);
+        }
+    }
+}
+
+fn scale_units(constituents: &mut [IndexConstituent], symbol: &str, factor: f64) -> bool {
+    let mut scaled = false;
+    for constituent in constituents.iter_mut().filter(|constituent| constituent.symbol == symbol) {
+        constituent.units *= factor;
+        scaled = true;
+    }
+    scaled
+}
+
+fn find(index_id: &str) -> CanisterResult<IndexDefinition> {
+    let index_id = trading::normalize_stock_id(index_id)?;
+    storage::get_index(&index_id).ok_or_else(|| CanisterError::NotFound(format!("Index {}", index_id)))
+}
+
+// A basket trades while any of its constituents' markets is open. A basket
+// spanning exchanges whose sessions barely overlap (the Dhaniverse 10 mixes
+// NSE and US listings) would otherwise hardly ever trade, so constituents
+// whose market is closed are priced at their last quote, as an index level
+// published outside their session would be.
+fn ensure_trading(index: &IndexDefinition, now: u64) -> CanisterResult<()> {
+    if index.constituents.iter().any(|constituent| market_calendar::is_trading(&constituent.symbol, now)) {
+        return Ok(());
+    }
+    Err(CanisterError::MarketClosed(format!("every {} constituent market is closed", index.id)))
+}
+
+fn is_rebalance_due(index: &IndexDefinition, now: u64) -> bool {
+    match (index.base_date, index.last_rebalanced_at) {
+        (None, _) | (_, None) => true,
+        (Some(_), Some(_)) if index.rebalance_interval_days == 0 => false,
+        (Some(_), Some(last)) => now.saturating_sub(last) >= index.rebalance_interval_days as u64 * NANOS_PER_DAY,
+    }
+}
+
+// Rupee price and rupee market cap of a constituent from its last real quote;
+// a constituent never fetched leaves the index unpriced rather than valued
+// at a generated price
+fn quote(symbol: &str, now: u64) -> CanisterResult<(f64, f64)> {
+    let stock = stock_sse::get_quoted_stock(symbol)?;
+    let (_, fx_rate) = trading::rupee_rate(symbol, now)?;
+    let price = stock.current_price * fx_rate;
+    if price <= 0.0 || !price.is_finite() {
+        return Err(CanisterError::InternalError(format!("No valid price for {}", symbol)));
+    }
+    Ok((price, stock.metrics.market_cap * fx_rate))
+}
+
+fn quotes(index: &IndexDefinition, now: u64) -> CanisterResult<Vec<(f64, f64)>> {
+    index.constituents.iter().map(|constituent| quote(&constituent.symbol, now)).collect()
+}
+
+// Reset weights and units at current prices. A new index starts at its base
+// value; an established one keeps its current value across the rebalance.
+fn rebalance(index: &mut IndexDefinition, now: u64) -> CanisterResult<()> {
+    let quotes = quotes(index, now)?;
+    let prices: Vec<f64> = quotes.iter().map(|(price, _)| *price).collect();
+    let market_caps: Vec<f64> = quotes.iter().map(|(_, market_cap)| *market_cap).collect();
+
+    let level = match index.base_date {
+        Some(_) => level(&index.constituents, &prices),
+        None => index.base_value,
+    };
+    let weights = target_weights(index, &market_caps)
+        .ok_or_else(|| CanisterError::InternalError(format!("No usable market caps for {}", index.id)))?;
+
+    reset_units(&mut index.constituents, &weights, &prices, level);
+    index.base_date.get_or_insert(now);
+    index.last_rebalanced_at = Some(now);
+    Ok(())
+}
+
+fn value(index: &IndexDefinition, now: u64) -> CanisterResult<IndexValue> {
+    if index.base_date.is_none() {
+        return Err(CanisterError::NotFound(format!("{} has not been established yet", index.id)));
+    }
+    let prices: Vec<f64> = quotes(index, now)?.into_iter().map(|(price, _)| price).collect();
+    let value = level(&index.constituents, &prices);
+
+    let constituents = index
+        .constituents
+        .iter()
+        .zip(&prices)
+        .map(|(constituent, price)| IndexConstituentValue {
+            symbol: constituent.symbol.clone(),
+            price: *price,
+            weight: if value > 0.0 { constituent.units * price / value } else { 0.0 },
+        })
+        .collect();
+
+    Ok(IndexValue {
+        id: index.id.clone(),
+        name: index.name.clone(),
+        value,
+        change_since_base_percent: (value - index.base_value) / index.base_value * 100.0,
+        constituents,
+        timestamp: now,
+    })
+}
+
+fn level(constituents: &[IndexConstituent], prices: &[f64]) -> f64 {
+    constituents.iter().zip(prices).map(|(constituent, price)| constituent.units * price).sum()
+}
+
+// Weights summing to 1, or None when there is nothing to weight by. A
+// constituent without a known market cap (the NSE/BSE feed has no share
+// count) would drop out of a cap-weighted index, so such an index is weighted
+// equally until every cap is known.
+fn target_weights(index: &IndexDefinition, market_caps: &[f64]) -> Option<Vec<f64>> {
+    let caps_known = market_caps.iter().all(|cap| cap.is_finite() && *cap > 0.0);
+    let raw: Vec<f64> = match index.weighting {
+        IndexWeighting::Equal => vec![1.0; index.constituents.len()],
+        IndexWeighting::Custom => index.constituents.iter().map(|constituent| constituent.target_weight).collect(),
+        IndexWeighting::MarketCap if caps_known => market_caps.to_vec(),
+        IndexWeighting::MarketCap => vec![1.0; index.constituents.len()],
+    };
+
+    let total: f64 = raw.iter().sum();
+    (total > 0.0 && total.is_finite()).then(|| raw.iter().map(|weight| weight / total).collect())
+}
+
+fn reset_units(constituents: &mut [IndexConstituent], weights: &[f64], prices: &[f64], level: f64) {
+    for ((constituent, weight), price) in constituents.iter_mut().zip(weights).zip(prices) {
+        constituent.target_weight = *weight;
+        constituent.units = weight * level / price;
+    }
+}
+
+fn validate_request(request: IndexDefinitionRequest, now: u64) -> CanisterResult<IndexDefinition> {
+    let id = trading::normalize_stock_id(&request.id)?;
+    let name = request.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > MAX_INDEX_NAME_LEN {
+        return Err(CanisterError::InvalidInput(format!("Index name must be 1-{} characters", MAX_INDEX_NAME_LEN)));
+    }
+    if request.constituents.is_empty() || request.constituents.len() > MAX_CONSTITUENTS {
+        return Err(CanisterError::InvalidInput(format!("An index needs 1-{} constituents", MAX_CONSTITUENTS)));
+    }
+    if !request.base_value.is_finite() || request.base_value <= 0.0 {
+        return Err(CanisterError::InvalidAmount);
+    }
+    if request.rebalance_interval_days > MAX_REBALANCE_INTERVAL_DAYS {
+        return Err(CanisterError::InvalidInput(format!("Rebalance at least every {} days", MAX_REBALANCE_INTERVAL_DAYS)));
+    }
+
+    let mut constituents: Vec<IndexConstituent> = Vec::new();
+    for requested in request.constituents {
+        let symbol = trading::normalize_stock_id(&requested.symbol)?;
+        if symbol == id || constituents.iter().any(|constituent| constituent.symbol == symbol) {
+            return Err(CanisterError::InvalidInput(format!("{} is listed twice or is the index itself", symbol)));
+        }
+        let target_weight = match (request.weighting, requested.weight) {
+            (IndexWeighting::Custom, Some(weight)) if weight.is_finite() && weight > 0.0 => weight,
+            (IndexWeighting::Custom, _) => {
+                return Err(CanisterError::InvalidInput(format!("{} needs a positive custom weight", symbol)));
+            }
+            _ => 0.0,
+        };
+        constituents.push(IndexConstituent { symbol, target_weight, units: 0.0 });
+    }
+
+    Ok(IndexDefinition {
+        id,
+        name,
+        weighting: request.weighting,
+        constituents,
+        base_value: request.base_value,
+        base_date: None,
+        rebalance_interval_days: request.rebalance_interval_days,
+        last_rebalanced_at: None,
+        created_at: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(weighting: IndexWeighting, weights: &[(&str, Option<f64>)]) -> IndexDefinitionRequest {
+        IndexDefinitionRequest {
+            id: " bank3 ".to_string(),
+            name: "Bank 3".to_string(),
+            weighting,
+            constituents: weights
+                .iter()
+                .map(|(symbol, weight)| IndexConstituentRequest { symbol: symbol.to_string(), weight: *weight })
+                .collect(),
+            base_value: 1_000.0,
+            rebalance_interval_days: 30,
+        }
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let custom = validate_request(request(IndexWeighting::Custom, &[("HDFC", Some(3.0)), ("ICICI", Some(1.0))]), 0).unwrap();
+        assert_eq!(custom.id, "BANK3");
+        assert_eq!(custom.constituents[0].target_weight, 3.0);
+
+        assert!(validate_request(request(IndexWeighting::Custom, &[("HDFC", None)]), 0).is_err());
+        assert!(validate_request(request(IndexWeighting::Equal, &[("HDFC", None), ("hdfc", None)]), 0).is_err());
+        assert!(validate_request(request(IndexWeighting::Equal, &[]), 0).is_err());
+    }
+
+    #[test]
+    fn test_split_keeps_index_level() {
+        let mut constituents = vec![
+            IndexConstituent { symbol: "TCS".to_string(), target_weight: 0.5, units: 0.2 },
+            IndexConstituent { symbol: "INFY".to_string(), target_weight: 0.5, units: 0.5 },
+        ];
+        let before = level(&constituents, &[2_500.0, 1_000.0]);
+
+        // 2-for-1 split halves the TCS price
+        assert!(scale_units(&mut constituents, "TCS", 2.0));
+        assert_eq!(constituents[0].units, 0.4);
+        assert_eq!(level(&constituents, &[1_250.0, 1_000.0]), before);
+        assert!(!scale_units(&mut constituents, "WIPRO", 2.0));
+    }
+
+    #[test]
+    fn test_target_weights() {
+        let custom = validate_request(request(IndexWeighting::Custom, &[("HDFC", Some(3.0)), ("ICICI", Some(1.0))]), 0).unwrap();
+        assert_eq!(target_weights(&custom, &[0.0, 0.0]), Some(vec![0.75, 0.25]));
+
+        let mut by_cap = custom.clone();
+        by_cap.weighting = IndexWeighting::MarketCap;
+        assert_eq!(target_weights(&by_cap, &[600.0, 200.0]), Some(vec![0.75, 0.25]));
+        assert_eq!(target_weights(&by_cap, &[600.0, 0.0]), Some(vec![0.5, 0.5]));
+        assert_eq!(target_weights(&by_cap, &[600.0, f64::NAN]), Some(vec![0.5, 0.5]));
+
+        let mut equal = custom;
+        equal.weighting = IndexWeighting::Equal;
+        assert_eq!(target_weights(&equal, &[0.0, 0.0]), Some(vec![0.5, 0.5]));
+    }
+
+    #[test]
+    fn test_rebalance_keeps_level() {
+        let mut index = validate_request(request(IndexWeighting::Equal, &[("HDFC", None), ("ICICI", None)]), 0).unwrap();
+
+        // Established at the base value: 500 rupees in each at 100 and 50
+        reset_units(&mut index.constituents, &[0.5, 0.5], &[100.0, 50.0], index.base_value);
+        assert_eq!(index.constituents[0].units, 5.0);
+        assert_eq!(index.constituents[1].units, 10.0);
+
+        // HDFC doubles: the index rises 50% and HDFC drifts to two thirds of it
+        let moved = [200.0, 50.0];
+        let risen = level(&index.constituents, &moved);
+        assert_eq!(risen, 1_500.0);
+
+        // Rebalancing restores equal weights without moving the level
+        reset_units(&mut index.constituents, &[0.5, 0.5], &moved, risen);
+        assert_eq!(level(&index.constituents, &moved), risen);
+        assert_eq!(index.constituents[0].units * moved[0], index.constituents[1].units * moved[1]);
+    }
+
+    #[test]
+    fn test_is_rebalance_due() {
+        let mut index = validate_request(request(IndexWeighting::Equal, &[("HDFC", None)]), 0).unwrap();
+        assert!(is_rebalance_due(&index, 0));
+
+        index.base_date = Some(0);
+        index.last_rebalanced_at = Some(0);
+        assert!(!is_rebalance_due(&index, 29 * NANOS_PER_DAY));
+        assert!(is_rebalance_due(&index, 30 * NANOS_PER_DAY));
+
+        index.rebalance_interval_days = 0;
+        assert!(!is_rebalance_due(&index, 365 * NANOS_PER_DAY));
+    }
+}
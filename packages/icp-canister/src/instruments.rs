@@ -0,0 +1,316 @@
+use crate::types::*;
+use crate::error::*;
+use crate::trading;
+use crate::storage;
+use crate::stock_sse;
+use crate::market_sim;
+
+pub const INR: &str = "INR";
+pub const USD: &str = "USD";
+
+// NSE and BSE share the 09:15-15:30 IST cash session
+const INDIAN_SESSION: (u16, u16, i16) = (9 * 60 + 15, 15 * 60 + 30, 330);
+// US regular session, 09:30-16:00 ET at the standard-time offset
+const US_SESSION: (u16, u16, i16) = (9 * 60 + 30, 16 * 60, -300);
+
+pub const UNCLASSIFIED: &str = "Unclassified";
+
+// Game id, company name, exchange, exchange ticker and sector for the listings
+// the game knows about. Indian ids keep the short names used by the mock data.
+const LISTINGS: &[(&str, &str, Exchange, &str, &str)] = &[
+    ("RELIANCE", "Reliance Industries Ltd", Exchange::NSE, "RELIANCE", "Energy"),
+    ("TCS", "Tata Consultancy Services", Exchange::NSE, "TCS", "Technology"),
+    ("INFY", "Infosys Limited", Exchange::NSE, "INFY", "Technology"),
+    ("WIPRO", "Wipro Limited", Exchange::NSE, "WIPRO", "Technology"),
+    ("TECHM", "Tech Mahindra Limited", Exchange::NSE, "TECHM", "Technology"),
+    ("HDFC", "HDFC Bank Limited", Exchange::NSE, "HDFCBANK", "Financials"),
+    ("ICICI", "ICICI Bank Limited", Exchange::NSE, "ICICIBANK", "Financials"),
+    ("SBI", "State Bank of India", Exchange::NSE, "SBIN", "Financials"),
+    ("BHARTI", "Bharti Airtel Limited", Exchange::NSE, "BHARTIARTL", "Telecom"),
+    ("ITC", "ITC Limited", Exchange::NSE, "ITC", "Consumer Goods"),
+    ("HINDUNILVR", "Hindustan Unilever Limited", Exchange::NSE, "HINDUNILVR", "Consumer Goods"),
+    ("BAJFINANCE", "Bajaj Finance Limited", Exchange::NSE, "BAJFINANCE", "Financials"),
+    ("AAPL", "Apple Inc.", Exchange::NASDAQ, "AAPL", "Technology"),
+    ("GOOGL", "Alphabet Inc.", Exchange::NASDAQ, "GOOGL", "Technology"),
+    ("MSFT", "Microsoft Corporation", Exchange::NASDAQ, "MSFT", "Technology"),
+    ("AMZN", "Amazon.com, Inc.", Exchange::NASDAQ, "AMZN", "Technology"),
+    ("TSLA", "Tesla, Inc.", Exchange::NASDAQ, "TSLA", "Automobile"),
+    ("NVDA", "NVIDIA Corporation", Exchange::NASDAQ, "NVDA", "Technology"),
+    ("META", "Meta Platforms, Inc.", Exchange::NASDAQ, "META", "Technology"),
+    ("NFLX", "Netflix, Inc.", Exchange::NASDAQ, "NFLX", "Technology"),
+    ("AMD", "Advanced Micro Devices, Inc.", Exchange::NASDAQ, "AMD", "Technology"),
+    ("INTC", "Intel Corporation", Exchange::NASDAQ, "INTC", "Technology"),
+    ("CSCO", "Cisco Systems, Inc.", Exchange::NASDAQ, "CSCO", "Technology"),
+    ("JPM", "JPMorgan Chase & Co.", Exchange::NYSE, "JPM", "Financials"),
+    ("BAC", "Bank of America Corporation", Exchange::NYSE, "BAC", "Financials"),
+    ("WFC", "Wells Fargo & Company", Exchange::NYSE, "WFC", "Financials"),
+    ("GS", "The Goldman Sachs Group, Inc.", Exchange::NYSE, "GS", "Financials"),
+    ("XOM", "Exxon Mobil Corporation", Exchange::NYSE, "XOM", "Energy"),
+    ("CVX", "Chevron Corporation", Exchange::NYSE, "CVX", "Energy"),
+    ("JNJ", "Johnson & Johnson", Exchange::NYSE, "JNJ", "Healthcare"),
+    ("UNH", "UnitedHealth Group Incorporated", Exchange::NYSE, "UNH", "Healthcare"),
+    ("PFE", "Pfizer Inc.", Exchange::NYSE, "PFE", "Healthcare"),
+    ("F", "Ford Motor Company", Exchange::NYSE, "F", "Automobile"),
+    ("GM", "General Motors Company", Exchange::NYSE, "GM", "Automobile"),
+];
+
+pub fn is_indian(exchange: Exchange) -> bool {
+    matches!(exchange, Exchange::NSE | Exchange::BSE)
+}
+
+// Metadata for a normalized symbol. Listings outside the table take the
+// exchange and currency set on their universe entry or simulated instrument,
+// are otherwise inferred from a ".NS"/".BO" suffix, and are otherwise treated
+// as US shares.
+pub fn lookup(symbol: &str) -> Instrument {
+    if let Some((symbol, name, exchange, exchange_symbol, _)) = LISTINGS.iter().find(|listing| listing.0 == symbol) {
+        return instrument(symbol, name, *exchange, exchange_symbol);
+    }
+
+    let (exchange, currency) = configured_listing(symbol);
+    let mut listing = if let Some(exchange) = exchange {
+        instrument(symbol, symbol, exchange, symbol)
+    } else if let Some(ticker) = symbol.strip_suffix(".NS") {
+        instrument(symbol, ticker, Exchange::NSE, ticker)
+    } else if let Some(ticker) = symbol.strip_suffix(".BO") {
+        instrument(symbol, ticker, Exchange::BSE, ticker)
+    } else {
+        instrument(symbol, symbol, Exchange::NASDAQ, symbol)
+    };
+    if let Some(currency) = currency {
+        listing.currency = currency;
+    }
+    listing
+}
+
+// Exchange and currency a controller set for a symbol, preferring the
+// universe entry over the simulator configuration
+fn configured_listing(symbol: &str) -> (Option<Exchange>, Option<String>) {
+    let universe = storage::get_universe_stock(symbol)
+        .map(|entry| (entry.exchange, entry.currency))
+        .filter(|(exchange, currency)| exchange.is_some() || currency.is_some());
+    universe
+        .or_else(|| market_sim::simulated_instrument(symbol).map(|instrument| (instrument.exchange, instrument.currency)))
+        .unwrap_or((None, None))
+}
+
+// Whether the symbol is one of the game's known listings
+pub fn is_listed(symbol: &str) -> bool {
+    LISTINGS.iter().any(|listing| listing.0 == symbol)
+}
+
+// Shares can be traded in listed symbols, universe entries and simulated
+// instruments; anything else would only ever have a generated price
+pub fn ensure_tradable(symbol: &str) -> CanisterResult<()> {
+    if is_listed(symbol)
+        || storage::get_universe_stock(symbol).is_some()
+        || market_sim::simulated_instrument(symbol).is_some()
+    {
+        return Ok(());
+    }
+    Err(CanisterError::InvalidInput(format!("{} is not a tradable instrument", symbol)))
+}
+
+// Sector used for allocation breakdowns: the universe entry's, then a
+// simulated instrument's, then the listing's; anything else is unclassified
+pub fn sector_of(symbol: &str) -> String {
+    universe_sector(symbol)
+        .or_else(|| {
+            market_sim::simulated_instrument(symbol)
+                .map(|instrument| instrument.sector)
+                .filter(|sector| !sector.is_empty())
+        })
+        .or_else(|| listed_sector(symbol).map(str::to_string))
+        .unwrap_or_else(|| UNCLASSIFIED.to_string())
+}
+
+// Sector from the universe entry or the listing, for seeding simulated instruments
+pub fn catalog_sector(symbol: &str) -> String {
+    universe_sector(symbol)
+        .or_else(|| listed_sector(symbol).map(str::to_string))
+        .unwrap_or_else(|| UNCLASSIFIED.to_string())
+}
+
+fn universe_sector(symbol: &str) -> Option<String> {
+    storage::get_universe_stock(symbol).and_then(|entry| entry.sector)
+}
+
+fn listed_sector(symbol: &str) -> Option<&'static str> {
+    LISTINGS.iter().find(|listing| listing.0 == symbol).map(|listing| listing.4)
+}
+
+pub fn is_indian_symbol(symbol: &str) -> bool {
+    is_indian(lookup(&symbol.trim().to_uppercase()).exchange)
+}
+
+// Regular session for an exchange; the market calendar adds pre-open,
+// holidays and US daylight saving on top
+pub fn trading_hours(exchange: Exchange) -> TradingHours {
+    let (open_minute, close_minute, utc_offset_minutes) = if is_indian(exchange) {
+        INDIAN_SESSION
+    } else {
+        US_SESSION
+    };
+    TradingHours {
+        open_minute,
+        close_minute,
+        utc_offset_minutes,
+    }
+}
+
+fn instrument(symbol: &str, name: &str, exchange: Exchange, exchange_symbol: &str) -> Instrument {
+    let (currency, tick_size) = if is_indian(exchange) { (INR, 0.05) } else { (USD, 0.01) };
+
+    Instrument {
+        symbol: symbol.to_string(),
+        name: name.to_string(),
+        exchange,
+        exchange_symbol: exchange_symbol.to_string(),
+        currency: currency.to_string(),
+        lot_size: 1, // cash equities trade in single shares on all four exchanges
+        tick_size,
+        trading_hours: trading_hours(exchange),
+    }
+}
+
+// Ticker in the form Indian quote feeds expect: "HDFCBANK.NS", "500325.BO"
+pub fn feed_symbol(instrument: &Instrument) -> String {
+    match instrument.exchange {
+        Exchange::NSE => format!("{}.NS", instrument.exchange_symbol),
+        Exchange::BSE => format!("{}.BO", instrument.exchange_symbol),
+        Exchange::NASDAQ | Exchange::NYSE => instrument.exchange_symbol.clone(),
+    }
+}
+
+// Quantities must be whole lots; a limit or trigger price must sit on the tick grid
+pub fn validate_order(instrument: &Instrument, quantity: u64, price: Option<f64>) -> CanisterResult<()> {
+    if instrument.lot_size > 1 && !quantity.is_multiple_of(instrument.lot_size) {
+        return Err(CanisterError::InvalidInput(format!(
+            "{} trades in lots of {}", instrument.symbol, instrument.lot_size
+        )));
+    }
+
+    if let Some(price) = price {
+        let ticks = price / instrument.tick_size;
+        if (ticks - ticks.round()).abs() > 1e-6 {
+            return Err(CanisterError::InvalidInput(format!(
+                "{} prices move in steps of {} {}", instrument.symbol, instrument.tick_size, instrument.currency
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn get_instrument(symbol: String) -> CanisterResult<Instrument> {
+    Ok(lookup(&trading::normalize_stock_id(&symbol)?))
+}
+
+pub fn list_instruments() -> Vec<Instrument> {
+    LISTINGS
+        .iter()
+        .map(|(symbol, name, exchange, exchange_symbol, _)| instrument(symbol, name, *exchange, exchange_symbol))
+        .collect()
+}
+
+// Current price for a symbol with the currency it is quoted in
+pub fn get_stock_quote(symbol: String) -> CanisterResult<StockQuote> {
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    let stock = stock_sse::get_cached_stock_data(&symbol)?;
+    if stock.current_price <= 0.0 || !stock.current_price.is_finite() {
+        return Err(CanisterError::InternalError(format!("No valid price for {}", symbol)));
+    }
+    let instrument = lookup(&symbol);
+
+    Ok(StockQuote {
+        symbol,
+        exchange: stock.exchange.unwrap_or(instrument.exchange),
+        price: stock.current_price,
+        currency: stock.currency.unwrap_or(instrument.currency),
+        timestamp: stock.last_update,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let hdfc = lookup("HDFC");
+        assert_eq!(hdfc.exchange, Exchange::NSE);
+        assert_eq!(hdfc.currency, INR);
+        assert_eq!(feed_symbol(&hdfc), "HDFCBANK.NS");
+
+        let bse = lookup("500325.BO");
+        assert_eq!(bse.exchange, Exchange::BSE);
+        assert_eq!(feed_symbol(&bse), "500325.BO");
+
+        let aapl = lookup("AAPL");
+        assert_eq!(aapl.currency, USD);
+        assert_eq!(aapl.trading_hours.utc_offset_minutes, -300);
+        assert_eq!(lookup("UNLISTED").exchange, Exchange::NASDAQ);
+    }
+
+    #[test]
+    fn test_lookup_uses_universe_listing() {
+        storage::set_universe_stock(&UniverseStock {
+            symbol: "BAJAJ-AUTO".to_string(),
+            enabled: true,
+            priority: 0,
+            display_name: None,
+            sector: None,
+            logo_url: None,
+            exchange: Some(Exchange::NSE),
+            currency: None,
+            added_at: 0,
+            updated_at: 0,
+        });
+
+        let bajaj = lookup("BAJAJ-AUTO");
+        assert_eq!(bajaj.exchange, Exchange::NSE);
+        assert_eq!(bajaj.currency, INR);
+        assert_eq!(bajaj.tick_size, 0.05);
+        assert_eq!(sector_of("BAJAJ-AUTO"), UNCLASSIFIED);
+    }
+
+    #[test]
+    fn test_ensure_tradable() {
+        assert!(ensure_tradable("RELIANCE").is_ok());
+        assert!(ensure_tradable("ZZZZ").is_err());
+    }
+
+    #[test]
+    fn test_sector_of() {
+        assert_eq!(sector_of("TCS"), "Technology");
+        assert_eq!(sector_of("SBI"), "Financials");
+        assert_eq!(sector_of("UNLISTED"), UNCLASSIFIED);
+
+        storage::set_universe_stock(&UniverseStock {
+            symbol: "SBI".to_string(),
+            enabled: true,
+            priority: 0,
+            display_name: None,
+            sector: Some("Public Sector Banks".to_string()),
+            logo_url: None,
+            exchange: None,
+            currency: None,
+            added_at: 0,
+            updated_at: 0,
+        });
+        assert_eq!(sector_of("SBI"), "Public Sector Banks");
+        assert_eq!(catalog_sector("SBI"), "Public Sector Banks");
+    }
+
+    #[test]
+    fn test_validate_order() {
+        let reliance = lookup("RELIANCE");
+        assert!(validate_order(&reliance, 3, Some(2500.05)).is_ok());
+        assert!(validate_order(&reliance, 3, Some(2500.03)).is_err());
+        assert!(validate_order(&reliance, 3, None).is_ok());
+
+        let mut lot_of_five = reliance;
+        lot_of_five.lot_size = 5;
+        assert!(validate_order(&lot_of_five, 10, None).is_ok());
+        assert!(validate_order(&lot_of_five, 7, None).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::currency;
+use crate::savings;
+use crate::central_bank;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Interest is credited once per calendar month; the timer only has to notice
+// the month rolling over.
+const CREDIT_CHECK_INTERVAL_SECONDS: u64 = 60 * 60;
+const DAYS_PER_YEAR: f64 = 365.0;
+const RUPEE_DECIMALS: u8 = 2;
+
+thread_local! {
+    static CREDIT_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_crediting() {
+    CREDIT_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(CREDIT_CHECK_INTERVAL_SECONDS), || {
+            let credited = credit_monthly_interest(ic_cdk::api::time());
+            if credited > 0 {
+                ic_cdk::println!("🏦 Credited monthly interest to {} wallets", credited);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Accrue interest on the rupee balance held since the last balance checkpoint
+// (`DualBalance.last_updated`) and move the checkpoint to `now`. Must run before
+// any change to the dual balance so each period is priced at the balance
+// actually held. The interest goes into `writes` and reaches the interest
+// account only with the caller's `storage::commit_user_data`. Returns the
+// interest accrued.
+pub fn accrue(user_data: &mut UserData, writes: &mut PendingWrites, now: u64) -> CanisterResult<f64> {
+    let elapsed = now.saturating_sub(user_data.dual_balance.last_updated);
+    if elapsed == 0 {
+        return Ok(0.0);
+    }
+
+    let interest = pending_interest(user_data.dual_balance.rupees_balance, user_data.dual_balance.last_updated, now)?;
+
+    if interest > 0.0 {
+        writes.accrued_interest = utils::safe_add(writes.accrued_interest, interest)?;
+    }
+
+    user_data.dual_balance.last_updated = now;
+
+    Ok(interest)
+}
+
+// Credit accrued interest to every wallet that has not been paid this month.
+// Returns the number of wallets credited.
+pub fn credit_monthly_interest(now: u64) -> usize {
+    let mut credited = 0;
+
+    for wallet_address in storage::get_all_user_addresses() {
+        match credit_wallet(&wallet_address, now) {
+            Ok(true) => credited += 1,
+            Ok(false) => {}
+            Err(e) => ic_cdk::println!("⚠️ Interest crediting failed for {}: {}", wallet_address, e),
+        }
+    }
+
+    credited
+}
+
+fn credit_wallet(wallet_address: &str, now: u64) -> CanisterResult<bool> {
+    let mut user_data = storage::get_user_data(wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let mut account = storage::get_interest_account(wallet_address).unwrap_or_default();
+
+    let last_paid = account.last_credited_at.unwrap_or(user_data.created_at);
+    if same_month(last_paid, now) {
+        return Ok(false);
+    }
+
+    let mut writes = PendingWrites::default();
+    accrue(&mut user_data, &mut writes, now)?;
+    // Pay out what was just accrued too, so move it straight into the account
+    account.accrued_interest = utils::safe_add(account.accrued_interest, std::mem::take(&mut writes.accrued_interest))?;
+
+    // Pay whole paise only; the fraction carries into next month
+    let payout = currency::round_down(account.accrued_interest, RUPEE_DECIMALS);
+    account.last_credited_at = Some(now);
+
+    if payout <= 0.0 {
+        storage::set_interest_account(wallet_address, &account);
+        storage::commit_user_data(wallet_address, user_data, writes)?;
+        return Ok(false);
+    }
+
+    account.accrued_interest = (account.accrued_interest - payout).max(0.0);
+    account.total_credited = utils::safe_add(account.total_credited, payout)?;
+
+    currency::credit(&mut user_data, &mut writes, currency::RUPEES, payout)?;
+    savings::sweep_incoming_credit(&mut user_data, &mut writes, payout)?;
+
+    let transaction = Web3Transaction {
+        id: utils::generate_transaction_id(),
+        from: wallet_address.to_string(),
+        to: None,
+        amount: payout,
+        transaction_type: TransactionType::Interest,
+        timestamp: now,
+        status: TransactionStatus::Confirmed,
+        hash: Some(utils::generate_transaction_hash()),
+    };
+
+    storage::set_interest_account(wallet_address, &account);
+    storage::commit_user_data(wallet_address, user_data, writes)?;
+    storage::append_transaction(wallet_address, transaction);
+
+    Ok(true)
+}
+
+// Interest state for a wallet, including what has accrued since the last checkpoint
+pub fn get_interest_summary(wallet_address: String) -> CanisterResult<InterestSummary> {
+    let user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let account = storage::get_interest_account(&wallet_address).unwrap_or_default();
+    let config = storage::get_interest_config();
+
+    let balance = user_data.dual_balance.rupees_balance;
+    let rate = if config.enabled {
+        deposit_rate(rate_for_balance(&config.tiers, balance), central_bank::deposit_rate_adjustment())
+    } else {
+        0.0
+    };
+    let pending = pending_interest(balance, user_data.dual_balance.last_updated, ic_cdk::api::time())?;
+
+    Ok(InterestSummary {
+        current_rate_percent: rate,
+        accrued_interest: account.accrued_interest + pending,
+        total_credited: account.total_credited,
+        last_credited_at: account.last_credited_at,
+    })
+}
+
+pub fn get_interest_config() -> InterestConfig {
+    storage::get_interest_config()
+}
+
+// Replace the interest configuration (controllers only)
+pub fn set_interest_config(config: InterestConfig) -> CanisterResult<InterestConfig> {
+    auth::verify_controller()?;
+
+    let mut config = config;
+    validate_tiers(&config.tiers)?;
+    config.tiers.sort_by(|a, b| a.min_balance.total_cmp(&b.min_balance));
+
+    storage::set_interest_config(config.clone());
+
+    Ok(config)
+}
+
+// Interest earned on `balance` held from `since` to `now`. Each stretch is
+// priced at the tier rate moved by the central bank adjustment then in force.
+fn pending_interest(balance: f64, since: u64, now: u64) -> CanisterResult<f64> {
+    let config = storage::get_interest_config();
+    let tier_rate = rate_for_balance(&config.tiers, balance);
+    if !config.enabled || tier_rate <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let mut interest = 0.0;
+    for (start, end, adjustment) in central_bank::deposit_adjustment_segments(since, now) {
+        let earned = interest_for_period(balance, deposit_rate(tier_rate, adjustment), end - start)?;
+        interest = utils::safe_add(interest, earned)?;
+    }
+
+    Ok(interest)
+}
+
+// Tier rates are quoted at the neutral policy rate and move with it
+fn deposit_rate(tier_rate: f64, adjustment: f64) -> f64 {
+    if tier_rate <= 0.0 {
+        return 0.0;
+    }
+    (tier_rate + adjustment).max(0.0)
+}
+
+// Annual rate for a balance: the highest tier whose minimum it meets
+fn rate_for_balance(tiers: &[InterestTier], balance: f64) -> f64 {
+    tiers
+        .iter()
+        .filter(|tier| balance >= tier.min_balance)
+        .max_by(|a, b| a.min_balance.total_cmp(&b.min_balance))
+        .map(|tier| tier.annual_rate_percent)
+        .unwrap_or(0.0)
+}
+
+// Simple interest over the exact elapsed day count (actual/365)
+fn interest_for_period(balance: f64, annual_rate_percent: f64, elapsed_nanos: u64) -> CanisterResult<f64> {
+    if balance <= 0.0 || annual_rate_percent <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let days = elapsed_nanos as f64 / utils::NANOS_PER_DAY as f64;
+    let daily_rate = annual_rate_percent / 100.0 / DAYS_PER_YEAR;
+    utils::safe_multiply(balance, daily_rate * days)
+}
+
+fn same_month(a: u64, b: u64) -> bool {
+    let (year_a, month_a, _) = utils::days_to_civil((a / utils::NANOS_PER_DAY) as i64);
+    let (year_b, month_b, _) = utils::days_to_civil((b / utils::NANOS_PER_DAY) as i64);
+    year_a == year_b && month_a == month_b
+}
+
+fn validate_tiers(tiers: &[InterestTier]) -> CanisterResult<()> {
+    for (i, tier) in tiers.iter().enumerate() {
+        if tier.min_balance < 0.0 || !tier.min_balance.is_finite() {
+            return Err(CanisterError::InvalidInput("Tier minimum balance must be non-negative".to_string()));
+        }
+        if !(0.0..=100.0).contains(&tier.annual_rate_percent) {
+            return Err(CanisterError::InvalidInput("Tier rate must be between 0 and 100 percent".to_string()));
+        }
+        if tiers[..i].iter().any(|other| other.min_balance == tier.min_balance) {
+            return Err(CanisterError::InvalidInput("Duplicate tier minimum balance".to_string()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_for_balance_tiers() {
+        let tiers = InterestConfig::default().tiers;
+        assert_eq!(rate_for_balance(&tiers, 500.0), 0.0);
+        assert_eq!(rate_for_balance(&tiers, 1_000.0), 3.0);
+        assert_eq!(rate_for_balance(&tiers, 50_000.0), 3.5);
+        assert_eq!(rate_for_balance(&tiers, 250_000.0), 4.0);
+    }
+
+    #[test]
+    fn test_interest_uses_exact_day_count() {
+        let one_year = 365 * utils::NANOS_PER_DAY;
+        let interest = interest_for_period(10_000.0, 3.65, one_year).unwrap();
+        assert!((interest - 365.0).abs() < 1e-9);
+
+        // 36 hours is a day and a half
+        let interest = interest_for_period(10_000.0, 3.65, 36 * 60 * 60 * 1_000_000_000).unwrap();
+        assert!((interest - 1.5).abs() < 1e-9);
+
+        assert_eq!(interest_for_period(0.0, 3.0, one_year).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_deposit_rate_follows_policy_adjustment() {
+        assert_eq!(deposit_rate(3.0, 0.5), 3.5);
+        assert_eq!(deposit_rate(3.0, -4.0), 0.0);
+        assert_eq!(deposit_rate(0.0, 1.0), 0.0); // below the minimum balance tier
+    }
+
+    #[test]
+    fn test_same_month() {
+        let jan_31 = utils::civil_to_days(2024, 1, 31) as u64 * utils::NANOS_PER_DAY;
+        let feb_1 = utils::civil_to_days(2024, 2, 1) as u64 * utils::NANOS_PER_DAY;
+        assert!(same_month(jan_31, jan_31 + 3_600_000_000_000));
+        assert!(!same_month(jan_31, feb_1));
+    }
+
+    #[test]
+    fn test_validate_tiers() {
+        assert!(validate_tiers(&InterestConfig::default().tiers).is_ok());
+        assert!(validate_tiers(&[InterestTier { min_balance: 0.0, annual_rate_percent: 150.0 }]).is_err());
+        assert!(validate_tiers(&[
+            InterestTier { min_balance: 100.0, annual_rate_percent: 1.0 },
+            InterestTier { min_balance: 100.0, annual_rate_percent: 2.0 },
+        ]).is_err());
+    }
+}
@@ -14,6 +14,30 @@ mod http_client;
 mod price_feed;
 mod sse;
 mod stock_sse;
+mod standing_instructions;
+mod savings;
+mod currency;
+mod interest;
+mod central_bank;
+mod oracle_peg;
+mod trading;
+mod orders;
+mod order_book;
+mod portfolio_analytics;
+mod watchlists;
+mod indicators;
+mod candles;
+mod market_data;
+mod market_sim;
+mod corporate_actions;
+mod news;
+mod fundamentals;
+mod instruments;
+mod fx;
+mod universe;
+mod market_calendar;
+mod screener;
+mod indices;
 
 #[cfg(test)]
 mod tests;
@@ -28,6 +52,20 @@ pub use error::*;
 #[ic_cdk::init]
 fn init() {
     storage::init_state();
+    universe::ensure_seeded();
+    indices::ensure_default_indices();
+    currency::ensure_builtin_currencies();
+    central_bank::ensure_rate_history();
+    standing_instructions::start_executor();
+    interest::start_crediting();
+    central_bank::start_policy_engine();
+    orders::start_expiry_sweeper();
+    portfolio_analytics::start_snapshots();
+    watchlists::start_alert_monitor();
+    market_sim::resume();
+    corporate_actions::start_action_processor();
+    fx::start_rate_refresher();
+    indices::start_index_processor();
 }
 
 // Pre-upgrade hook to save state
@@ -40,6 +78,20 @@ fn pre_upgrade() {
 #[ic_cdk::post_upgrade]
 fn post_upgrade() {
     storage::restore_state();
+    universe::ensure_seeded();
+    indices::ensure_default_indices();
+    currency::ensure_builtin_currencies();
+    central_bank::ensure_rate_history();
+    standing_instructions::start_executor();
+    interest::start_crediting();
+    central_bank::start_policy_engine();
+    orders::start_expiry_sweeper();
+    portfolio_analytics::start_snapshots();
+    watchlists::start_alert_monitor();
+    market_sim::resume();
+    corporate_actions::start_action_processor();
+    fx::start_rate_refresher();
+    indices::start_index_processor();
 }
 
 // Authentication Methods
@@ -168,6 +220,611 @@ fn get_transaction_history(wallet_address: String) -> Vec<Web3Transaction> {
     banking::get_transaction_history(wallet_address)
 }
 
+#[ic_cdk::query]
+fn get_transaction_count(wallet_address: String) -> u64 {
+    storage::get_transaction_count(&wallet_address)
+}
+
+// Standing Instruction Methods
+#[ic_cdk::update]
+fn create_standing_instruction(
+    wallet_address: String,
+    action: InstructionAction,
+    recurrence: Recurrence,
+    start_at: u64,
+    end_at: Option<u64>,
+    retry_policy: Option<RetryPolicy>,
+) -> Result<StandingInstruction, String> {
+    standing_instructions::create_standing_instruction(wallet_address, action, recurrence, start_at, end_at, retry_policy)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_standing_instructions(wallet_address: String) -> Vec<StandingInstruction> {
+    standing_instructions::list_standing_instructions(wallet_address)
+}
+
+#[ic_cdk::update]
+fn pause_standing_instruction(wallet_address: String, instruction_id: String) -> Result<StandingInstruction, String> {
+    standing_instructions::pause_standing_instruction(wallet_address, instruction_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn resume_standing_instruction(wallet_address: String, instruction_id: String) -> Result<StandingInstruction, String> {
+    standing_instructions::resume_standing_instruction(wallet_address, instruction_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_standing_instruction(wallet_address: String, instruction_id: String) -> Result<StandingInstruction, String> {
+    standing_instructions::cancel_standing_instruction(wallet_address, instruction_id)
+        .map_err(|e| e.to_string())
+}
+
+// Savings Goal Methods
+#[ic_cdk::update]
+fn create_savings_goal(
+    wallet_address: String,
+    name: String,
+    target_amount: f64,
+    deadline: Option<u64>,
+    auto_sweep_percent: Option<f64>,
+) -> Result<SavingsGoal, String> {
+    savings::create_savings_goal(wallet_address, name, target_amount, deadline, auto_sweep_percent)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_savings_goals(wallet_address: String) -> Vec<SavingsGoal> {
+    savings::get_savings_goals(wallet_address)
+}
+
+#[ic_cdk::query]
+fn get_savings_goal_progress(wallet_address: String, goal_id: String) -> Result<SavingsGoalProgress, String> {
+    savings::get_savings_goal_progress(wallet_address, goal_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn update_savings_goal(wallet_address: String, goal_id: String, update: SavingsGoalUpdate) -> Result<SavingsGoal, String> {
+    savings::update_savings_goal(wallet_address, goal_id, update)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn contribute_to_savings_goal(wallet_address: String, goal_id: String, amount: f64) -> Result<SavingsGoal, String> {
+    savings::contribute_to_savings_goal(wallet_address, goal_id, amount)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn delete_savings_goal(wallet_address: String, goal_id: String) -> Result<f64, String> {
+    savings::delete_savings_goal(wallet_address, goal_id)
+        .map_err(|e| e.to_string())
+}
+
+// Currency Registry Methods
+#[ic_cdk::query]
+fn list_currencies() -> Vec<CurrencyDefinition> {
+    currency::list_currencies()
+}
+
+#[ic_cdk::query]
+fn get_balances(wallet_address: String) -> Result<Vec<AssetBalance>, String> {
+    currency::get_balances(wallet_address)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn register_currency(definition: CurrencyDefinition) -> Result<CurrencyDefinition, String> {
+    currency::register_currency(definition)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn set_currency_exchange_pairs(symbol: String, exchange_pairs: Vec<ExchangePair>) -> Result<CurrencyDefinition, String> {
+    currency::set_exchange_pairs(symbol, exchange_pairs)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn set_currency_enabled(symbol: String, enabled: bool) -> Result<CurrencyDefinition, String> {
+    currency::set_currency_enabled(symbol, enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn mint_currency(wallet_address: String, symbol: String, amount: f64) -> Result<f64, String> {
+    currency::mint_currency(wallet_address, symbol, amount)
+        .map_err(|e| e.to_string())
+}
+
+// Interest Methods
+#[ic_cdk::query]
+fn get_interest_summary(wallet_address: String) -> Result<InterestSummary, String> {
+    interest::get_interest_summary(wallet_address)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_interest_config() -> InterestConfig {
+    interest::get_interest_config()
+}
+
+#[ic_cdk::update]
+fn set_interest_config(config: InterestConfig) -> Result<InterestConfig, String> {
+    interest::set_interest_config(config)
+        .map_err(|e| e.to_string())
+}
+
+// Central Bank Methods
+#[ic_cdk::query]
+fn get_monetary_policy() -> MonetaryPolicy {
+    central_bank::get_monetary_policy()
+}
+
+#[ic_cdk::query]
+fn get_lending_rate() -> f64 {
+    central_bank::lending_rate_percent()
+}
+
+#[ic_cdk::update]
+fn schedule_rate_decision(request: RateDecisionRequest) -> Result<RateDecision, String> {
+    central_bank::schedule_rate_decision(request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_rate_decision(decision_id: String) -> Result<RateDecision, String> {
+    central_bank::cancel_rate_decision(decision_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_rate_decisions() -> Vec<RateDecision> {
+    central_bank::list_rate_decisions()
+}
+
+#[ic_cdk::query]
+fn get_rate_history(start: Option<u64>, end: Option<u64>) -> Vec<RateRecord> {
+    central_bank::get_rate_history(start, end)
+}
+
+#[ic_cdk::query]
+fn get_rate_at(timestamp: u64) -> RateRecord {
+    central_bank::get_rate_at(timestamp)
+}
+
+// Oracle Peg Methods
+#[ic_cdk::query]
+fn get_peg_config() -> PegConfig {
+    oracle_peg::get_peg_config()
+}
+
+#[ic_cdk::update]
+fn set_peg_config(config: PegConfig) -> Result<PegConfig, String> {
+    oracle_peg::set_peg_config(config)
+        .map_err(|e| e.to_string())
+}
+
+// Market Data Provider Methods
+#[ic_cdk::query]
+fn get_market_data_config() -> MarketDataConfig {
+    market_data::get_market_data_config()
+}
+
+#[ic_cdk::update]
+fn set_market_data_config(config: MarketDataConfig) -> Result<MarketDataConfig, String> {
+    market_data::set_market_data_config(config)
+        .map_err(|e| e.to_string())
+}
+
+// Market Simulator Methods
+#[ic_cdk::query]
+fn get_simulator_state() -> SimulatorState {
+    market_sim::get_simulator_state()
+}
+
+#[ic_cdk::update]
+fn configure_simulator(config: SimulatorConfig) -> Result<SimulatorState, String> {
+    market_sim::configure_simulator(config)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn start_simulator() -> Result<(), String> {
+    market_sim::start_simulator()
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn stop_simulator() -> Result<(), String> {
+    market_sim::stop_simulator()
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn preview_simulation(config: SimulatorConfig, ticks: u32) -> Result<Vec<SimTick>, String> {
+    market_sim::preview_simulation(config, ticks)
+        .map_err(|e| e.to_string())
+}
+
+// Corporate Action Methods
+#[ic_cdk::update]
+fn schedule_corporate_action(request: CorporateActionRequest) -> Result<CorporateAction, String> {
+    corporate_actions::schedule_corporate_action(request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_corporate_action(action_id: String) -> Result<CorporateAction, String> {
+    corporate_actions::cancel_corporate_action(action_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_corporate_actions(stock_id: String) -> Result<Vec<CorporateAction>, String> {
+    corporate_actions::get_corporate_actions(stock_id)
+        .map_err(|e| e.to_string())
+}
+
+// News Methods
+#[ic_cdk::update]
+fn ingest_news(items: Vec<NewsItemRequest>) -> Result<Vec<NewsItem>, String> {
+    news::ingest_news(items)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+async fn refresh_news(symbol: String) -> Result<Vec<NewsItem>, String> {
+    news::refresh_news(symbol).await
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_news(symbol: String, since: Option<u64>, limit: Option<u32>) -> Result<Vec<NewsItem>, String> {
+    news::get_news(symbol, since, limit)
+        .map_err(|e| e.to_string())
+}
+
+// Fundamentals Methods
+#[ic_cdk::update]
+async fn refresh_fundamentals(symbol: String) -> Result<Fundamentals, String> {
+    fundamentals::refresh_fundamentals(symbol).await
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_fundamentals(symbol: String) -> Result<Fundamentals, String> {
+    fundamentals::get_fundamentals(symbol)
+        .map_err(|e| e.to_string())
+}
+
+// Instrument Methods
+#[ic_cdk::query]
+fn get_instrument(symbol: String) -> Result<Instrument, String> {
+    instruments::get_instrument(symbol)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_instruments() -> Vec<Instrument> {
+    instruments::list_instruments()
+}
+
+#[ic_cdk::query]
+fn get_stock_quote(symbol: String) -> Result<StockQuote, String> {
+    instruments::get_stock_quote(symbol)
+        .map_err(|e| e.to_string())
+}
+
+// FX Methods
+#[ic_cdk::update]
+fn submit_fx_rate(base: String, quote: String, rate: f64) -> Result<FxRate, String> {
+    fx::submit_fx_rate(base, quote, rate)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+async fn refresh_fx_rate(base: String, quote: String) -> Result<FxRate, String> {
+    fx::refresh_fx_rate(base, quote).await
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_fx_rate(base: String, quote: String) -> Result<FxRate, String> {
+    fx::get_fx_rate(base, quote)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_fx_rates() -> Vec<FxRate> {
+    fx::list_fx_rates()
+}
+
+#[ic_cdk::query]
+fn convert_currency_amount(amount: f64, from: String, to: String) -> Result<FxConversion, String> {
+    fx::convert(amount, from, to)
+        .map_err(|e| e.to_string())
+}
+
+// Stock Universe Methods
+#[ic_cdk::update]
+fn add_universe_stock(request: UniverseStockRequest) -> Result<UniverseStock, String> {
+    universe::add_universe_stock(request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn remove_universe_stock(symbol: String) -> Result<UniverseStock, String> {
+    universe::remove_universe_stock(symbol)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn set_universe_stock_enabled(symbol: String, enabled: bool) -> Result<UniverseStock, String> {
+    universe::set_universe_stock_enabled(symbol, enabled)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_universe_stocks() -> Vec<UniverseStock> {
+    universe::list_universe_stocks()
+}
+
+// Market Calendar Methods
+#[ic_cdk::query]
+fn is_market_open(exchange: Exchange) -> bool {
+    market_calendar::is_market_open(exchange)
+}
+
+#[ic_cdk::query]
+fn get_market_status(exchange: Exchange) -> Result<MarketStatus, String> {
+    market_calendar::get_market_status(exchange)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn next_market_open(exchange: Exchange) -> Result<u64, String> {
+    market_calendar::next_market_open(exchange)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn next_market_close(exchange: Exchange) -> Result<u64, String> {
+    market_calendar::next_market_close(exchange)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn add_market_holiday(holiday: MarketHoliday) -> Result<MarketHoliday, String> {
+    market_calendar::add_market_holiday(holiday)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn remove_market_holiday(exchange: Exchange, date: String) -> Result<MarketHoliday, String> {
+    market_calendar::remove_market_holiday(exchange, date)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_market_holidays(exchange: Exchange) -> Vec<MarketHoliday> {
+    market_calendar::list_market_holidays(exchange)
+}
+
+// Screener Methods
+#[ic_cdk::query]
+fn screen_stocks(query: ScreenerQuery) -> Result<ScreenerPage, String> {
+    screener::screen_stocks(query)
+        .map_err(|e| e.to_string())
+}
+
+// Index Methods
+#[ic_cdk::update]
+fn create_index(request: IndexDefinitionRequest) -> Result<IndexDefinition, String> {
+    indices::create_index(request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn rebalance_index(index_id: String) -> Result<IndexDefinition, String> {
+    indices::rebalance_index(index_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_index(index_id: String) -> Result<IndexDefinition, String> {
+    indices::get_index(index_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_indices() -> Vec<IndexDefinition> {
+    indices::list_indices()
+}
+
+#[ic_cdk::query]
+fn get_index_value(index_id: String) -> Result<IndexValue, String> {
+    indices::get_index_value(index_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn list_index_values() -> Vec<IndexValue> {
+    indices::list_index_values()
+}
+
+#[ic_cdk::update]
+fn buy_index(wallet_address: String, index_id: String, units: u64) -> Result<StockTrade, String> {
+    indices::buy_index(wallet_address, index_id, units)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn sell_index(wallet_address: String, index_id: String, units: u64) -> Result<StockTrade, String> {
+    indices::sell_index(wallet_address, index_id, units)
+        .map_err(|e| e.to_string())
+}
+
+// Paper Trading Methods
+#[ic_cdk::update]
+fn buy_stock(wallet_address: String, stock_id: String, quantity: u64) -> Result<StockTrade, String> {
+    trading::buy_stock(wallet_address, stock_id, quantity)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn sell_stock(wallet_address: String, stock_id: String, quantity: u64) -> Result<StockTrade, String> {
+    trading::sell_stock(wallet_address, stock_id, quantity)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_portfolio(wallet_address: String) -> Result<Portfolio, String> {
+    trading::get_portfolio(wallet_address)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_stock_trades(wallet_address: String) -> Vec<StockTrade> {
+    trading::get_stock_trades(wallet_address)
+}
+
+// Stock Order Methods
+#[ic_cdk::update]
+fn place_stock_order(wallet_address: String, request: StockOrderRequest) -> Result<StockOrder, String> {
+    orders::place_stock_order(wallet_address, request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_stock_order(wallet_address: String, order_id: String) -> Result<StockOrder, String> {
+    orders::cancel_stock_order(wallet_address, order_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_stock_orders(wallet_address: String) -> Vec<StockOrder> {
+    orders::get_stock_orders(wallet_address)
+}
+
+// Portfolio Analytics Methods
+#[ic_cdk::query]
+fn get_portfolio_history(wallet_address: String, start: Option<u64>, end: Option<u64>) -> Vec<PortfolioSnapshot> {
+    portfolio_analytics::get_portfolio_history(wallet_address, start, end)
+}
+
+#[ic_cdk::query]
+fn get_portfolio_performance(wallet_address: String, start: Option<u64>, end: Option<u64>) -> Result<PortfolioPerformance, String> {
+    portfolio_analytics::get_portfolio_performance(wallet_address, start, end)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_sector_allocation(wallet_address: String) -> Result<Vec<SectorAllocation>, String> {
+    portfolio_analytics::get_sector_allocation(wallet_address)
+        .map_err(|e| e.to_string())
+}
+
+// Watchlist and Alert Methods
+#[ic_cdk::update]
+fn create_watchlist(wallet_address: String, name: String, items: Vec<WatchlistItem>) -> Result<Watchlist, String> {
+    watchlists::create_watchlist(wallet_address, name, items)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_watchlists(wallet_address: String) -> Vec<Watchlist> {
+    watchlists::get_watchlists(wallet_address)
+}
+
+#[ic_cdk::update]
+fn add_to_watchlist(wallet_address: String, watchlist_id: String, item: WatchlistItem) -> Result<Watchlist, String> {
+    watchlists::add_to_watchlist(wallet_address, watchlist_id, item)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn remove_from_watchlist(wallet_address: String, watchlist_id: String, symbol: String) -> Result<Watchlist, String> {
+    watchlists::remove_from_watchlist(wallet_address, watchlist_id, symbol)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn delete_watchlist(wallet_address: String, watchlist_id: String) -> Result<(), String> {
+    watchlists::delete_watchlist(wallet_address, watchlist_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn create_price_alert(wallet_address: String, request: PriceAlertRequest) -> Result<PriceAlert, String> {
+    watchlists::create_price_alert(wallet_address, request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_price_alert(wallet_address: String, alert_id: String) -> Result<PriceAlert, String> {
+    watchlists::cancel_price_alert(wallet_address, alert_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_price_alerts(wallet_address: String) -> Vec<PriceAlert> {
+    watchlists::get_price_alerts(wallet_address)
+}
+
+// Technical Indicator Methods
+#[ic_cdk::query]
+fn get_stock_indicators(symbol: String, window: u32) -> Result<IndicatorSeries, String> {
+    indicators::get_stock_indicators(symbol, window)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+async fn get_crypto_indicators(coin_id: String, vs_currency: String, days: u32, window: u32) -> Result<IndicatorSeries, String> {
+    indicators::get_crypto_indicators(coin_id, vs_currency, days, window).await
+        .map_err(|e| e.to_string())
+}
+
+// Candle Methods
+#[ic_cdk::query]
+fn get_candles(symbol: String, resolution: CandleResolution, from: Option<u64>, to: Option<u64>) -> Result<Vec<Candle>, String> {
+    candles::get_candles(symbol, resolution, from, to)
+        .map_err(|e| e.to_string())
+}
+
+// Order Book Methods
+#[ic_cdk::update]
+fn place_book_order(wallet_address: String, request: BookOrderRequest) -> Result<BookOrderResult, String> {
+    order_book::place_book_order(wallet_address, request)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::update]
+fn cancel_book_order(wallet_address: String, order_id: String) -> Result<BookOrder, String> {
+    order_book::cancel_book_order(wallet_address, order_id)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_book_orders(wallet_address: String) -> Vec<BookOrder> {
+    order_book::get_book_orders(wallet_address)
+}
+
+#[ic_cdk::query]
+fn get_order_book(base: String, quote: String, depth: Option<u32>) -> Result<OrderBookSnapshot, String> {
+    order_book::get_order_book(base, quote, depth)
+        .map_err(|e| e.to_string())
+}
+
+#[ic_cdk::query]
+fn get_book_trades(base: String, quote: String, limit: Option<u32>) -> Result<Vec<BookTrade>, String> {
+    order_book::get_book_trades(base, quote, limit)
+        .map_err(|e| e.to_string())
+}
+
 // Monitoring and Performance Methods
 #[ic_cdk::query]
 fn get_canister_metrics() -> monitoring::CanisterMetrics {
@@ -358,18 +1015,18 @@ fn get_current_time_formatted() -> String {
 // Future HTTP price fetching (now with real implementation)
 #[ic_cdk::update]
 async fn fetch_external_price(symbol: String) -> Result<Option<f64>, String> {
-    let prices = http_client::fetch_price(&symbol).await?;
+    let prices = market_data::crypto_prices(&symbol).await?;
     Ok(prices.into_iter().find(|(s, _)| s == &symbol).map(|(_, p)| p))
 }
 
 #[ic_cdk::update]
 async fn fetch_multiple_crypto_prices(token_ids: String) -> Result<Vec<(String, f64)>, String> {
-    http_client::fetch_price(&token_ids).await
+    market_data::crypto_prices(&token_ids).await
 }
 
 #[ic_cdk::update]
 async fn fetch_stock_price(symbol: String) -> Result<Option<f64>, String> {
-    let prices = http_client::fetch_stock_prices(&symbol).await?;
+    let prices = market_data::stock_quotes(&symbol).await?;
     Ok(prices.into_iter().find(|(s, _)| s == &symbol).map(|(_, p)| p))
 }
 
@@ -378,13 +1035,13 @@ async fn fetch_stock_price(symbol: String) -> Result<Option<f64>, String> {
 /// Returns Vec<(symbol, price)>
 #[ic_cdk::update]
 async fn fetch_multiple_stock_prices(symbols: String) -> Result<Vec<(String, f64)>, String> {
-    http_client::fetch_stock_prices(&symbols).await
+    market_data::stock_quotes(&symbols).await
 }
 
 // CoinGecko API endpoints for historical data
 #[ic_cdk::update]
 async fn fetch_coin_market_chart_range(coin_id: String, vs_currency: String, from: u64, to: u64) -> Result<Vec<(String, Vec<(u64, f64)>)>, String> {
-    match http_client::fetch_coin_market_chart_range(&coin_id, &vs_currency, from, to).await {
+    match market_data::crypto_market_chart_range(&coin_id, &vs_currency, from, to).await {
         Ok(data) => {
             let mut result = Vec::new();
             for (key, values) in data {
@@ -398,7 +1055,7 @@ async fn fetch_coin_market_chart_range(coin_id: String, vs_currency: String, fro
 
 #[ic_cdk::update]
 async fn fetch_coin_ohlc(coin_id: String, vs_currency: String, days: u32) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
-    http_client::fetch_coin_ohlc(&coin_id, &vs_currency, days).await
+    market_data::crypto_ohlc(&coin_id, &vs_currency, days).await
 }
 
 #[ic_cdk::update]
@@ -424,7 +1081,7 @@ async fn fetch_coin_history(coin_id: String, days: String) -> Result<Vec<(String
 
     ic_cdk::println!("Fetching {} days of price history for {}", days_num, coin_id);
 
-    match http_client::fetch_coin_market_chart(&coin_id, "usd", days_num).await {
+    match market_data::crypto_market_chart(&coin_id, "usd", days_num).await {
         Ok(data) => {
             // Extract just the prices and convert timestamps to date strings
             if let Some(prices) = data.get("prices") {
@@ -478,7 +1135,7 @@ fn format_timestamp_to_date(timestamp: u64) -> String {
 async fn update_prices_from_external() -> Result<usize, String> {
     // Fetch major crypto prices
     let crypto_ids = "bitcoin,ethereum,internet-computer,chainlink,uniswap";
-    let crypto_prices = http_client::fetch_price(crypto_ids).await?;
+    let crypto_prices = market_data::crypto_prices(crypto_ids).await?;
     
     let mut updated_count = 0;
     for (token_id, price) in crypto_prices {
@@ -493,6 +1150,7 @@ async fn update_prices_from_external() -> Result<usize, String> {
         };
         
         storage::set_price_feed(symbol, price);
+        candles::record_price(symbol, price, 0.0, ic_cdk::api::time());
         updated_count += 1;
     }
     
@@ -553,7 +1211,12 @@ fn http_request(req: ic_http_certification::HttpRequest) -> HttpResponse {
         .query_pairs()
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect();
-    
+
+    // JSON candles: /candles?symbol=BTC&resolution=5m&from=<ns>&to=<ns>
+    if path == "/candles" {
+        return handle_candles_request(&query_params);
+    }
+
     // Check if this is an SSE endpoint
     if path.starts_with("/sse/rooms/") && path.ends_with("/subscribe") {
         // Extract room ID from path
@@ -609,6 +1272,45 @@ fn http_request(req: ic_http_certification::HttpRequest) -> HttpResponse {
     }
 }
 
+fn handle_candles_request(query_params: &HashMap<String, String>) -> HttpResponse {
+    use ic_cdk::api::management_canister::http_request::HttpHeader;
+
+    let text_response = |status: u16, message: String| HttpResponse {
+        status: status.into(),
+        headers: vec![HttpHeader { name: "Content-Type".to_string(), value: "text/plain".to_string() }],
+        body: message.into_bytes(),
+    };
+
+    let Some(symbol) = query_params.get("symbol") else {
+        return text_response(400, "Missing symbol".to_string());
+    };
+    let resolution = match candles::parse_resolution(query_params.get("resolution").map(String::as_str).unwrap_or("1m")) {
+        Ok(resolution) => resolution,
+        Err(e) => return text_response(400, e.to_string()),
+    };
+    let mut bounds = [None, None];
+    for (bound, name) in bounds.iter_mut().zip(["from", "to"]) {
+        if let Some(value) = query_params.get(name) {
+            match value.parse::<u64>() {
+                Ok(value) => *bound = Some(value),
+                Err(_) => return text_response(400, format!("Invalid {}", name)),
+            }
+        }
+    }
+
+    match candles::get_candles(symbol.clone(), resolution, bounds[0], bounds[1]) {
+        Ok(candles) => match serde_json::to_string(&candles) {
+            Ok(body_str) => HttpResponse {
+                status: 200u16.into(),
+                headers: vec![HttpHeader { name: "Content-Type".to_string(), value: "application/json".to_string() }],
+                body: body_str.into_bytes(),
+            },
+            Err(_) => text_response(500, "Failed to serialize candles".to_string()),
+        },
+        Err(e) => text_response(400, e.to_string()),
+    }
+}
+
 fn handle_sse_subscription(
     req: ic_http_certification::HttpRequest,
     room_id: &str,
@@ -1012,8 +1714,8 @@ async fn broadcast_stock_update(stock_id: String) -> Result<usize, String> {
 }
 
 #[ic_cdk::update]
-async fn broadcast_stock_news(stock_id: String, news: Vec<String>) -> Result<usize, String> {
-    stock_sse::broadcast_stock_news(&stock_id, &news)
+async fn broadcast_stock_news(stock_id: String) -> Result<usize, String> {
+    news::broadcast_latest(stock_id)
         .map_err(|e| format!("Failed to broadcast news: {:?}", e))
 }
 
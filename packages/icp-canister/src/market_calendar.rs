@@ -0,0 +1,351 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::instruments;
+use crate::utils::{self, NANOS_PER_DAY};
+
+const NANOS_PER_MINUTE: u64 = 60 * 1_000_000_000;
+// Longest run of closed days searched for the next session (Diwali weeks, long weekends)
+const MAX_SEARCH_DAYS: i64 = 30;
+const MAX_HOLIDAY_NAME_LEN: usize = 100;
+
+const ET_DAYLIGHT_OFFSET_MINUTES: i16 = -240;
+// NSE and BSE collect pre-open orders for the 15 minutes before the open
+const INDIAN_PRE_OPEN_MINUTES: u16 = 15;
+// US exchanges take pre-market orders from 04:00 ET
+const US_PRE_MARKET_MINUTE: u16 = 4 * 60;
+
+struct Session {
+    pre_open_minute: u16,
+    open_minute: u16,
+    close_minute: u16,
+}
+
+// Where an exchange's trading day stands at `now`
+pub fn phase(exchange: Exchange, now: u64) -> MarketPhase {
+    phase_at(exchange, now, &stored_holiday(exchange))
+}
+
+pub fn is_open(exchange: Exchange, now: u64) -> bool {
+    phase(exchange, now) == MarketPhase::Open
+}
+
+// Whether trades in a stock execute now. Mock and simulated markets trade
+// around the clock; only live exchange data follows the calendar.
+pub fn is_trading(symbol: &str, now: u64) -> bool {
+    if storage::get_market_data_config().stock_source != MarketDataSource::Polygon {
+        return true;
+    }
+    is_open(instruments::lookup(symbol).exchange, now)
+}
+
+pub fn ensure_trading(symbol: &str, now: u64) -> CanisterResult<()> {
+    if is_trading(symbol, now) {
+        return Ok(());
+    }
+
+    let exchange = instruments::lookup(symbol).exchange;
+    let reopens = next_open(exchange, now)
+        .map(|at| format!("; reopens {}", format_local(exchange, at)))
+        .unwrap_or_default();
+    Err(CanisterError::MarketClosed(format!("{} trades on {:?}{}", symbol, exchange, reopens)))
+}
+
+// Whether a quote last fetched at `fetched_at` may have moved since: the
+// exchange was open at either end or a session started in between
+pub fn needs_refresh(symbol: &str, fetched_at: u64, now: u64) -> bool {
+    if storage::get_market_data_config().stock_source != MarketDataSource::Polygon {
+        return true;
+    }
+    let exchange = instruments::lookup(symbol).exchange;
+    is_open(exchange, fetched_at)
+        || is_open(exchange, now)
+        || next_open(exchange, fetched_at).is_some_and(|at| at <= now)
+}
+
+pub fn next_open(exchange: Exchange, now: u64) -> Option<u64> {
+    next_session_time(exchange, now, &stored_holiday(exchange), false)
+}
+
+pub fn next_close(exchange: Exchange, now: u64) -> Option<u64> {
+    next_session_time(exchange, now, &stored_holiday(exchange), true)
+}
+
+pub fn is_market_open(exchange: Exchange) -> bool {
+    is_open(exchange, ic_cdk::api::time())
+}
+
+pub fn next_market_open(exchange: Exchange) -> CanisterResult<u64> {
+    next_open(exchange, ic_cdk::api::time()).ok_or_else(|| no_session(exchange))
+}
+
+pub fn next_market_close(exchange: Exchange) -> CanisterResult<u64> {
+    next_close(exchange, ic_cdk::api::time()).ok_or_else(|| no_session(exchange))
+}
+
+pub fn get_market_status(exchange: Exchange) -> CanisterResult<MarketStatus> {
+    let now = ic_cdk::api::time();
+    let phase = phase(exchange, now);
+    let (day, _) = local_time(exchange, now);
+
+    Ok(MarketStatus {
+        exchange,
+        phase,
+        is_open: phase == MarketPhase::Open,
+        time_zone: time_zone(exchange).to_string(),
+        utc_offset_minutes: utc_offset_minutes(exchange, now),
+        local_date: utils::format_civil_date(day),
+        holiday: stored_holiday(exchange)(day).map(|holiday| holiday.name),
+        next_open: next_market_open(exchange)?,
+        next_close: next_market_close(exchange)?,
+    })
+}
+
+// Add or replace a holiday or half-day (controllers only)
+pub fn add_market_holiday(holiday: MarketHoliday) -> CanisterResult<MarketHoliday> {
+    auth::verify_controller()?;
+    let holiday = validate_holiday(holiday)?;
+    storage::set_market_holiday(&holiday);
+    Ok(holiday)
+}
+
+pub fn remove_market_holiday(exchange: Exchange, date: String) -> CanisterResult<MarketHoliday> {
+    auth::verify_controller()?;
+    let date = normalize_date(&date)?;
+    storage::remove_market_holiday(exchange, &date)
+        .ok_or_else(|| CanisterError::NotFound(format!("No {:?} holiday on {}", exchange, date)))
+}
+
+pub fn list_market_holidays(exchange: Exchange) -> Vec<MarketHoliday> {
+    storage::get_market_holidays(exchange)
+}
+
+fn stored_holiday(exchange: Exchange) -> impl Fn(i64) -> Option<MarketHoliday> {
+    move |day| storage::get_market_holiday(exchange, &utils::format_civil_date(day))
+}
+
+fn no_session(exchange: Exchange) -> CanisterError {
+    CanisterError::NotFound(format!("No {:?} session in the next {} days", exchange, MAX_SEARCH_DAYS))
+}
+
+fn session(exchange: Exchange) -> Session {
+    let hours = instruments::trading_hours(exchange);
+    let pre_open_minute = if instruments::is_indian(exchange) {
+        hours.open_minute - INDIAN_PRE_OPEN_MINUTES
+    } else {
+        US_PRE_MARKET_MINUTE
+    };
+
+    Session {
+        pre_open_minute,
+        open_minute: hours.open_minute,
+        close_minute: hours.close_minute,
+    }
+}
+
+fn time_zone(exchange: Exchange) -> &'static str {
+    if instruments::is_indian(exchange) { "IST" } else { "ET" }
+}
+
+// IST has no daylight saving; ET moves an hour forward in summer
+fn utc_offset_minutes(exchange: Exchange, timestamp: u64) -> i16 {
+    if !instruments::is_indian(exchange) && is_us_daylight_time(timestamp) {
+        return ET_DAYLIGHT_OFFSET_MINUTES;
+    }
+    instruments::trading_hours(exchange).utc_offset_minutes
+}
+
+// US daylight saving runs from 02:00 local on the second Sunday of March to
+// 02:00 local on the first Sunday of November
+fn is_us_daylight_time(timestamp: u64) -> bool {
+    let (year, _, _) = utils::days_to_civil((timestamp / NANOS_PER_DAY) as i64);
+    let starts = nth_sunday(year, 3, 2) as u64 * NANOS_PER_DAY + 7 * 60 * NANOS_PER_MINUTE;
+    let ends = nth_sunday(year, 11, 1) as u64 * NANOS_PER_DAY + 6 * 60 * NANOS_PER_MINUTE;
+    timestamp >= starts && timestamp < ends
+}
+
+// 0 = Sunday; 1970-01-01 was a Thursday
+fn weekday(day: i64) -> i64 {
+    (day + 4).rem_euclid(7)
+}
+
+fn nth_sunday(year: i64, month: u32, n: i64) -> i64 {
+    let first = utils::civil_to_days(year, month, 1);
+    first + (7 - weekday(first)) % 7 + 7 * (n - 1)
+}
+
+// Local day number and minute of the day at an exchange
+fn local_time(exchange: Exchange, timestamp: u64) -> (i64, u16) {
+    let offset = utc_offset_minutes(exchange, timestamp) as i64 * NANOS_PER_MINUTE as i64;
+    let local = timestamp as i64 + offset;
+    let day = local.div_euclid(NANOS_PER_DAY as i64);
+    let minute = local.rem_euclid(NANOS_PER_DAY as i64) / NANOS_PER_MINUTE as i64;
+    (day, minute as u16)
+}
+
+// UTC timestamp of a local minute. The offset is taken at midday, clear of
+// the early-Sunday daylight saving switch.
+fn local_to_utc(exchange: Exchange, day: i64, minute: u16) -> u64 {
+    let midday = day as u64 * NANOS_PER_DAY + NANOS_PER_DAY / 2;
+    let offset = utc_offset_minutes(exchange, midday) as i64;
+    (day * NANOS_PER_DAY as i64 + (minute as i64 - offset) * NANOS_PER_MINUTE as i64) as u64
+}
+
+// The day's session, cut short on a half-day, or None on weekends and holidays
+fn day_session(exchange: Exchange, day: i64, holiday: Option<&MarketHoliday>) -> Option<Session> {
+    if matches!(weekday(day), 0 | 6) {
+        return None;
+    }
+
+    let mut session = session(exchange);
+    if let Some(holiday) = holiday {
+        session.close_minute = session.close_minute.min(holiday.early_close_minute?);
+    }
+    Some(session)
+}
+
+fn phase_at(exchange: Exchange, now: u64, holiday_on: &impl Fn(i64) -> Option<MarketHoliday>) -> MarketPhase {
+    let (day, minute) = local_time(exchange, now);
+    match day_session(exchange, day, holiday_on(day).as_ref()) {
+        Some(session) if (session.open_minute..session.close_minute).contains(&minute) => MarketPhase::Open,
+        Some(session) if (session.pre_open_minute..session.open_minute).contains(&minute) => MarketPhase::PreOpen,
+        _ => MarketPhase::Closed,
+    }
+}
+
+// First open (or close) strictly after `now`
+fn next_session_time(
+    exchange: Exchange,
+    now: u64,
+    holiday_on: &impl Fn(i64) -> Option<MarketHoliday>,
+    close: bool,
+) -> Option<u64> {
+    let (today, _) = local_time(exchange, now);
+    (today..=today + MAX_SEARCH_DAYS).find_map(|day| {
+        let session = day_session(exchange, day, holiday_on(day).as_ref())?;
+        let minute = if close { session.close_minute } else { session.open_minute };
+        let at = local_to_utc(exchange, day, minute);
+        (at > now).then_some(at)
+    })
+}
+
+// "2026-03-09 09:30 ET"
+fn format_local(exchange: Exchange, timestamp: u64) -> String {
+    let (day, minute) = local_time(exchange, timestamp);
+    format!("{} {:02}:{:02} {}", utils::format_civil_date(day), minute / 60, minute % 60, time_zone(exchange))
+}
+
+fn normalize_date(date: &str) -> CanisterResult<String> {
+    utils::parse_civil_date(date)
+        .map(utils::format_civil_date)
+        .ok_or_else(|| CanisterError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", date.trim())))
+}
+
+fn validate_holiday(mut holiday: MarketHoliday) -> CanisterResult<MarketHoliday> {
+    holiday.date = normalize_date(&holiday.date)?;
+    holiday.name = holiday.name.trim().to_string();
+
+    if holiday.name.is_empty() || holiday.name.chars().count() > MAX_HOLIDAY_NAME_LEN {
+        return Err(CanisterError::InvalidInput(format!("Holiday name must be 1-{} characters", MAX_HOLIDAY_NAME_LEN)));
+    }
+    if let Some(close) = holiday.early_close_minute {
+        let session = session(holiday.exchange);
+        if close <= session.open_minute || close >= session.close_minute {
+            return Err(CanisterError::InvalidInput("Early close must fall inside the regular session".to_string()));
+        }
+    }
+    Ok(holiday)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, hour: u64, minute: u64) -> u64 {
+        utils::parse_civil_date(date).unwrap() as u64 * NANOS_PER_DAY + (hour * 60 + minute) * NANOS_PER_MINUTE
+    }
+
+    fn no_holidays(_: i64) -> Option<MarketHoliday> {
+        None
+    }
+
+    fn holiday(exchange: Exchange, date: &str, early_close_minute: Option<u16>) -> impl Fn(i64) -> Option<MarketHoliday> {
+        let holiday = MarketHoliday {
+            exchange,
+            date: date.to_string(),
+            name: "Holiday".to_string(),
+            early_close_minute,
+        };
+        let day = utils::parse_civil_date(date).unwrap();
+        move |d| (d == day).then(|| holiday.clone())
+    }
+
+    #[test]
+    fn test_us_daylight_time() {
+        // 2026: second Sunday of March is the 8th, first Sunday of November the 1st
+        assert!(!is_us_daylight_time(at("2026-03-08", 6, 59)));
+        assert!(is_us_daylight_time(at("2026-03-08", 7, 0)));
+        assert!(is_us_daylight_time(at("2026-10-31", 12, 0)));
+        assert!(!is_us_daylight_time(at("2026-11-01", 6, 0)));
+    }
+
+    #[test]
+    fn test_phase_at() {
+        // Monday 2026-03-09, the first trading day of US daylight time: the open is 13:30 UTC
+        assert_eq!(phase_at(Exchange::NYSE, at("2026-03-09", 13, 29), &no_holidays), MarketPhase::PreOpen);
+        assert_eq!(phase_at(Exchange::NYSE, at("2026-03-09", 13, 30), &no_holidays), MarketPhase::Open);
+        assert_eq!(phase_at(Exchange::NYSE, at("2026-03-09", 20, 0), &no_holidays), MarketPhase::Closed);
+
+        // NSE 09:15-15:30 IST is 03:45-10:00 UTC
+        assert_eq!(phase_at(Exchange::NSE, at("2026-03-09", 3, 35), &no_holidays), MarketPhase::PreOpen);
+        assert_eq!(phase_at(Exchange::NSE, at("2026-03-09", 9, 59), &no_holidays), MarketPhase::Open);
+        assert_eq!(phase_at(Exchange::NSE, at("2026-03-07", 5, 0), &no_holidays), MarketPhase::Closed);
+
+        let republic_day = holiday(Exchange::NSE, "2026-01-26", None);
+        assert_eq!(phase_at(Exchange::NSE, at("2026-01-26", 5, 0), &republic_day), MarketPhase::Closed);
+
+        // Half-day closing at 13:00 ET (18:00 UTC in November)
+        let day_after_thanksgiving = holiday(Exchange::NYSE, "2026-11-27", Some(13 * 60));
+        assert_eq!(phase_at(Exchange::NYSE, at("2026-11-27", 17, 59), &day_after_thanksgiving), MarketPhase::Open);
+        assert_eq!(phase_at(Exchange::NYSE, at("2026-11-27", 18, 0), &day_after_thanksgiving), MarketPhase::Closed);
+    }
+
+    #[test]
+    fn test_next_session_time() {
+        // Friday after the close: next open is Monday 09:15 IST (03:45 UTC)
+        let friday_evening = at("2026-03-06", 12, 0);
+        assert_eq!(next_session_time(Exchange::NSE, friday_evening, &no_holidays, false), Some(at("2026-03-09", 3, 45)));
+        assert_eq!(next_session_time(Exchange::NSE, friday_evening, &no_holidays, true), Some(at("2026-03-09", 10, 0)));
+
+        // A Monday holiday pushes the open to Tuesday
+        let monday_off = holiday(Exchange::NSE, "2026-03-09", None);
+        assert_eq!(next_session_time(Exchange::NSE, friday_evening, &monday_off, false), Some(at("2026-03-10", 3, 45)));
+
+        // While open, the next close is today's
+        let mid_session = at("2026-03-09", 15, 0);
+        assert_eq!(next_session_time(Exchange::NASDAQ, mid_session, &no_holidays, true), Some(at("2026-03-09", 20, 0)));
+        assert_eq!(next_session_time(Exchange::NASDAQ, mid_session, &no_holidays, false), Some(at("2026-03-10", 13, 30)));
+    }
+
+    #[test]
+    fn test_validate_holiday() {
+        let half_day = MarketHoliday {
+            exchange: Exchange::NYSE,
+            date: " 2026-11-27 ".to_string(),
+            name: " Day after Thanksgiving ".to_string(),
+            early_close_minute: Some(13 * 60),
+        };
+        let validated = validate_holiday(half_day.clone()).unwrap();
+        assert_eq!(validated.date, "2026-11-27");
+        assert_eq!(validated.name, "Day after Thanksgiving");
+
+        let mut after_close = half_day.clone();
+        after_close.early_close_minute = Some(17 * 60);
+        assert!(validate_holiday(after_close).is_err());
+
+        let mut bad_date = half_day;
+        bad_date.date = "2026-02-30".to_string();
+        assert!(validate_holiday(bad_date).is_err());
+    }
+}
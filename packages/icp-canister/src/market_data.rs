@@ -0,0 +1,665 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::market_sim;
+use crate::instruments;
+use crate::http_client::{self, StockDetails};
+use crate::utils::{NANOS_PER_DAY, NANOS_PER_SECOND};
+use std::collections::HashMap;
+
+// Mock closes stay within ±MOCK_DAILY_SWING of the reference price
+const MOCK_DAILY_SWING: f64 = 0.08;
+const MOCK_INTRADAY_RANGE: f64 = 0.02;
+const MOCK_CRYPTO_SUPPLY: f64 = 20_000_000.0;
+const NANOS_PER_MILLI: u64 = 1_000_000;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// A source of quotes, daily history, company details, financial statements,
+// crypto prices and charts, exchange rates and news.
+// Sources that do not cover an asset class keep the default, which errors.
+pub trait MarketDataProvider {
+    fn name(&self) -> &'static str;
+
+    async fn stock_quotes(&self, _symbols: &[String], _now: u64) -> Result<Vec<(String, f64)>, String> {
+        Err(format!("{} does not provide stock quotes", self.name()))
+    }
+
+    async fn stock_history(&self, _symbol: &str, _days: u32, _now: u64) -> Result<Vec<StockPrice>, String> {
+        Err(format!("{} does not provide stock history", self.name()))
+    }
+
+    async fn stock_details(&self, _symbol: &str) -> Result<StockDetails, String> {
+        Err(format!("{} does not provide stock details", self.name()))
+    }
+
+    async fn crypto_prices(&self, _coin_ids: &[String], _now: u64) -> Result<Vec<(String, f64)>, String> {
+        Err(format!("{} does not provide crypto prices", self.name()))
+    }
+
+    // Candles as (timestamp ms, open, high, low, close)
+    async fn crypto_ohlc(&self, _coin_id: &str, _vs_currency: &str, _days: u32, _now: u64) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
+        Err(format!("{} does not provide crypto charts", self.name()))
+    }
+
+    // "prices", "market_caps" and "total_volumes" series as (timestamp ms, value)
+    async fn crypto_market_chart(&self, _coin_id: &str, _vs_currency: &str, _days: u32, _now: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        Err(format!("{} does not provide crypto charts", self.name()))
+    }
+
+    // As `crypto_market_chart`, between `from` and `to` in seconds
+    async fn crypto_market_chart_range(&self, _coin_id: &str, _vs_currency: &str, _from: u64, _to: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        Err(format!("{} does not provide crypto charts", self.name()))
+    }
+
+    async fn stock_news(&self, _symbol: &str, _limit: u32) -> Result<Vec<NewsItemRequest>, String> {
+        Err(format!("{} does not provide news", self.name()))
+    }
+
+    async fn stock_fundamentals(&self, _symbol: &str, _now: u64) -> Result<Fundamentals, String> {
+        Err(format!("{} does not provide financial statements", self.name()))
+    }
+
+    // Units of `quote` per unit of `base`
+    async fn fx_rate(&self, _base: &str, _quote: &str, _now: u64) -> Result<f64, String> {
+        Err(format!("{} does not provide exchange rates", self.name()))
+    }
+}
+
+pub struct PolygonProvider;
+
+impl MarketDataProvider for PolygonProvider {
+    fn name(&self) -> &'static str {
+        "Polygon"
+    }
+
+    async fn stock_quotes(&self, symbols: &[String], _now: u64) -> Result<Vec<(String, f64)>, String> {
+        http_client::fetch_stock_prices(&symbols.join(",")).await
+    }
+
+    async fn stock_history(&self, symbol: &str, days: u32, now: u64) -> Result<Vec<StockPrice>, String> {
+        http_client::fetch_polygon_historical(symbol, days, now).await
+    }
+
+    async fn stock_details(&self, symbol: &str) -> Result<StockDetails, String> {
+        http_client::fetch_polygon_stock_details(symbol).await
+    }
+
+    async fn stock_news(&self, symbol: &str, limit: u32) -> Result<Vec<NewsItemRequest>, String> {
+        http_client::fetch_polygon_news(symbol, limit).await
+    }
+
+    async fn stock_fundamentals(&self, symbol: &str, now: u64) -> Result<Fundamentals, String> {
+        http_client::fetch_polygon_financials(symbol, now).await
+    }
+
+    async fn fx_rate(&self, base: &str, quote: &str, _now: u64) -> Result<f64, String> {
+        http_client::fetch_polygon_fx_rate(base, quote).await
+    }
+}
+
+// NSE/BSE listings, which Polygon does not cover. Quotes are rejected if the
+// feed reports a currency other than the instrument's.
+pub struct IndianExchangeProvider;
+
+impl IndianExchangeProvider {
+    async fn chart(&self, symbol: &str, days: u32) -> Result<http_client::ExchangeChart, String> {
+        let instrument = instruments::lookup(&symbol.trim().to_uppercase());
+        if !instruments::is_indian(instrument.exchange) {
+            return Err(format!("{} is not listed on NSE or BSE", symbol));
+        }
+
+        let chart = http_client::fetch_exchange_chart(&instruments::feed_symbol(&instrument), days).await?;
+        if chart.currency != instrument.currency {
+            return Err(format!("{} quoted in {}, expected {}", symbol, chart.currency, instrument.currency));
+        }
+        Ok(chart)
+    }
+}
+
+impl MarketDataProvider for IndianExchangeProvider {
+    fn name(&self) -> &'static str {
+        "NSE/BSE"
+    }
+
+    async fn stock_quotes(&self, symbols: &[String], _now: u64) -> Result<Vec<(String, f64)>, String> {
+        let mut quotes = Vec::new();
+        for symbol in symbols {
+            let chart = self.chart(symbol, 1).await?;
+            quotes.push((symbol.trim().to_uppercase(), chart.price));
+        }
+        Ok(quotes)
+    }
+
+    async fn stock_history(&self, symbol: &str, days: u32, _now: u64) -> Result<Vec<StockPrice>, String> {
+        let history = self.chart(symbol, days).await?.history;
+        if history.is_empty() {
+            return Err(format!("No historical data available for {}", symbol));
+        }
+        Ok(history)
+    }
+
+    // The chart feed has no share count; metrics derive market cap from the
+    // statements' share count when there is one and otherwise leave it at zero
+    async fn stock_details(&self, symbol: &str) -> Result<StockDetails, String> {
+        let instrument = instruments::lookup(&symbol.trim().to_uppercase());
+        Ok(StockDetails {
+            name: instrument.name.clone(),
+            market_cap: 0.0,
+            outstanding_shares: 0,
+            description: format!("{} listing {}", instrument.exchange_symbol, instruments::feed_symbol(&instrument)),
+        })
+    }
+}
+
+pub struct CoinGeckoProvider;
+
+impl MarketDataProvider for CoinGeckoProvider {
+    fn name(&self) -> &'static str {
+        "CoinGecko"
+    }
+
+    async fn crypto_prices(&self, coin_ids: &[String], _now: u64) -> Result<Vec<(String, f64)>, String> {
+        http_client::fetch_price(&coin_ids.join(",")).await
+    }
+
+    async fn crypto_ohlc(&self, coin_id: &str, vs_currency: &str, days: u32, _now: u64) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
+        http_client::fetch_coin_ohlc(coin_id, vs_currency, days).await
+    }
+
+    async fn crypto_market_chart(&self, coin_id: &str, vs_currency: &str, days: u32, _now: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        http_client::fetch_coin_market_chart(coin_id, vs_currency, days).await
+    }
+
+    async fn crypto_market_chart_range(&self, coin_id: &str, vs_currency: &str, from: u64, to: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        http_client::fetch_coin_market_chart_range(coin_id, vs_currency, from, to).await
+    }
+}
+
+// Deterministic prices computed from the seed, symbol and day, so offline
+// runs (local dev, PocketIC) see the same market for the same seed
+pub struct MockProvider {
+    pub seed: u64,
+}
+
+impl MarketDataProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    async fn stock_quotes(&self, symbols: &[String], now: u64) -> Result<Vec<(String, f64)>, String> {
+        Ok(symbols
+            .iter()
+            .map(|symbol| {
+                let symbol = symbol.trim().to_uppercase();
+                let price = mock_price(self.seed, &symbol, http_client::fallback_stock_price(&symbol), now);
+                (symbol, price)
+            })
+            .collect())
+    }
+
+    async fn stock_history(&self, symbol: &str, days: u32, now: u64) -> Result<Vec<StockPrice>, String> {
+        Ok(mock_history(self.seed, symbol, http_client::fallback_stock_price(symbol), days, now))
+    }
+
+    async fn stock_details(&self, symbol: &str) -> Result<StockDetails, String> {
+        let outstanding_shares = 100_000_000 + mix(self.seed ^ hash_symbol(symbol)) % 900_000_000;
+        Ok(StockDetails {
+            name: instruments::lookup(symbol).name,
+            market_cap: outstanding_shares as f64 * http_client::fallback_stock_price(symbol),
+            outstanding_shares,
+            description: format!("Simulated listing for {}", symbol),
+        })
+    }
+
+    async fn crypto_prices(&self, coin_ids: &[String], now: u64) -> Result<Vec<(String, f64)>, String> {
+        Ok(coin_ids
+            .iter()
+            .map(|coin_id| {
+                let coin_id = coin_id.trim().to_lowercase();
+                let price = mock_price(self.seed, &coin_id, mock_crypto_reference(&coin_id), now);
+                (coin_id, price)
+            })
+            .collect())
+    }
+
+    // Daily candles; the mock market has no intraday bars
+    async fn crypto_ohlc(&self, coin_id: &str, vs_currency: &str, days: u32, now: u64) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
+        Ok(self.crypto_history(coin_id, vs_currency, days, now)?
+            .into_iter()
+            .map(|bar| (bar.timestamp / NANOS_PER_MILLI, bar.open, bar.high, bar.low, bar.close))
+            .collect())
+    }
+
+    async fn crypto_market_chart(&self, coin_id: &str, vs_currency: &str, days: u32, now: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        Ok(mock_market_chart(&self.crypto_history(coin_id, vs_currency, days, now)?, 0))
+    }
+
+    async fn crypto_market_chart_range(&self, coin_id: &str, vs_currency: &str, from: u64, to: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        if from > to {
+            return Err("Chart range ends before it starts".to_string());
+        }
+        let days = ((to - from) / SECONDS_PER_DAY + 1).min(u32::MAX as u64) as u32;
+        let history = self.crypto_history(coin_id, vs_currency, days, to.saturating_mul(NANOS_PER_SECOND))?;
+        Ok(mock_market_chart(&history, from.saturating_mul(1_000)))
+    }
+    // Fixed reference rates; a daily ±8% swing would be unrealistic for currencies
+    async fn fx_rate(&self, base: &str, quote: &str, _now: u64) -> Result<f64, String> {
+        match (mock_rupees_per_unit(base), mock_rupees_per_unit(quote)) {
+            (Some(base_rupees), Some(quote_rupees)) => Ok(base_rupees / quote_rupees),
+            _ => Err(format!("No mock rate for {}/{}", base, quote)),
+        }
+    }
+}
+
+impl MockProvider {
+    // Daily bars for a coin, priced in `vs_currency` via the mock FX rates
+    fn crypto_history(&self, coin_id: &str, vs_currency: &str, days: u32, now: u64) -> Result<Vec<StockPrice>, String> {
+        let coin_id = coin_id.trim().to_lowercase();
+        let vs_currency = vs_currency.trim().to_uppercase();
+        let (usd_rupees, vs_rupees) = match (mock_rupees_per_unit(instruments::USD), mock_rupees_per_unit(&vs_currency)) {
+            (Some(usd_rupees), Some(vs_rupees)) => (usd_rupees, vs_rupees),
+            _ => return Err(format!("No mock rate for {}", vs_currency)),
+        };
+        let reference = mock_crypto_reference(&coin_id) * usd_rupees / vs_rupees;
+        Ok(mock_history(self.seed, &coin_id, reference, days, now))
+    }
+}
+
+// Stocks from the seeded market simulator (see market_sim)
+pub struct SimulatorProvider;
+
+impl MarketDataProvider for SimulatorProvider {
+    fn name(&self) -> &'static str {
+        "Simulator"
+    }
+
+    async fn stock_quotes(&self, symbols: &[String], now: u64) -> Result<Vec<(String, f64)>, String> {
+        let quotes: Vec<(String, f64)> = symbols
+            .iter()
+            .filter_map(|symbol| {
+                let symbol = symbol.trim().to_uppercase();
+                let (price, _) = market_sim::simulated_prices(&symbol, 1, now)?;
+                Some((symbol, price))
+            })
+            .collect();
+
+        if quotes.is_empty() {
+            return Err("None of the symbols are simulated".to_string());
+        }
+        Ok(quotes)
+    }
+
+    async fn stock_history(&self, symbol: &str, days: u32, now: u64) -> Result<Vec<StockPrice>, String> {
+        market_sim::simulated_prices(symbol, days, now)
+            .map(|(_, history)| history)
+            .ok_or_else(|| format!("{} is not simulated", symbol))
+    }
+
+    async fn stock_details(&self, symbol: &str) -> Result<StockDetails, String> {
+        let instrument = market_sim::simulated_instrument(symbol)
+            .ok_or_else(|| format!("{} is not simulated", symbol))?;
+        let (price, _) = market_sim::simulated_prices(symbol, 1, ic_cdk::api::time())
+            .unwrap_or((instrument.initial_price, Vec::new()));
+
+        Ok(StockDetails {
+            name: instrument.symbol.clone(),
+            market_cap: instrument.outstanding_shares as f64 * price,
+            outstanding_shares: instrument.outstanding_shares,
+            description: format!("Simulated {} stock", instrument.sector),
+        })
+    }
+}
+
+// The configured stock and crypto sources behind one provider. In Polygon
+// mode, NSE/BSE symbols go to IndianExchangeProvider instead.
+struct ConfiguredProvider {
+    config: MarketDataConfig,
+}
+
+impl ConfiguredProvider {
+    fn load() -> Self {
+        Self { config: storage::get_market_data_config() }
+    }
+
+    fn mock(&self) -> MockProvider {
+        MockProvider { seed: self.config.mock_seed }
+    }
+}
+
+impl MarketDataProvider for ConfiguredProvider {
+    fn name(&self) -> &'static str {
+        "Configured"
+    }
+
+    async fn stock_quotes(&self, symbols: &[String], now: u64) -> Result<Vec<(String, f64)>, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon => {
+                let (indian, others): (Vec<String>, Vec<String>) = symbols
+                    .iter()
+                    .cloned()
+                    .partition(|symbol| instruments::is_indian_symbol(symbol));
+                let mut quotes = Vec::new();
+                if !indian.is_empty() {
+                    quotes.extend(IndianExchangeProvider.stock_quotes(&indian, now).await?);
+                }
+                if !others.is_empty() {
+                    quotes.extend(PolygonProvider.stock_quotes(&others, now).await?);
+                }
+                Ok(quotes)
+            }
+            MarketDataSource::CoinGecko => CoinGeckoProvider.stock_quotes(symbols, now).await,
+            MarketDataSource::Mock => self.mock().stock_quotes(symbols, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.stock_quotes(symbols, now).await,
+        }
+    }
+
+    async fn stock_history(&self, symbol: &str, days: u32, now: u64) -> Result<Vec<StockPrice>, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon if instruments::is_indian_symbol(symbol) => {
+                IndianExchangeProvider.stock_history(symbol, days, now).await
+            }
+            MarketDataSource::Polygon => PolygonProvider.stock_history(symbol, days, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.stock_history(symbol, days, now).await,
+            MarketDataSource::Mock => self.mock().stock_history(symbol, days, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.stock_history(symbol, days, now).await,
+        }
+    }
+
+    async fn stock_details(&self, symbol: &str) -> Result<StockDetails, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon if instruments::is_indian_symbol(symbol) => {
+                IndianExchangeProvider.stock_details(symbol).await
+            }
+            MarketDataSource::Polygon => PolygonProvider.stock_details(symbol).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.stock_details(symbol).await,
+            MarketDataSource::Mock => self.mock().stock_details(symbol).await,
+            MarketDataSource::Simulator => SimulatorProvider.stock_details(symbol).await,
+        }
+    }
+
+    async fn crypto_prices(&self, coin_ids: &[String], now: u64) -> Result<Vec<(String, f64)>, String> {
+        match self.config.crypto_source {
+            MarketDataSource::Polygon => PolygonProvider.crypto_prices(coin_ids, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.crypto_prices(coin_ids, now).await,
+            MarketDataSource::Mock => self.mock().crypto_prices(coin_ids, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.crypto_prices(coin_ids, now).await,
+        }
+    }
+
+    async fn crypto_ohlc(&self, coin_id: &str, vs_currency: &str, days: u32, now: u64) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
+        match self.config.crypto_source {
+            MarketDataSource::Polygon => PolygonProvider.crypto_ohlc(coin_id, vs_currency, days, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.crypto_ohlc(coin_id, vs_currency, days, now).await,
+            MarketDataSource::Mock => self.mock().crypto_ohlc(coin_id, vs_currency, days, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.crypto_ohlc(coin_id, vs_currency, days, now).await,
+        }
+    }
+
+    async fn crypto_market_chart(&self, coin_id: &str, vs_currency: &str, days: u32, now: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        match self.config.crypto_source {
+            MarketDataSource::Polygon => PolygonProvider.crypto_market_chart(coin_id, vs_currency, days, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.crypto_market_chart(coin_id, vs_currency, days, now).await,
+            MarketDataSource::Mock => self.mock().crypto_market_chart(coin_id, vs_currency, days, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.crypto_market_chart(coin_id, vs_currency, days, now).await,
+        }
+    }
+
+    async fn crypto_market_chart_range(&self, coin_id: &str, vs_currency: &str, from: u64, to: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+        match self.config.crypto_source {
+            MarketDataSource::Polygon => PolygonProvider.crypto_market_chart_range(coin_id, vs_currency, from, to).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.crypto_market_chart_range(coin_id, vs_currency, from, to).await,
+            MarketDataSource::Mock => self.mock().crypto_market_chart_range(coin_id, vs_currency, from, to).await,
+            MarketDataSource::Simulator => SimulatorProvider.crypto_market_chart_range(coin_id, vs_currency, from, to).await,
+        }
+    }
+
+    async fn stock_news(&self, symbol: &str, limit: u32) -> Result<Vec<NewsItemRequest>, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon if instruments::is_indian_symbol(symbol) => {
+                IndianExchangeProvider.stock_news(symbol, limit).await
+            }
+            MarketDataSource::Polygon => PolygonProvider.stock_news(symbol, limit).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.stock_news(symbol, limit).await,
+            MarketDataSource::Mock => self.mock().stock_news(symbol, limit).await,
+            MarketDataSource::Simulator => SimulatorProvider.stock_news(symbol, limit).await,
+        }
+    }
+
+    async fn stock_fundamentals(&self, symbol: &str, now: u64) -> Result<Fundamentals, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon if instruments::is_indian_symbol(symbol) => {
+                IndianExchangeProvider.stock_fundamentals(symbol, now).await
+            }
+            MarketDataSource::Polygon => PolygonProvider.stock_fundamentals(symbol, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.stock_fundamentals(symbol, now).await,
+            MarketDataSource::Mock => self.mock().stock_fundamentals(symbol, now).await,
+            MarketDataSource::Simulator => SimulatorProvider.stock_fundamentals(symbol, now).await,
+        }
+    }
+
+    // Rates follow the stock source; the offline simulator uses the mock rates
+    async fn fx_rate(&self, base: &str, quote: &str, now: u64) -> Result<f64, String> {
+        match self.config.stock_source {
+            MarketDataSource::Polygon => PolygonProvider.fx_rate(base, quote, now).await,
+            MarketDataSource::CoinGecko => CoinGeckoProvider.fx_rate(base, quote, now).await,
+            MarketDataSource::Mock | MarketDataSource::Simulator => self.mock().fx_rate(base, quote, now).await,
+        }
+    }
+}
+
+// Entry points used by the rest of the canister; symbols are comma-separated
+pub async fn stock_quotes(symbols: &str) -> Result<Vec<(String, f64)>, String> {
+    ConfiguredProvider::load().stock_quotes(&split_list(symbols), ic_cdk::api::time()).await
+}
+
+pub async fn stock_history(symbol: &str, days: u32) -> Result<Vec<StockPrice>, String> {
+    ConfiguredProvider::load().stock_history(symbol, days, ic_cdk::api::time()).await
+}
+
+pub async fn stock_details(symbol: &str) -> Result<StockDetails, String> {
+    ConfiguredProvider::load().stock_details(symbol).await
+}
+
+pub async fn crypto_prices(coin_ids: &str) -> Result<Vec<(String, f64)>, String> {
+    ConfiguredProvider::load().crypto_prices(&split_list(coin_ids), ic_cdk::api::time()).await
+}
+
+pub async fn crypto_ohlc(coin_id: &str, vs_currency: &str, days: u32) -> Result<Vec<(u64, f64, f64, f64, f64)>, String> {
+    ConfiguredProvider::load().crypto_ohlc(coin_id, vs_currency, days, ic_cdk::api::time()).await
+}
+
+pub async fn crypto_market_chart(coin_id: &str, vs_currency: &str, days: u32) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+    ConfiguredProvider::load().crypto_market_chart(coin_id, vs_currency, days, ic_cdk::api::time()).await
+}
+
+pub async fn crypto_market_chart_range(coin_id: &str, vs_currency: &str, from: u64, to: u64) -> Result<HashMap<String, Vec<(u64, f64)>>, String> {
+    ConfiguredProvider::load().crypto_market_chart_range(coin_id, vs_currency, from, to).await
+}
+
+pub async fn stock_news(symbol: &str, limit: u32) -> Result<Vec<NewsItemRequest>, String> {
+    ConfiguredProvider::load().stock_news(symbol, limit).await
+}
+
+pub async fn stock_fundamentals(symbol: &str) -> Result<Fundamentals, String> {
+    ConfiguredProvider::load().stock_fundamentals(symbol, ic_cdk::api::time()).await
+}
+
+pub async fn fx_rate(base: &str, quote: &str) -> Result<f64, String> {
+    ConfiguredProvider::load().fx_rate(base, quote, ic_cdk::api::time()).await
+}
+
+pub fn get_market_data_config() -> MarketDataConfig {
+    storage::get_market_data_config()
+}
+
+// Switch market data sources (controllers only)
+pub fn set_market_data_config(config: MarketDataConfig) -> CanisterResult<MarketDataConfig> {
+    auth::verify_controller()?;
+
+    if config.stock_source == MarketDataSource::CoinGecko {
+        return Err(CanisterError::InvalidInput("CoinGecko does not provide stock data".to_string()));
+    }
+    if matches!(config.crypto_source, MarketDataSource::Polygon | MarketDataSource::Simulator) {
+        return Err(CanisterError::InvalidInput(format!("{:?} does not provide crypto prices", config.crypto_source)));
+    }
+
+    storage::set_market_data_config(config.clone());
+
+    Ok(config)
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn mock_rupees_per_unit(currency: &str) -> Option<f64> {
+    match currency {
+        "INR" => Some(1.0),
+        "USD" => Some(83.0),
+        "EUR" => Some(90.0),
+        "GBP" => Some(105.0),
+        _ => None,
+    }
+}
+
+fn mock_crypto_reference(coin_id: &str) -> f64 {
+    match coin_id {
+        "bitcoin" => 60_000.0,
+        "ethereum" => 3_000.0,
+        "internet-computer" => 10.0,
+        "chainlink" => 15.0,
+        "uniswap" => 7.0,
+        _ => 1.0,
+    }
+}
+
+// SplitMix64 finalizer
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// FNV-1a, stable across builds unlike the std hasher
+fn hash_symbol(symbol: &str) -> u64 {
+    symbol.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+// Uniform in [-1, 1) for this seed, symbol and stream position
+fn noise(seed: u64, symbol: &str, position: u64) -> f64 {
+    let bits = mix(seed ^ hash_symbol(symbol) ^ mix(position));
+    (bits >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+fn mock_close(seed: u64, symbol: &str, reference: f64, day: u64) -> f64 {
+    reference * (1.0 + MOCK_DAILY_SWING * noise(seed, symbol, day))
+}
+
+// Moves linearly from yesterday's close to today's across the day
+fn mock_price(seed: u64, symbol: &str, reference: f64, now: u64) -> f64 {
+    let day = now / NANOS_PER_DAY;
+    let progress = (now % NANOS_PER_DAY) as f64 / NANOS_PER_DAY as f64;
+    let previous = mock_close(seed, symbol, reference, day.saturating_sub(1));
+    let close = mock_close(seed, symbol, reference, day);
+    previous + (close - previous) * progress
+}
+
+// Daily bars for the last `days` days, ending with today's bar at the current price
+fn mock_history(seed: u64, symbol: &str, reference: f64, days: u32, now: u64) -> Vec<StockPrice> {
+    let today = now / NANOS_PER_DAY;
+
+    (today.saturating_sub(days.saturating_sub(1) as u64)..=today)
+        .map(|day| {
+            let open = mock_close(seed, symbol, reference, day.saturating_sub(1));
+            let close = if day == today {
+                mock_price(seed, symbol, reference, now)
+            } else {
+                mock_close(seed, symbol, reference, day)
+            };
+            let range = MOCK_INTRADAY_RANGE * noise(seed, symbol, day ^ u64::MAX).abs();
+            StockPrice {
+                timestamp: day * NANOS_PER_DAY,
+                price: close,
+                volume: 100_000 + mix(seed ^ hash_symbol(symbol) ^ day) % 900_000,
+                high: open.max(close) * (1.0 + range),
+                low: open.min(close) * (1.0 - range),
+                open,
+                close,
+            }
+        })
+        .collect()
+}
+
+// Chart series from daily bars, keeping points at or after `from_ms`. Market
+// cap is the close times a fixed mock supply.
+fn mock_market_chart(history: &[StockPrice], from_ms: u64) -> HashMap<String, Vec<(u64, f64)>> {
+    let points: Vec<&StockPrice> = history
+        .iter()
+        .filter(|bar| bar.timestamp / NANOS_PER_MILLI >= from_ms)
+        .collect();
+    let series = |value: &dyn Fn(&StockPrice) -> f64| -> Vec<(u64, f64)> {
+        points.iter().map(|bar| (bar.timestamp / NANOS_PER_MILLI, value(bar))).collect()
+    };
+
+    let mut chart = HashMap::new();
+    chart.insert("prices".to_string(), series(&|bar| bar.close));
+    chart.insert("market_caps".to_string(), series(&|bar| bar.close * MOCK_CRYPTO_SUPPLY));
+    chart.insert("total_volumes".to_string(), series(&|bar| bar.volume as f64 * bar.close));
+    chart
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_prices_are_deterministic() {
+        let now = 20_000 * NANOS_PER_DAY + 12 * 60 * 60 * 1_000_000_000;
+        assert_eq!(mock_price(7, "TCS", 3500.0, now), mock_price(7, "TCS", 3500.0, now));
+        assert_ne!(mock_price(7, "TCS", 3500.0, now), mock_price(8, "TCS", 3500.0, now));
+
+        let price = mock_price(7, "TCS", 3500.0, now);
+        assert!((3500.0 * (1.0 - MOCK_DAILY_SWING)..=3500.0 * (1.0 + MOCK_DAILY_SWING)).contains(&price));
+    }
+
+    #[test]
+    fn test_mock_history_ends_at_current_price() {
+        let now = 20_000 * NANOS_PER_DAY + 1_000;
+        let history = mock_history(7, "INFY", 1450.0, 7, now);
+
+        assert_eq!(history.len(), 7);
+        assert_eq!(history[6].timestamp, 20_000 * NANOS_PER_DAY);
+        assert_eq!(history[6].close, mock_price(7, "INFY", 1450.0, now));
+        assert_eq!(history[6].open, history[5].close);
+        assert!(history.iter().all(|bar| bar.low <= bar.open.min(bar.close) && bar.high >= bar.open.max(bar.close)));
+    }
+
+    #[test]
+    fn test_mock_crypto_chart() {
+        let mock = MockProvider { seed: 7 };
+        let now = 20_000 * NANOS_PER_DAY + 1_000;
+        let usd = mock.crypto_history("bitcoin", "usd", 3, now).unwrap();
+        let inr = mock.crypto_history("bitcoin", "inr", 3, now).unwrap();
+        assert_eq!(usd.len(), 3);
+        assert!((inr[2].close / usd[2].close - 83.0).abs() < 1e-9);
+        assert!(mock.crypto_history("bitcoin", "xyz", 3, now).is_err());
+
+        let chart = mock_market_chart(&usd, usd[1].timestamp / NANOS_PER_MILLI);
+        assert_eq!(chart["prices"], vec![
+            (usd[1].timestamp / NANOS_PER_MILLI, usd[1].close),
+            (usd[2].timestamp / NANOS_PER_MILLI, usd[2].close),
+        ]);
+        assert_eq!(chart["market_caps"].len(), 2);
+    }
+
+    #[test]
+    fn test_noise_range() {
+        assert!((0..1_000).all(|position| (-1.0..1.0).contains(&noise(42, "BTC", position))));
+        assert_eq!(split_list(" AAPL, ,MSFT "), vec!["AAPL".to_string(), "MSFT".to_string()]);
+    }
+}
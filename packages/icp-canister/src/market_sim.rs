@@ -0,0 +1,503 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::trading;
+use crate::candles;
+use crate::corporate_actions;
+use crate::instruments;
+use crate::fx;
+use crate::utils::{NANOS_PER_DAY, NANOS_PER_SECOND};
+use ic_cdk_timers::{TimerId, set_timer_interval, clear_timer};
+use std::cell::RefCell;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+const MAX_TICK_INTERVAL_SECONDS: u64 = 60 * 60;
+const MAX_INSTRUMENTS: usize = 50;
+const MAX_ANNUAL_VOLATILITY: f64 = 2.0;
+const MAX_ANNUAL_DRIFT: f64 = 1.0;
+const MAX_PREVIEW_TICKS: u32 = 2_000;
+// Spread of per-tick volume around its mean, in log terms
+const VOLUME_NOISE: f64 = 0.3;
+// Simulated corporate actions: a 2-for-1 split once a stock doubles from its
+// starting price, and a small dividend roughly once a quarter
+const SPLIT_TRIGGER_MULTIPLE: f64 = 2.0;
+const DIVIDEND_INTERVAL_DAYS: u64 = 91;
+const DIVIDEND_YIELD_PER_PAYMENT: f64 = 0.005;
+
+thread_local! {
+    static SIM_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+// SplitMix64: tiny, seedable and identical on every replica and build
+struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in (0, 1]
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    // Standard normal via Box-Muller
+    fn normal(&mut self) -> f64 {
+        let u1 = self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+}
+
+// The built-in universe used when a configuration lists no instruments
+pub fn default_instruments() -> Vec<SimInstrument> {
+    [
+        ("RELIANCE", 2500.0, 0.10, 0.25, 6_000_000, 6_000_000_000),
+        ("TCS", 3200.0, 0.12, 0.22, 2_000_000, 3_750_000_000),
+        ("INFY", 1450.0, 0.12, 0.28, 5_000_000, 4_150_000_000),
+        ("WIPRO", 380.0, 0.08, 0.30, 8_000_000, 5_200_000_000),
+        ("TECHM", 1120.0, 0.08, 0.32, 2_500_000, 880_000_000),
+        ("HDFC", 1680.0, 0.11, 0.24, 7_000_000, 7_600_000_000),
+        ("ICICI", 750.0, 0.13, 0.27, 12_000_000, 7_000_000_000),
+        ("SBI", 520.0, 0.09, 0.30, 15_000_000, 8_900_000_000),
+        ("BHARTI", 820.0, 0.10, 0.26, 5_000_000, 5_800_000_000),
+        ("ITC", 420.0, 0.07, 0.20, 10_000_000, 12_400_000_000),
+    ]
+    .into_iter()
+    .map(|(symbol, initial_price, annual_drift, annual_volatility, average_daily_volume, outstanding_shares)| SimInstrument {
+        symbol: symbol.to_string(),
+        sector: instruments::catalog_sector(symbol),
+        initial_price,
+        annual_drift,
+        annual_volatility,
+        average_daily_volume,
+        outstanding_shares,
+        exchange: None,
+        currency: None,
+    })
+    .collect()
+}
+
+pub fn get_simulator_state() -> SimulatorState {
+    storage::get_simulator_state()
+}
+
+// Replace the simulator configuration and rewind to tick zero (controllers only).
+// An empty instrument list selects the built-in universe.
+pub fn configure_simulator(config: SimulatorConfig) -> CanisterResult<SimulatorState> {
+    auth::verify_controller()?;
+
+    let config = validate_config(config)?;
+    let running = storage::get_simulator_state().running;
+    let state = SimulatorState { running, ..initial_state(config) };
+    storage::set_simulator_state(state.clone());
+
+    if running {
+        restart_timer(state.config.tick_interval_seconds);
+    }
+
+    Ok(state)
+}
+
+// Start ticking (controllers only)
+pub fn start_simulator() -> CanisterResult<()> {
+    auth::verify_controller()?;
+
+    let mut state = storage::get_simulator_state();
+    if state.quotes.is_empty() {
+        return Err(CanisterError::InvalidInput("Configure the simulator first".to_string()));
+    }
+    state.running = true;
+    let interval = state.config.tick_interval_seconds;
+    storage::set_simulator_state(state);
+
+    restart_timer(interval);
+    Ok(())
+}
+
+// Stop ticking; prices stay where they are (controllers only)
+pub fn stop_simulator() -> CanisterResult<()> {
+    auth::verify_controller()?;
+
+    let mut state = storage::get_simulator_state();
+    state.running = false;
+    storage::set_simulator_state(state);
+
+    SIM_TIMER.with(|timer_cell| {
+        if let Some(timer_id) = timer_cell.borrow_mut().take() {
+            clear_timer(timer_id);
+        }
+    });
+    Ok(())
+}
+
+// Re-arm the timer after an upgrade if the simulator was running
+pub fn resume() {
+    let state = storage::get_simulator_state();
+    if state.running {
+        restart_timer(state.config.tick_interval_seconds);
+    }
+}
+
+// Replay a configuration from tick zero without touching the live market.
+// Timestamps count from zero in tick intervals.
+pub fn preview_simulation(config: SimulatorConfig, ticks: u32) -> CanisterResult<Vec<SimTick>> {
+    if ticks == 0 || ticks > MAX_PREVIEW_TICKS {
+        return Err(CanisterError::InvalidInput(format!("Ticks must be between 1 and {}", MAX_PREVIEW_TICKS)));
+    }
+
+    let config = validate_config(config)?;
+    let interval = config.tick_interval_seconds * NANOS_PER_SECOND;
+    let mut state = initial_state(config);
+
+    Ok((1..=ticks as u64)
+        .flat_map(|tick| step(&mut state, tick * interval))
+        .collect())
+}
+
+// Current simulated price and daily history for a symbol, if it is simulated
+pub fn simulated_prices(symbol: &str, days: u32, now: u64) -> Option<(f64, Vec<StockPrice>)> {
+    let state = storage::get_simulator_state();
+    let quote = state.quotes.iter().find(|quote| quote.symbol == symbol)?;
+
+    let from = (now / NANOS_PER_DAY).saturating_sub(days.saturating_sub(1) as u64) * NANOS_PER_DAY;
+    let mut history: Vec<StockPrice> = storage::get_candles(symbol, CandleResolution::Day1, from, now)
+        .into_iter()
+        .map(|candle| StockPrice {
+            timestamp: candle.bucket_start,
+            price: candle.close,
+            volume: candle.volume as u64,
+            high: candle.high,
+            low: candle.low,
+            open: candle.open,
+            close: candle.close,
+        })
+        .collect();
+
+    if history.is_empty() {
+        history.push(StockPrice {
+            timestamp: quote.day * NANOS_PER_DAY,
+            price: quote.price,
+            volume: quote.day_volume,
+            high: quote.day_high,
+            low: quote.day_low,
+            open: quote.day_open,
+            close: quote.price,
+        });
+    }
+
+    Some((quote.price, history))
+}
+
+pub fn simulated_instrument(symbol: &str) -> Option<SimInstrument> {
+    storage::get_simulator_state()
+        .config
+        .instruments
+        .into_iter()
+        .find(|instrument| instrument.symbol == symbol)
+}
+
+fn restart_timer(interval_seconds: u64) {
+    SIM_TIMER.with(|timer_cell| {
+        if let Some(timer_id) = timer_cell.borrow_mut().take() {
+            clear_timer(timer_id);
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(interval_seconds), || {
+            run_tick(ic_cdk::api::time());
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Advance the live market one tick and feed the candles and resting orders
+fn run_tick(now: u64) {
+    let mut state = storage::get_simulator_state();
+    if !state.running {
+        return;
+    }
+
+    let new_session = state.last_tick_at.is_some_and(|last| last / NANOS_PER_DAY != now / NANOS_PER_DAY);
+    let ticks = step(&mut state, now);
+    storage::set_simulator_state(state.clone());
+
+    for tick in &ticks {
+        candles::record_price(&tick.symbol, tick.price, tick.volume as f64, now);
+        crate::orders::evaluate_orders(&tick.symbol, tick.price, now);
+    }
+
+    if new_session {
+        schedule_session_actions(&state, now);
+    }
+}
+
+// At the start of each session, put generated splits and dividends on the
+// calendar for the next day
+fn schedule_session_actions(state: &SimulatorState, now: u64) {
+    let day = now / NANOS_PER_DAY;
+
+    for (instrument, quote) in state.config.instruments.iter().zip(&state.quotes) {
+        let Some(kind) = session_action(instrument, quote.price, day) else {
+            continue;
+        };
+        if corporate_actions::has_scheduled_action(&instrument.symbol) {
+            continue;
+        }
+
+        let request = CorporateActionRequest {
+            stock_id: instrument.symbol.clone(),
+            kind,
+            ex_date: (day + 1) * NANOS_PER_DAY,
+        };
+        if let Err(e) = corporate_actions::schedule(request, CorporateActionSource::Simulator, now) {
+            ic_cdk::println!("⚠️ Simulated corporate action failed for {}: {}", instrument.symbol, e);
+        }
+    }
+}
+
+fn session_action(instrument: &SimInstrument, price: f64, day: u64) -> Option<CorporateActionKind> {
+    if price >= instrument.initial_price * SPLIT_TRIGGER_MULTIPLE {
+        return Some(CorporateActionKind::Split { new_shares: 2, old_shares: 1 });
+    }
+
+    // Stagger payment days across symbols
+    let offset = instrument.symbol.bytes().map(u64::from).sum::<u64>();
+    if (day + offset).is_multiple_of(DIVIDEND_INTERVAL_DAYS) {
+        let per_share = (price * DIVIDEND_YIELD_PER_PAYMENT * 100.0).floor() / 100.0;
+        if per_share > 0.0 {
+            return Some(CorporateActionKind::CashDividend { per_share });
+        }
+    }
+
+    None
+}
+
+// Rescale a simulated stock after a corporate action (e.g. halve it on a 2-for-1 split)
+pub fn apply_price_factor(symbol: &str, factor: f64) {
+    let mut state = storage::get_simulator_state();
+    let Some(quote) = state.quotes.iter_mut().find(|quote| quote.symbol == symbol) else {
+        return;
+    };
+
+    quote.price *= factor;
+    quote.day_open *= factor;
+    quote.day_high *= factor;
+    quote.day_low *= factor;
+    storage::set_simulator_state(state);
+}
+
+fn validate_config(mut config: SimulatorConfig) -> CanisterResult<SimulatorConfig> {
+    if config.tick_interval_seconds == 0 || config.tick_interval_seconds > MAX_TICK_INTERVAL_SECONDS {
+        return Err(CanisterError::InvalidInput(format!("Tick interval must be between 1 and {} seconds", MAX_TICK_INTERVAL_SECONDS)));
+    }
+    if !(0.0..=1.0).contains(&config.sector_correlation) {
+        return Err(CanisterError::InvalidInput("Sector correlation must be between 0 and 1".to_string()));
+    }
+    if config.instruments.is_empty() {
+        config.instruments = default_instruments();
+    }
+    if config.instruments.len() > MAX_INSTRUMENTS {
+        return Err(CanisterError::InvalidInput(format!("At most {} instruments can be simulated", MAX_INSTRUMENTS)));
+    }
+
+    for instrument in config.instruments.iter_mut() {
+        instrument.symbol = trading::normalize_stock_id(&instrument.symbol)?;
+        instrument.sector = instrument.sector.trim().to_string();
+        instrument.currency = fx::normalize_optional_currency(instrument.currency.take())?;
+        if !instrument.initial_price.is_finite() || instrument.initial_price <= 0.0 {
+            return Err(CanisterError::InvalidInput(format!("{}: initial price must be positive", instrument.symbol)));
+        }
+        if !(0.0..=MAX_ANNUAL_VOLATILITY).contains(&instrument.annual_volatility) {
+            return Err(CanisterError::InvalidInput(format!("{}: volatility must be between 0 and {}", instrument.symbol, MAX_ANNUAL_VOLATILITY)));
+        }
+        if !(-MAX_ANNUAL_DRIFT..=MAX_ANNUAL_DRIFT).contains(&instrument.annual_drift) {
+            return Err(CanisterError::InvalidInput(format!("{}: drift must be between -{} and {}", instrument.symbol, MAX_ANNUAL_DRIFT, MAX_ANNUAL_DRIFT)));
+        }
+    }
+
+    let mut symbols: Vec<&str> = config.instruments.iter().map(|instrument| instrument.symbol.as_str()).collect();
+    symbols.sort_unstable();
+    if symbols.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(CanisterError::InvalidInput("Instrument symbols must be unique".to_string()));
+    }
+
+    Ok(config)
+}
+
+fn initial_state(config: SimulatorConfig) -> SimulatorState {
+    let quotes = config.instruments
+        .iter()
+        .map(|instrument| SimQuote {
+            symbol: instrument.symbol.clone(),
+            price: instrument.initial_price,
+            day: 0,
+            day_open: instrument.initial_price,
+            day_high: instrument.initial_price,
+            day_low: instrument.initial_price,
+            day_volume: 0,
+        })
+        .collect();
+
+    SimulatorState {
+        rng_state: config.seed,
+        config,
+        running: false,
+        tick: 0,
+        quotes,
+        last_tick_at: None,
+    }
+}
+
+// One GBM step for every instrument. Each shock mixes a per-sector factor
+// (drawn once per tick) with an idiosyncratic one, so stocks in a sector
+// move together. Prices depend only on the seed and tick count; `timestamp`
+// only decides when a new session (day) begins.
+fn step(state: &mut SimulatorState, timestamp: u64) -> Vec<SimTick> {
+    let mut rng = SimRng { state: state.rng_state };
+    let config = &state.config;
+    let dt = config.tick_interval_seconds as f64 / SECONDS_PER_YEAR;
+    let ticks_per_day = (NANOS_PER_DAY / (config.tick_interval_seconds * NANOS_PER_SECOND)).max(1) as f64;
+    let rho = config.sector_correlation;
+    let day = timestamp / NANOS_PER_DAY;
+
+    let mut sectors: Vec<&str> = config.instruments.iter().map(|instrument| instrument.sector.as_str()).collect();
+    sectors.sort_unstable();
+    sectors.dedup();
+    let sector_factors: Vec<f64> = sectors.iter().map(|_| rng.normal()).collect();
+
+    state.tick += 1;
+    let mut ticks = Vec::with_capacity(config.instruments.len());
+
+    for (instrument, quote) in config.instruments.iter().zip(state.quotes.iter_mut()) {
+        let sector = sectors.binary_search(&instrument.sector.as_str()).unwrap_or(0);
+        let shock = rho.sqrt() * sector_factors[sector] + (1.0 - rho).sqrt() * rng.normal();
+
+        let sigma = instrument.annual_volatility;
+        let log_return = (instrument.annual_drift - sigma * sigma / 2.0) * dt + sigma * dt.sqrt() * shock;
+        let price = quote.price * log_return.exp();
+
+        // Busier on big moves, with some noise of its own
+        let mean_volume = instrument.average_daily_volume as f64 / ticks_per_day;
+        let volume = (mean_volume * (0.5 + shock.abs()) * (VOLUME_NOISE * rng.normal()).exp()).round() as u64;
+
+        if quote.day != day {
+            quote.day = day;
+            quote.day_open = quote.price;
+            quote.day_high = quote.price;
+            quote.day_low = quote.price;
+            quote.day_volume = 0;
+        }
+        quote.price = price;
+        quote.day_high = quote.day_high.max(price);
+        quote.day_low = quote.day_low.min(price);
+        quote.day_volume += volume;
+
+        ticks.push(SimTick {
+            symbol: instrument.symbol.clone(),
+            tick: state.tick,
+            timestamp,
+            price,
+            volume,
+        });
+    }
+
+    state.rng_state = rng.state;
+    state.last_tick_at = Some(timestamp);
+    ticks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instrument(symbol: &str, sector: &str, annual_volatility: f64) -> SimInstrument {
+        SimInstrument {
+            symbol: symbol.to_string(),
+            sector: sector.to_string(),
+            initial_price: 100.0,
+            annual_drift: 0.05,
+            annual_volatility,
+            average_daily_volume: 1_000_000,
+            outstanding_shares: 1_000_000,
+            exchange: None,
+            currency: None,
+        }
+    }
+
+    fn config(seed: u64, sector_correlation: f64, instruments: Vec<SimInstrument>) -> SimulatorConfig {
+        SimulatorConfig { seed, tick_interval_seconds: 60, sector_correlation, instruments }
+    }
+
+    fn run(config: SimulatorConfig, ticks: u64) -> Vec<Vec<SimTick>> {
+        let mut state = initial_state(config);
+        (1..=ticks).map(|tick| step(&mut state, tick * 60 * NANOS_PER_SECOND)).collect()
+    }
+
+    fn log_returns(path: &[Vec<SimTick>], index: usize) -> Vec<f64> {
+        path.windows(2).map(|pair| (pair[1][index].price / pair[0][index].price).ln()).collect()
+    }
+
+    #[test]
+    fn test_same_seed_same_market() {
+        let instruments = vec![instrument("AAA", "Tech", 0.3), instrument("BBB", "Energy", 0.2)];
+        let first = run(config(7, 0.5, instruments.clone()), 50);
+        let second = run(config(7, 0.5, instruments.clone()), 50);
+        let other = run(config(8, 0.5, instruments), 50);
+
+        let prices = |path: &[Vec<SimTick>]| path.iter().flatten().map(|tick| tick.price).collect::<Vec<_>>();
+        assert_eq!(prices(&first), prices(&second));
+        assert_ne!(prices(&first), prices(&other));
+    }
+
+    #[test]
+    fn test_zero_volatility_follows_drift() {
+        let path = run(config(1, 0.0, vec![instrument("AAA", "Tech", 0.0)]), 10);
+        let expected = 100.0 * (0.05 * 10.0 * 60.0 / SECONDS_PER_YEAR).exp();
+        assert!((path[9][0].price - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sector_correlation() {
+        let instruments = vec![instrument("AAA", "Tech", 0.3), instrument("BBB", "Tech", 0.3)];
+
+        let together = run(config(3, 1.0, instruments.clone()), 200);
+        assert_eq!(log_returns(&together, 0), log_returns(&together, 1));
+
+        let apart = run(config(3, 0.0, instruments), 200);
+        assert_ne!(log_returns(&apart, 0), log_returns(&apart, 1));
+    }
+
+    #[test]
+    fn test_session_rollover_and_validation() {
+        let mut state = initial_state(config(5, 0.0, vec![instrument("AAA", "Tech", 0.3)]));
+        step(&mut state, NANOS_PER_DAY - 1);
+        let close = state.quotes[0].price;
+        step(&mut state, NANOS_PER_DAY);
+
+        assert_eq!(state.quotes[0].day, 1);
+        assert_eq!(state.quotes[0].day_open, close);
+        assert!(state.quotes[0].day_volume > 0);
+
+        assert!(validate_config(config(1, 1.5, vec![])).is_err());
+        assert!(validate_config(config(1, 0.5, vec![instrument("AAA", "Tech", 0.3), instrument("aaa", "Tech", 0.3)])).is_err());
+        assert_eq!(validate_config(config(1, 0.5, vec![])).unwrap().instruments.len(), default_instruments().len());
+    }
+
+    #[test]
+    fn test_session_actions() {
+        let aaa = instrument("AAA", "Tech", 0.3);
+        assert_eq!(session_action(&aaa, 200.0, 1), Some(CorporateActionKind::Split { new_shares: 2, old_shares: 1 }));
+
+        // "AAA" staggers payments by 195 days, so day 78 pays (78 + 195 = 3 * 91)
+        assert_eq!(session_action(&aaa, 150.0, 78), Some(CorporateActionKind::CashDividend { per_share: 0.75 }));
+        assert_eq!(session_action(&aaa, 150.0, 79), None);
+    }
+}
@@ -197,37 +197,41 @@ pub fn get_user_activity_metrics() -> UserActivityMetrics {
 }
 
 pub fn get_transaction_metrics() -> TransactionMetrics {
-    // Iterate user data to build metrics (lightweight estimation if large user base)
+    // Walk the stable transaction log to build metrics
     let mut total_volume_rupees = 0.0;
     let mut total_volume_tokens = 0.0;
     let mut exchange_count = 0usize;
     let mut total_transactions = 0usize;
     let mut successful_transactions = 0usize;
 
-    crate::storage::STATE.with(|state| {
-        let state = state.borrow();
-        for (_addr, user) in state.users.iter() {
-            for tx in &user.transactions {
-                total_transactions += 1;
-                if tx.status == crate::types::TransactionStatus::Confirmed {
-                    successful_transactions += 1;
-                }
-                match tx.transaction_type {
-                    crate::types::TransactionType::Exchange => {
-                        exchange_count += 1;
-                        // treat amount as rupees if from is user and to None? simplified
-                        total_volume_rupees += tx.amount; // approximation
-                    },
-                    crate::types::TransactionType::Deposit => {
-                        total_volume_rupees += tx.amount;
-                    },
-                    crate::types::TransactionType::Withdraw => {
-                        total_volume_tokens += tx.amount;
-                    },
-                }
-            }
+    for tx in crate::storage::get_all_transactions() {
+        total_transactions += 1;
+        if tx.status == crate::types::TransactionStatus::Confirmed {
+            successful_transactions += 1;
         }
-    });
+        match tx.transaction_type {
+            crate::types::TransactionType::Exchange => {
+                exchange_count += 1;
+                // treat amount as rupees if from is user and to None? simplified
+                total_volume_rupees += tx.amount; // approximation
+            },
+            crate::types::TransactionType::Deposit => {
+                total_volume_rupees += tx.amount;
+            },
+            crate::types::TransactionType::Withdraw => {
+                total_volume_tokens += tx.amount;
+            },
+            crate::types::TransactionType::Transfer => {
+                total_volume_rupees += tx.amount; // approximation
+            },
+            crate::types::TransactionType::Interest => {
+                total_volume_rupees += tx.amount;
+            },
+            crate::types::TransactionType::StockBuy | crate::types::TransactionType::StockSell | crate::types::TransactionType::Dividend => {
+                total_volume_rupees += tx.amount;
+            },
+        }
+    }
 
     let average_transaction_size = if total_transactions > 0 {
         (total_volume_rupees + total_volume_tokens) / total_transactions as f64
@@ -340,9 +344,7 @@ fn get_error_metrics(store: &MetricsStore) -> ErrorMetrics {
 }
 
 fn calculate_total_transactions() -> usize {
-    // This would sum transactions across all users
-    // For now, return estimated count
-    storage::get_users_count() * 5 // Estimate 5 transactions per user
+    storage::get_total_transactions_count()
 }
 
 // staking removed
@@ -440,7 +442,7 @@ pub fn heartbeat_tasks() {
     if now - last_update > ten_minutes {
         // Spawn price update task (fire and forget)
         ic_cdk::spawn(async {
-            match crate::http_client::fetch_price("bitcoin,ethereum,internet-computer").await {
+            match crate::market_data::crypto_prices("bitcoin,ethereum,internet-computer").await {
                 Ok(prices) => {
                     // prices is a Vec<(String, f64)>; iterate by reference to avoid moving it
                     for (token_id, price) in prices.iter() {
@@ -472,6 +474,7 @@ pub fn heartbeat_tasks() {
                         prices: price_entries,
                     };
 
+                    crate::candles::record_snapshot(&snapshot);
                     PRICE_HISTORY.with(|hist| {
                         let mut hist = hist.borrow_mut();
                         hist.push_back(snapshot);
@@ -497,7 +500,7 @@ pub fn get_price_history() -> Vec<PriceSnapshot> {
 /// Fetch prices for token IDs (comma-separated) and append a snapshot to the in-memory history.
 /// Returns the new history length on success.
 pub async fn fetch_and_append_snapshot(token_ids: &str) -> Result<usize, String> {
-    match crate::http_client::fetch_price(token_ids).await {
+    match crate::market_data::crypto_prices(token_ids).await {
         Ok(prices) => {
             let mut price_entries = Vec::new();
             for (token_id, price) in prices.iter() {
@@ -518,6 +521,7 @@ pub async fn fetch_and_append_snapshot(token_ids: &str) -> Result<usize, String>
                 prices: price_entries,
             };
 
+            crate::candles::record_snapshot(&snapshot);
             PRICE_HISTORY.with(|hist| {
                 let mut hist = hist.borrow_mut();
                 hist.push_back(snapshot);
@@ -0,0 +1,257 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::trading;
+use crate::market_data;
+use crate::stock_sse;
+
+// Older items are pruned once a symbol holds more than this
+const MAX_NEWS_PER_SYMBOL: usize = 200;
+const DEFAULT_NEWS_LIMIT: u32 = 20;
+const MAX_NEWS_LIMIT: u32 = 100;
+
+const MAX_HEADLINE_LEN: usize = 300;
+const MAX_BODY_LEN: usize = 5_000;
+const MAX_SOURCE_LEN: usize = 100;
+const MAX_TAGS: usize = 10;
+const MAX_TAG_LEN: usize = 32;
+
+// Store items pushed by an admin or oracle (controllers only). The batch is
+// rejected if any item is invalid. Items already held for the symbol are
+// skipped; each symbol's new items are broadcast.
+pub fn ingest_news(items: Vec<NewsItemRequest>) -> CanisterResult<Vec<NewsItem>> {
+    auth::verify_controller()?;
+    let requests = items
+        .into_iter()
+        .map(validate_request)
+        .collect::<CanisterResult<Vec<_>>>()?;
+    Ok(store(requests, ic_cdk::api::time()))
+}
+
+// Pull the latest items for a symbol from the configured provider (controllers only)
+pub async fn refresh_news(symbol: String) -> CanisterResult<Vec<NewsItem>> {
+    auth::verify_controller()?;
+    let symbol = trading::normalize_stock_id(&symbol)?;
+
+    let items = market_data::stock_news(&symbol, DEFAULT_NEWS_LIMIT).await
+        .map_err(CanisterError::internal_error)?;
+
+    // One malformed article should not cost the rest of the feed
+    let mut requests = Vec::new();
+    for item in items {
+        let headline = item.headline.clone();
+        match fit_provider_item(item) {
+            Ok(request) => requests.push(request),
+            Err(e) => ic_cdk::println!("⚠️ Skipped {} news item {:?}: {}", symbol, headline, e),
+        }
+    }
+    Ok(store(requests, ic_cdk::api::time()))
+}
+
+// A symbol's items, newest first, optionally only those published after `since`
+pub fn get_news(symbol: String, since: Option<u64>, limit: Option<u32>) -> CanisterResult<Vec<NewsItem>> {
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    let limit = limit.unwrap_or(DEFAULT_NEWS_LIMIT).clamp(1, MAX_NEWS_LIMIT) as usize;
+    let start = since.map_or(0, |since| since.saturating_add(1));
+
+    let mut items = storage::get_news_items(&symbol, start, u64::MAX);
+    items.reverse();
+    items.truncate(limit);
+    Ok(items)
+}
+
+// Most recent headlines for a symbol, as carried on `Stock.news`
+pub fn headlines(symbol: &str, count: usize) -> Vec<String> {
+    storage::get_news_items(symbol, 0, u64::MAX)
+        .into_iter()
+        .rev()
+        .take(count)
+        .map(|item| item.headline)
+        .collect()
+}
+
+// Re-send a symbol's latest items to its stock room
+pub fn broadcast_latest(symbol: String) -> CanisterResult<usize> {
+    let items = get_news(symbol.clone(), None, None)?;
+    stock_sse::broadcast_stock_news(&trading::normalize_stock_id(&symbol)?, &items)
+}
+
+// Store validated requests, skipping duplicates
+fn store(requests: Vec<NewsItemRequest>, now: u64) -> Vec<NewsItem> {
+    let mut stored: Vec<NewsItem> = Vec::new();
+    for request in requests {
+        let existing = storage::get_news_items(&request.symbol, request.published_at, request.published_at);
+        if existing.iter().chain(stored.iter()).any(|item| is_duplicate(item, &request)) {
+            continue;
+        }
+
+        let item = NewsItem {
+            id: utils::generate_news_id(),
+            symbol: request.symbol,
+            headline: request.headline,
+            body: request.body,
+            source: request.source,
+            published_at: request.published_at,
+            sentiment: request.sentiment,
+            tags: request.tags,
+            ingested_at: now,
+        };
+        storage::insert_news_item(&item);
+        stored.push(item);
+    }
+
+    let mut symbols: Vec<&str> = stored.iter().map(|item| item.symbol.as_str()).collect();
+    symbols.sort_unstable();
+    symbols.dedup();
+    for symbol in symbols {
+        storage::prune_news_items(symbol, MAX_NEWS_PER_SYMBOL);
+        let items: Vec<NewsItem> = stored.iter().filter(|item| item.symbol == symbol).cloned().collect();
+        let _ = stock_sse::broadcast_stock_news(symbol, &items);
+    }
+
+    stored
+}
+
+// Provider text is cut to the stored limits rather than rejected; items that
+// are still invalid (no headline, source or publish time) fail validation
+fn fit_provider_item(mut request: NewsItemRequest) -> CanisterResult<NewsItemRequest> {
+    request.headline = truncate_chars(request.headline.trim(), MAX_HEADLINE_LEN);
+    request.body = truncate_chars(request.body.trim(), MAX_BODY_LEN);
+    request.source = truncate_chars(request.source.trim(), MAX_SOURCE_LEN);
+    validate_request(request)
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+fn validate_request(mut request: NewsItemRequest) -> CanisterResult<NewsItemRequest> {
+    request.symbol = trading::normalize_stock_id(&request.symbol)?;
+    request.headline = request.headline.trim().to_string();
+    request.body = request.body.trim().to_string();
+    request.source = request.source.trim().to_string();
+
+    if request.headline.is_empty() {
+        return Err(CanisterError::InvalidInput("Headline must not be empty".to_string()));
+    }
+    if request.headline.chars().count() > MAX_HEADLINE_LEN {
+        return Err(CanisterError::InvalidInput(format!("Headline exceeds {} characters", MAX_HEADLINE_LEN)));
+    }
+    if request.body.chars().count() > MAX_BODY_LEN {
+        return Err(CanisterError::InvalidInput(format!("Body exceeds {} characters", MAX_BODY_LEN)));
+    }
+    if request.source.is_empty() || request.source.chars().count() > MAX_SOURCE_LEN {
+        return Err(CanisterError::InvalidInput(format!("Source must be 1-{} characters", MAX_SOURCE_LEN)));
+    }
+    if request.published_at == 0 {
+        return Err(CanisterError::InvalidInput("published_at must be set".to_string()));
+    }
+
+    request.tags = normalize_tags(&request.tags);
+    Ok(request)
+}
+
+// Lowercased, trimmed and de-duplicated; blank and over-long tags are dropped
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || tag.chars().count() > MAX_TAG_LEN || normalized.contains(&tag) {
+            continue;
+        }
+        normalized.push(tag);
+        if normalized.len() == MAX_TAGS {
+            break;
+        }
+    }
+    normalized
+}
+
+// The same story re-sent by a feed keeps its headline and publish time
+fn is_duplicate(item: &NewsItem, request: &NewsItemRequest) -> bool {
+    item.symbol == request.symbol
+        && item.published_at == request.published_at
+        && item.headline.eq_ignore_ascii_case(&request.headline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headline: &str) -> NewsItemRequest {
+        NewsItemRequest {
+            symbol: " reliance ".to_string(),
+            headline: headline.to_string(),
+            body: String::new(),
+            source: "Exchange filing".to_string(),
+            published_at: 1_700_000_000_000_000_000,
+            sentiment: NewsSentiment::Neutral,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let validated = validate_request(request("  Board approves buyback  ")).unwrap();
+        assert_eq!(validated.symbol, "RELIANCE");
+        assert_eq!(validated.headline, "Board approves buyback");
+
+        assert!(validate_request(request("   ")).is_err());
+        assert!(validate_request(request(&"x".repeat(MAX_HEADLINE_LEN + 1))).is_err());
+
+        let mut unsourced = request("Board approves buyback");
+        unsourced.source = " ".to_string();
+        assert!(validate_request(unsourced).is_err());
+    }
+
+    #[test]
+    fn test_fit_provider_item() {
+        let mut long = request(&"x".repeat(MAX_HEADLINE_LEN + 10));
+        long.body = "é".repeat(MAX_BODY_LEN + 1);
+        let fitted = fit_provider_item(long).unwrap();
+        assert_eq!(fitted.headline.chars().count(), MAX_HEADLINE_LEN);
+        assert_eq!(fitted.body.chars().count(), MAX_BODY_LEN);
+
+        let mut undated = request("Board approves buyback");
+        undated.published_at = 0;
+        assert!(fit_provider_item(undated).is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags: Vec<String> = ["Earnings", " earnings ", "", "Q3", &"t".repeat(MAX_TAG_LEN + 1)]
+            .iter()
+            .map(|tag| tag.to_string())
+            .collect();
+        assert_eq!(normalize_tags(&tags), vec!["earnings".to_string(), "q3".to_string()]);
+
+        let many: Vec<String> = (0..20).map(|i| format!("tag{}", i)).collect();
+        assert_eq!(normalize_tags(&many).len(), MAX_TAGS);
+    }
+
+    #[test]
+    fn test_is_duplicate() {
+        let validated = validate_request(request("Board approves buyback")).unwrap();
+        let item = NewsItem {
+            id: "news_1".to_string(),
+            symbol: validated.symbol.clone(),
+            headline: validated.headline.clone(),
+            body: String::new(),
+            source: validated.source.clone(),
+            published_at: validated.published_at,
+            sentiment: NewsSentiment::Neutral,
+            tags: Vec::new(),
+            ingested_at: 0,
+        };
+
+        let mut resent = validated.clone();
+        resent.headline = "BOARD APPROVES BUYBACK".to_string();
+        assert!(is_duplicate(&item, &resent));
+
+        let mut later = validated;
+        later.published_at += 1;
+        assert!(!is_duplicate(&item, &later));
+    }
+}
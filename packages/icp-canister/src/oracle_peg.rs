@@ -0,0 +1,147 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::fx;
+use crate::instruments::{INR, USD};
+use crate::utils::NANOS_PER_SECOND;
+
+const MAX_SPREAD_PERCENT: f64 = 20.0;
+
+pub fn get_peg_config() -> PegConfig {
+    storage::get_peg_config()
+}
+
+// Replace the peg configuration (controllers only)
+pub fn set_peg_config(config: PegConfig) -> CanisterResult<PegConfig> {
+    auth::verify_controller()?;
+
+    let mut config = config;
+    config.reference_symbol = config.reference_symbol.trim().to_uppercase();
+
+    if config.reference_symbol.is_empty() {
+        return Err(CanisterError::InvalidInput("Peg reference symbol cannot be empty".to_string()));
+    }
+    if config.reference_units_per_token <= 0.0 || !config.reference_units_per_token.is_finite() {
+        return Err(CanisterError::InvalidInput("Reference units per token must be positive".to_string()));
+    }
+    if !(0.0..=MAX_SPREAD_PERCENT).contains(&config.spread_percent) {
+        return Err(CanisterError::InvalidInput(format!("Spread must be between 0 and {}%", MAX_SPREAD_PERCENT)));
+    }
+    if config.max_staleness_seconds == 0 {
+        return Err(CanisterError::InvalidInput("Staleness window must be positive".to_string()));
+    }
+
+    storage::set_peg_config(config.clone());
+
+    Ok(config)
+}
+
+// Rate for a rupee/token exchange in the configured mode. `rupees_to_tokens`
+// gives tokens per rupee; otherwise rupees per token.
+pub fn rupee_token_rate(rupees_to_tokens: bool, now: u64) -> CanisterResult<(f64, RateSource)> {
+    let config = storage::get_peg_config();
+
+    if config.mode == ExchangeRateMode::Fixed {
+        return Ok((fixed_rate(rupees_to_tokens), RateSource::Fixed));
+    }
+
+    match oracle_mid_rate(&config, now) {
+        Ok(mid) => {
+            let mid = if rupees_to_tokens { mid } else { 1.0 / mid };
+            Ok((apply_spread(mid, config.spread_percent), RateSource::Oracle))
+        }
+        Err(reason) => match config.fallback {
+            PegFallback::FixedRate => {
+                ic_cdk::println!("⚠️ Oracle peg unavailable ({}), using fixed rate", reason);
+                Ok((fixed_rate(rupees_to_tokens), RateSource::OracleFallback))
+            }
+            PegFallback::Reject => Err(CanisterError::ExchangeRateFailed),
+        },
+    }
+}
+
+fn fixed_rate(rupees_to_tokens: bool) -> f64 {
+    let rate = storage::get_exchange_rate();
+    if rupees_to_tokens { rate } else { 1.0 / rate }
+}
+
+// Tokens per rupee at the mid price, from a reference feed and the USD/INR
+// rate the rest of the canister converts with, both within the peg's
+// staleness window
+fn oracle_mid_rate(config: &PegConfig, now: u64) -> Result<f64, String> {
+    let reference_usd = fresh_price(&config.reference_symbol, config.max_staleness_seconds, now)?;
+    let usd_inr = fx::rate(USD, INR, now).map_err(|e| e.to_string())?;
+    if is_stale(usd_inr.updated_at, now, config.max_staleness_seconds) {
+        return Err("USD/INR rate is stale".to_string());
+    }
+    let rupees_per_usd = usd_inr.rate;
+
+    peg_mid_rate(reference_usd, rupees_per_usd, config.reference_units_per_token)
+        .ok_or_else(|| "feeds produced an invalid rate".to_string())
+}
+
+fn fresh_price(symbol: &str, max_staleness_seconds: u64, now: u64) -> Result<f64, String> {
+    let price = storage::get_price_feed(symbol)
+        .ok_or_else(|| format!("no {} price", symbol))?;
+    let updated_at = storage::get_price_feed_updated_at(symbol)
+        .ok_or_else(|| format!("no {} timestamp", symbol))?;
+
+    if is_stale(updated_at, now, max_staleness_seconds) {
+        return Err(format!("{} price is stale", symbol));
+    }
+
+    Ok(price)
+}
+
+fn is_stale(updated_at: u64, now: u64, max_staleness_seconds: u64) -> bool {
+    now.saturating_sub(updated_at) > max_staleness_seconds.saturating_mul(NANOS_PER_SECOND)
+}
+
+// A token is worth `reference_units_per_token` of the reference asset, so its
+// rupee price is reference USD price x rupees per USD x units.
+fn peg_mid_rate(reference_usd: f64, rupees_per_usd: f64, reference_units_per_token: f64) -> Option<f64> {
+    let token_price_rupees = reference_usd * rupees_per_usd * reference_units_per_token;
+    if token_price_rupees > 0.0 && token_price_rupees.is_finite() {
+        Some(1.0 / token_price_rupees)
+    } else {
+        None
+    }
+}
+
+// The exchanger keeps half the spread on each side
+fn apply_spread(mid: f64, spread_percent: f64) -> f64 {
+    mid * (1.0 - spread_percent / 200.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peg_mid_rate() {
+        // ICP at $10, 83 rupees per dollar, 1 token = 0.01 ICP => token costs 8.3 rupees
+        let mid = peg_mid_rate(10.0, 83.0, 0.01).unwrap();
+        assert!((1.0 / mid - 8.3).abs() < 1e-9);
+
+        assert!(peg_mid_rate(0.0, 83.0, 0.01).is_none());
+    }
+
+    #[test]
+    fn test_spread_charged_both_ways() {
+        let buy = apply_spread(0.1, 2.0);
+        let sell = apply_spread(10.0, 2.0);
+        assert!((buy - 0.099).abs() < 1e-12);
+        assert!((sell - 9.9).abs() < 1e-12);
+        // A round trip always loses the full spread
+        assert!(buy * sell < 1.0);
+    }
+
+    #[test]
+    fn test_staleness_guard() {
+        let now = 1_000 * NANOS_PER_SECOND;
+        assert!(!is_stale(now - 60 * NANOS_PER_SECOND, now, 900));
+        assert!(is_stale(now - 901 * NANOS_PER_SECOND, now, 900));
+        assert!(!is_stale(now, now, 900));
+    }
+}
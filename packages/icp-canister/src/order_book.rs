@@ -0,0 +1,516 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::currency;
+use crate::savings;
+use crate::sse;
+
+// Order book configuration
+const QUANTITY_EPSILON: f64 = 1e-9;
+const MAX_OPEN_BOOK_ORDERS_PER_WALLET: usize = 100;
+const DEFAULT_DEPTH: usize = 20;
+const MAX_DEPTH: usize = 100;
+const DEFAULT_TAPE_LENGTH: usize = 50;
+const MAX_TAPE_LENGTH: usize = 500;
+
+// A fill against one resting order, at the resting order's price
+#[derive(Debug, PartialEq)]
+struct Fill {
+    resting_index: usize,
+    price: f64,
+    quantity: f64,
+}
+
+// Place a limit order on the base/quote book. It trades against the best
+// opposing prices first (oldest order first within a level) and any remainder
+// rests. Buys reserve quote at the limit price and sells reserve base.
+pub fn place_book_order(wallet_address: String, request: BookOrderRequest) -> CanisterResult<BookOrderResult> {
+    auth::verify_session(&wallet_address)?;
+
+    let (base, quote) = validate_instrument(&request.base, &request.quote)?;
+    if request.price <= 0.0 || !request.price.is_finite() {
+        return Err(CanisterError::InvalidAmount);
+    }
+    let quantity = currency::round_down(request.quantity, currency::decimals_of(&base));
+    if quantity <= 0.0 || !quantity.is_finite() {
+        return Err(CanisterError::InvalidAmount);
+    }
+
+    let open_orders = storage::get_book_orders_for_wallet(&wallet_address)
+        .iter()
+        .filter(|order| is_resting(order))
+        .count();
+    if open_orders >= MAX_OPEN_BOOK_ORDERS_PER_WALLET {
+        return Err(CanisterError::RateLimited("Too many open order book orders".to_string()));
+    }
+
+    let mut ledger = Ledger::default();
+    match request.side {
+        TradeSide::Buy => ledger.adjust(&wallet_address, &quote, -utils::safe_multiply(request.price, quantity)?)?,
+        TradeSide::Sell => ledger.adjust(&wallet_address, &base, -quantity)?,
+    }
+
+    let now = ic_cdk::api::time();
+    let mut order = BookOrder {
+        id: utils::generate_book_order_id(),
+        wallet_address,
+        base: base.clone(),
+        quote: quote.clone(),
+        side: request.side,
+        price: request.price,
+        quantity,
+        remaining: quantity,
+        sequence: storage::next_book_sequence(),
+        status: BookOrderStatus::Open,
+        status_reason: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let mut book: Vec<BookOrder> = storage::get_resting_book_orders(&base, &quote)
+        .into_iter()
+        .filter(|resting| resting.side != order.side)
+        .collect();
+    sort_by_priority(&mut book);
+
+    let (fills, self_trade) = match_order(&order, &book);
+
+    let first_trade_id = storage::next_book_trade_id();
+    let mut trades = Vec::new();
+    for fill in &fills {
+        let trade_id = first_trade_id + trades.len() as u64;
+        let resting = &mut book[fill.resting_index];
+        trades.push(settle_fill(&mut ledger, &mut order, resting, fill.price, fill.quantity, trade_id, now)?);
+    }
+
+    if self_trade && order.remaining > 0.0 {
+        // Self-trade prevention cancels the incoming remainder
+        release_remaining(&mut ledger, &order)?;
+        order.remaining = 0.0;
+        order.status = BookOrderStatus::Cancelled;
+        order.status_reason = Some("Self-trade prevented".to_string());
+    }
+
+    // Every fill settled: write balances, orders and the tape together
+    ledger.commit()?;
+    for fill in &fills {
+        storage::set_book_order(&book[fill.resting_index]);
+    }
+    storage::set_book_order(&order);
+    for trade in &trades {
+        storage::append_book_trade(trade);
+    }
+
+    for trade in &trades {
+        publish(&base, &quote, trade_event(trade));
+    }
+    publish_depth(&base, &quote);
+
+    Ok(BookOrderResult { order, trades })
+}
+
+// Cancel a resting order and release its remaining reservation
+pub fn cancel_book_order(wallet_address: String, order_id: String) -> CanisterResult<BookOrder> {
+    auth::verify_session(&wallet_address)?;
+
+    let mut order = storage::get_book_order(&order_id)
+        .ok_or(CanisterError::NotFound(format!("Order {}", order_id)))?;
+
+    if order.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+    if !is_resting(&order) {
+        return Err(CanisterError::InvalidInput("Only resting orders can be cancelled".to_string()));
+    }
+
+    let mut ledger = Ledger::default();
+    release_remaining(&mut ledger, &order)?;
+    ledger.commit()?;
+    order.remaining = 0.0;
+    order.status = BookOrderStatus::Cancelled;
+    order.updated_at = ic_cdk::api::time();
+    storage::set_book_order(&order);
+
+    publish_depth(&order.base, &order.quote);
+
+    Ok(order)
+}
+
+// A wallet's order book orders, most recently placed first
+pub fn get_book_orders(wallet_address: String) -> Vec<BookOrder> {
+    let mut orders = storage::get_book_orders_for_wallet(&wallet_address);
+    orders.sort_by_key(|order| std::cmp::Reverse(order.sequence));
+    orders
+}
+
+// Aggregated bid/ask depth, best prices first
+pub fn get_order_book(base: String, quote: String, depth: Option<u32>) -> CanisterResult<OrderBookSnapshot> {
+    let (base, quote) = validate_instrument(&base, &quote)?;
+    let depth = depth.map(|depth| depth as usize).unwrap_or(DEFAULT_DEPTH).min(MAX_DEPTH);
+    Ok(snapshot(&base, &quote, depth))
+}
+
+// The trade tape, newest first
+pub fn get_book_trades(base: String, quote: String, limit: Option<u32>) -> CanisterResult<Vec<BookTrade>> {
+    let (base, quote) = validate_instrument(&base, &quote)?;
+    let limit = limit.map(|limit| limit as usize).unwrap_or(DEFAULT_TAPE_LENGTH).min(MAX_TAPE_LENGTH);
+    Ok(storage::get_recent_book_trades(&base, &quote, limit))
+}
+
+fn validate_instrument(base: &str, quote: &str) -> CanisterResult<(String, String)> {
+    let base = currency::normalize_symbol(base);
+    let quote = currency::normalize_symbol(quote);
+    if base == quote {
+        return Err(CanisterError::InvalidInput("Base and quote must differ".to_string()));
+    }
+    currency::validate_currency(&base)?;
+    currency::validate_currency(&quote)?;
+    Ok((base, quote))
+}
+
+fn snapshot(base: &str, quote: &str, depth: usize) -> OrderBookSnapshot {
+    let (mut bids, mut asks): (Vec<BookOrder>, Vec<BookOrder>) = storage::get_resting_book_orders(base, quote)
+        .into_iter()
+        .partition(|order| order.side == TradeSide::Buy);
+    sort_by_priority(&mut bids);
+    sort_by_priority(&mut asks);
+
+    OrderBookSnapshot {
+        base: base.to_string(),
+        quote: quote.to_string(),
+        bids: aggregate_depth(&bids, depth),
+        asks: aggregate_depth(&asks, depth),
+        last_trade_price: storage::get_recent_book_trades(base, quote, 1).first().map(|trade| trade.price),
+        timestamp: ic_cdk::api::time(),
+    }
+}
+
+// Move the assets for one fill on the ledger and update both orders. The
+// buyer reserved quote at their own limit, so any price improvement is refunded.
+fn settle_fill(
+    ledger: &mut Ledger,
+    incoming: &mut BookOrder,
+    resting: &mut BookOrder,
+    price: f64,
+    quantity: f64,
+    trade_id: u64,
+    now: u64,
+) -> CanisterResult<BookTrade> {
+    let (buy, sell) = match incoming.side {
+        TradeSide::Buy => (&*incoming, &*resting),
+        TradeSide::Sell => (&*resting, &*incoming),
+    };
+
+    let proceeds = utils::safe_multiply(price, quantity)?;
+    let improvement = utils::safe_multiply(buy.price - price, quantity)?;
+
+    ledger.adjust(&buy.wallet_address, &buy.base, quantity)?;
+    if improvement > 0.0 {
+        ledger.adjust(&buy.wallet_address, &buy.quote, improvement)?;
+    }
+    ledger.adjust(&sell.wallet_address, &sell.quote, proceeds)?;
+    if sell.quote == currency::RUPEES {
+        ledger.sweeps.push((sell.wallet_address.clone(), proceeds));
+    }
+
+    let trade = BookTrade {
+        id: trade_id,
+        base: incoming.base.clone(),
+        quote: incoming.quote.clone(),
+        price,
+        quantity,
+        buy_order_id: buy.id.clone(),
+        sell_order_id: sell.id.clone(),
+        aggressor_side: incoming.side.clone(),
+        timestamp: now,
+    };
+
+    apply_fill(incoming, quantity, now);
+    apply_fill(resting, quantity, now);
+
+    Ok(trade)
+}
+
+fn apply_fill(order: &mut BookOrder, quantity: f64, now: u64) {
+    order.remaining -= quantity;
+    if order.remaining <= QUANTITY_EPSILON {
+        order.remaining = 0.0;
+        order.status = BookOrderStatus::Filled;
+    } else {
+        order.status = BookOrderStatus::PartiallyFilled;
+    }
+    order.updated_at = now;
+}
+
+fn release_remaining(ledger: &mut Ledger, order: &BookOrder) -> CanisterResult<()> {
+    if order.remaining <= 0.0 {
+        return Ok(());
+    }
+    match order.side {
+        TradeSide::Buy => ledger.adjust(&order.wallet_address, &order.quote, utils::safe_multiply(order.price, order.remaining)?),
+        TradeSide::Sell => ledger.adjust(&order.wallet_address, &order.base, order.remaining),
+    }
+}
+
+// Balance changes for every wallet an order touches. Nothing is written until
+// `commit`, so a failure part way through matching leaves no reservation,
+// fill or refund applied.
+#[derive(Default)]
+struct Ledger {
+    users: Vec<(UserData, PendingWrites)>,
+    sweeps: Vec<(String, f64)>, // rupee proceeds offered to savings goals on commit
+}
+
+impl Ledger {
+    fn user(&mut self, wallet_address: &str) -> CanisterResult<&mut (UserData, PendingWrites)> {
+        let index = match self.users.iter().position(|(user_data, _)| user_data.wallet_address == wallet_address) {
+            Some(index) => index,
+            None => {
+                let user_data = storage::get_user_data(wallet_address)
+                    .ok_or(CanisterError::UserNotFound)?;
+                self.users.push((user_data, PendingWrites::default()));
+                self.users.len() - 1
+            }
+        };
+        Ok(&mut self.users[index])
+    }
+
+    // Credit (positive) or debit (negative) a wallet balance
+    fn adjust(&mut self, wallet_address: &str, symbol: &str, amount: f64) -> CanisterResult<()> {
+        let (user_data, writes) = self.user(wallet_address)?;
+        if amount >= 0.0 {
+            currency::credit(user_data, writes, symbol, amount)?;
+        } else {
+            currency::debit(user_data, writes, symbol, -amount)?;
+        }
+        Ok(())
+    }
+
+    fn commit(mut self) -> CanisterResult<()> {
+        for (wallet_address, amount) in std::mem::take(&mut self.sweeps) {
+            let (user_data, writes) = self.user(&wallet_address)?;
+            savings::sweep_incoming_credit(user_data, writes, amount)?;
+        }
+        for (user_data, writes) in self.users {
+            let wallet_address = user_data.wallet_address.clone();
+            storage::commit_user_data(&wallet_address, user_data, writes)?;
+        }
+        Ok(())
+    }
+}
+
+fn publish_depth(base: &str, quote: &str) {
+    let book = snapshot(base, quote, DEFAULT_DEPTH);
+    publish(base, quote, serde_json::json!({
+        "type": "depth_update",
+        "base": book.base,
+        "quote": book.quote,
+        "bids": book.bids,
+        "asks": book.asks,
+        "last_trade_price": book.last_trade_price,
+        "timestamp": book.timestamp
+    }));
+}
+
+fn trade_event(trade: &BookTrade) -> serde_json::Value {
+    serde_json::json!({
+        "type": "trade",
+        "trade_id": trade.id,
+        "base": trade.base,
+        "quote": trade.quote,
+        "price": trade.price,
+        "quantity": trade.quantity,
+        "aggressor_side": format!("{:?}", trade.aggressor_side),
+        "timestamp": trade.timestamp
+    })
+}
+
+// Events go to the instrument's room, e.g. `orderbook_gold_rupees`
+fn publish(base: &str, quote: &str, event_data: serde_json::Value) {
+    let room_id = format!("orderbook_{}_{}", base, quote);
+    let result = sse::create_room(&room_id)
+        .and_then(|_| sse::broadcast_event(&room_id, SseEventType::RoomState, event_data));
+    if let Err(e) = result {
+        ic_cdk::println!("⚠️ Failed to publish order book event for {}: {:?}", room_id, e);
+    }
+}
+
+fn is_resting(order: &BookOrder) -> bool {
+    matches!(order.status, BookOrderStatus::Open | BookOrderStatus::PartiallyFilled)
+}
+
+// Best price first, then oldest first. Expects orders from one side only.
+fn sort_by_priority(orders: &mut [BookOrder]) {
+    orders.sort_by(|a, b| {
+        let by_price = match a.side {
+            TradeSide::Buy => b.price.total_cmp(&a.price),
+            TradeSide::Sell => a.price.total_cmp(&b.price),
+        };
+        by_price.then(a.sequence.cmp(&b.sequence))
+    });
+}
+
+fn crosses(incoming: &BookOrder, resting_price: f64) -> bool {
+    match incoming.side {
+        TradeSide::Buy => resting_price <= incoming.price,
+        TradeSide::Sell => resting_price >= incoming.price,
+    }
+}
+
+// Walk the opposing side in priority order. Matching stops at the first
+// price that does not cross, or at the incoming wallet's own resting order;
+// the flag reports the latter.
+fn match_order(incoming: &BookOrder, book: &[BookOrder]) -> (Vec<Fill>, bool) {
+    let mut fills = Vec::new();
+    let mut remaining = incoming.remaining;
+
+    for (resting_index, resting) in book.iter().enumerate() {
+        if remaining <= QUANTITY_EPSILON || !crosses(incoming, resting.price) {
+            break;
+        }
+        if resting.wallet_address == incoming.wallet_address {
+            return (fills, true);
+        }
+
+        let quantity = remaining.min(resting.remaining);
+        fills.push(Fill { resting_index, price: resting.price, quantity });
+        remaining -= quantity;
+    }
+
+    (fills, false)
+}
+
+// Sum sorted orders into price levels, keeping at most `depth` levels
+fn aggregate_depth(orders: &[BookOrder], depth: usize) -> Vec<DepthLevel> {
+    let mut levels: Vec<DepthLevel> = Vec::new();
+    for order in orders {
+        match levels.last_mut() {
+            Some(level) if level.price == order.price => {
+                level.quantity += order.remaining;
+                level.order_count += 1;
+            }
+            _ => {
+                if levels.len() == depth {
+                    break;
+                }
+                levels.push(DepthLevel { price: order.price, quantity: order.remaining, order_count: 1 });
+            }
+        }
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(wallet_address: &str, side: TradeSide, price: f64, quantity: f64, sequence: u64) -> BookOrder {
+        BookOrder {
+            id: format!("bo_{}", sequence),
+            wallet_address: wallet_address.to_string(),
+            base: "gold".to_string(),
+            quote: "rupees".to_string(),
+            side,
+            price,
+            quantity,
+            remaining: quantity,
+            sequence,
+            status: BookOrderStatus::Open,
+            status_reason: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_price_time_priority() {
+        let mut asks = vec![
+            order("0xa", TradeSide::Sell, 101.0, 1.0, 1),
+            order("0xb", TradeSide::Sell, 100.0, 1.0, 3),
+            order("0xc", TradeSide::Sell, 100.0, 1.0, 2),
+        ];
+        sort_by_priority(&mut asks);
+        let sequences: Vec<u64> = asks.iter().map(|ask| ask.sequence).collect();
+        assert_eq!(sequences, vec![2, 3, 1]);
+
+        let mut bids = vec![
+            order("0xa", TradeSide::Buy, 99.0, 1.0, 1),
+            order("0xb", TradeSide::Buy, 100.0, 1.0, 2),
+        ];
+        sort_by_priority(&mut bids);
+        assert_eq!(bids[0].price, 100.0);
+    }
+
+    #[test]
+    fn test_partial_fills_stop_at_limit() {
+        let asks = vec![
+            order("0xa", TradeSide::Sell, 100.0, 2.0, 1),
+            order("0xb", TradeSide::Sell, 101.0, 5.0, 2),
+            order("0xc", TradeSide::Sell, 103.0, 5.0, 3),
+        ];
+        let incoming = order("0xd", TradeSide::Buy, 102.0, 4.0, 4);
+
+        let (fills, self_trade) = match_order(&incoming, &asks);
+        assert!(!self_trade);
+        assert_eq!(fills, vec![
+            Fill { resting_index: 0, price: 100.0, quantity: 2.0 },
+            Fill { resting_index: 1, price: 101.0, quantity: 2.0 },
+        ]);
+
+        let mut resting = asks[1].clone();
+        apply_fill(&mut resting, 2.0, 10);
+        assert_eq!(resting.status, BookOrderStatus::PartiallyFilled);
+        assert_eq!(resting.remaining, 3.0);
+    }
+
+    #[test]
+    fn test_self_trade_prevention() {
+        let asks = vec![
+            order("0xa", TradeSide::Sell, 100.0, 1.0, 1),
+            order("0xd", TradeSide::Sell, 100.5, 1.0, 2),
+            order("0xb", TradeSide::Sell, 101.0, 1.0, 3),
+        ];
+        let incoming = order("0xd", TradeSide::Buy, 105.0, 3.0, 4);
+
+        let (fills, self_trade) = match_order(&incoming, &asks);
+        assert!(self_trade);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].resting_index, 0);
+    }
+
+    #[test]
+    fn test_aggregate_depth() {
+        let bids = vec![
+            order("0xa", TradeSide::Buy, 100.0, 1.0, 1),
+            order("0xb", TradeSide::Buy, 100.0, 2.5, 2),
+            order("0xc", TradeSide::Buy, 99.0, 1.0, 3),
+            order("0xd", TradeSide::Buy, 98.0, 1.0, 4),
+        ];
+
+        let levels = aggregate_depth(&bids, 2);
+        assert_eq!(levels, vec![
+            DepthLevel { price: 100.0, quantity: 3.5, order_count: 2 },
+            DepthLevel { price: 99.0, quantity: 1.0, order_count: 1 },
+        ]);
+    }
+
+    #[test]
+    fn test_resting_index_follows_status() {
+        let mut bid = order("0xa", TradeSide::Buy, 100.0, 1.0, 1);
+        let ask = order("0xb", TradeSide::Sell, 101.0, 1.0, 2);
+        storage::set_book_order(&bid);
+        storage::set_book_order(&ask);
+        let resting: Vec<u64> = storage::get_resting_book_orders("gold", "rupees").iter().map(|order| order.sequence).collect();
+        assert_eq!(resting, vec![1, 2]);
+
+        bid.remaining = 0.0;
+        bid.status = BookOrderStatus::Filled;
+        storage::set_book_order(&bid);
+        let resting: Vec<u64> = storage::get_resting_book_orders("gold", "rupees").iter().map(|order| order.sequence).collect();
+        assert_eq!(resting, vec![2]);
+        assert!(storage::get_resting_book_orders("silver", "rupees").is_empty());
+        assert_eq!(storage::get_book_orders_for_wallet("0xa").len(), 1);
+    }
+}
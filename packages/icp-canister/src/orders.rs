@@ -0,0 +1,374 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::currency;
+use crate::trading;
+use crate::stock_sse;
+use crate::instruments;
+use crate::market_calendar;
+use crate::indices;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// Fills happen on price updates; the sweeper only has to release day orders
+// when no new price arrives before the close.
+const EXPIRY_SWEEP_INTERVAL_SECONDS: u64 = 5 * 60;
+const MAX_OPEN_ORDERS_PER_WALLET: usize = 50;
+
+thread_local! {
+    static EXPIRY_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_expiry_sweeper() {
+    EXPIRY_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(EXPIRY_SWEEP_INTERVAL_SECONDS), || {
+            let expired = expire_orders(ic_cdk::api::time());
+            if expired > 0 {
+                ic_cdk::println!("⌛ Expired {} stock orders", expired);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Place a conditional order. Buys reserve rupees at the trigger price and
+// sells reserve shares until the order closes. An order whose condition
+// already holds at the current price fills straight away.
+pub fn place_stock_order(wallet_address: String, request: StockOrderRequest) -> CanisterResult<StockOrder> {
+    auth::verify_session(&wallet_address)?;
+
+    let stock_id = trading::normalize_stock_id(&request.stock_id)?;
+    indices::ensure_not_index(&stock_id)?;
+    instruments::ensure_tradable(&stock_id)?;
+    validate_request(&request)?;
+    instruments::validate_order(&instruments::lookup(&stock_id), request.quantity, Some(request.trigger_price))?;
+
+    let open_orders = storage::get_stock_orders_for_wallet(&wallet_address)
+        .iter()
+        .filter(|order| order.status == StockOrderStatus::Open)
+        .count();
+    if open_orders >= MAX_OPEN_ORDERS_PER_WALLET {
+        return Err(CanisterError::RateLimited("Too many open orders".to_string()));
+    }
+
+    let mut user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let mut writes = PendingWrites::default();
+    let reserved_rupees = match request.side {
+        TradeSide::Buy => {
+            let (_, fx_rate) = trading::rupee_rate(&stock_id, ic_cdk::api::time())?;
+            let reserve = utils::safe_multiply(request.trigger_price * fx_rate, request.quantity as f64)?;
+            currency::debit(&mut user_data, &mut writes, currency::RUPEES, reserve)?;
+            reserve
+        }
+        TradeSide::Sell => {
+            let available = available_shares(&wallet_address, &stock_id);
+            if request.quantity > available {
+                return Err(CanisterError::InvalidInput(format!(
+                    "Cannot sell {} shares of {}, only {} available",
+                    request.quantity, stock_id, available
+                )));
+            }
+            0.0
+        }
+    };
+
+    let now = ic_cdk::api::time();
+    let expires_at = match request.time_in_force {
+        TimeInForce::GoodTillCancelled => None,
+        TimeInForce::Day => Some(end_of_day(&stock_id, now)?),
+    };
+    let order = StockOrder {
+        id: utils::generate_order_id(),
+        wallet_address: wallet_address.clone(),
+        stock_id,
+        side: request.side,
+        order_type: request.order_type,
+        quantity: request.quantity,
+        trigger_price: request.trigger_price,
+        expires_at,
+        time_in_force: request.time_in_force,
+        reserved_rupees,
+        status: StockOrderStatus::Open,
+        created_at: now,
+        closed_at: None,
+        fill_price: None,
+        trade_id: None,
+        status_reason: None,
+    };
+
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::set_stock_order(&order);
+
+    match trading::current_price(&order.stock_id) {
+        Ok(price) if is_triggered(&order, price) && market_calendar::is_trading(&order.stock_id, now) => {
+            Ok(fill_order(order, price, now))
+        }
+        _ => Ok(order),
+    }
+}
+
+// Cancel an open order and release whatever it reserved
+pub fn cancel_stock_order(wallet_address: String, order_id: String) -> CanisterResult<StockOrder> {
+    auth::verify_session(&wallet_address)?;
+
+    let order = storage::get_stock_order(&order_id)
+        .ok_or(CanisterError::NotFound(format!("Order {}", order_id)))?;
+
+    if order.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+    if order.status != StockOrderStatus::Open {
+        return Err(CanisterError::InvalidInput("Only open orders can be cancelled".to_string()));
+    }
+
+    close_unfilled(order, StockOrderStatus::Cancelled, ic_cdk::api::time())
+}
+
+// A wallet's orders, most recently placed first
+pub fn get_stock_orders(wallet_address: String) -> Vec<StockOrder> {
+    let mut orders = storage::get_stock_orders_for_wallet(&wallet_address);
+    orders.sort_by_key(|order| std::cmp::Reverse(order.created_at));
+    orders
+}
+
+// Shares held but not committed to open sell orders
+pub fn available_shares(wallet_address: &str, stock_id: &str) -> u64 {
+    let held = storage::get_holding(wallet_address, stock_id)
+        .map(|holding| holding.quantity)
+        .unwrap_or(0);
+    let reserved: u64 = storage::get_open_stock_orders(stock_id)
+        .iter()
+        .filter(|order| order.wallet_address == wallet_address && order.side == TradeSide::Sell)
+        .map(|order| order.quantity)
+        .sum();
+
+    held.saturating_sub(reserved)
+}
+
+// Evaluate every open order on a stock against a new price. Returns the number
+// filled. Orders stay queued while the stock's market is closed.
+pub fn evaluate_orders(stock_id: &str, price: f64, now: u64) -> usize {
+    if price <= 0.0 || !price.is_finite() {
+        return 0;
+    }
+
+    let trading = market_calendar::is_trading(stock_id, now);
+    let mut filled = 0;
+    for order in storage::get_open_stock_orders(stock_id) {
+        if is_expired(&order, now) {
+            if let Err(e) = close_unfilled(order, StockOrderStatus::Expired, now) {
+                ic_cdk::println!("⚠️ Failed to expire order: {}", e);
+            }
+        } else if trading && is_triggered(&order, price) && fill_order(order, price, now).status == StockOrderStatus::Filled {
+            filled += 1;
+        }
+    }
+
+    filled
+}
+
+// Evaluate orders across a refreshed market snapshot
+pub fn evaluate_market(market_data: &HashMap<String, Stock>, now: u64) -> usize {
+    market_data
+        .iter()
+        .map(|(stock_id, stock)| evaluate_orders(stock_id, stock.current_price, now))
+        .sum()
+}
+
+// Expire every open order past its expiry. Returns the number expired.
+pub fn expire_orders(now: u64) -> usize {
+    let mut expired = 0;
+    for order in storage::get_expired_stock_orders(now) {
+        match close_unfilled(order, StockOrderStatus::Expired, now) {
+            Ok(_) => expired += 1,
+            Err(e) => ic_cdk::println!("⚠️ Failed to expire order: {}", e),
+        }
+    }
+    expired
+}
+
+// Settle a triggered order at the market price. Buy reservations are released
+// first so the trade pays the actual fill price; a failed fill leaves the order
+// rejected with nothing reserved.
+fn fill_order(mut order: StockOrder, price: f64, now: u64) -> StockOrder {
+    let result = release_reservation(&mut order).and_then(|_| match order.side {
+        TradeSide::Buy => trading::execute_buy(&order.wallet_address, &order.stock_id, order.quantity, price),
+        TradeSide::Sell => trading::execute_sell(&order.wallet_address, &order.stock_id, order.quantity, price),
+    });
+
+    order.closed_at = Some(now);
+    match result {
+        Ok(trade) => {
+            order.status = StockOrderStatus::Filled;
+            order.fill_price = Some(price); // quote currency, like trigger_price
+            order.trade_id = Some(trade.id);
+        }
+        Err(e) => {
+            order.status = StockOrderStatus::Rejected;
+            order.status_reason = Some(e.to_string());
+        }
+    }
+
+    storage::set_stock_order(&order);
+
+    if order.status == StockOrderStatus::Filled {
+        // Nobody may be watching the room; the fill stands either way
+        let _ = stock_sse::broadcast_order_fill(&order);
+    }
+
+    order
+}
+
+// Cancel an order that can no longer be filled as placed, releasing its reservation
+pub fn cancel_unfillable(mut order: StockOrder, reason: &str, now: u64) -> CanisterResult<StockOrder> {
+    order.status_reason = Some(reason.to_string());
+    close_unfilled(order, StockOrderStatus::Cancelled, now)
+}
+
+fn close_unfilled(mut order: StockOrder, status: StockOrderStatus, now: u64) -> CanisterResult<StockOrder> {
+    release_reservation(&mut order)?;
+
+    order.status = status;
+    order.closed_at = Some(now);
+    storage::set_stock_order(&order);
+
+    Ok(order)
+}
+
+fn release_reservation(order: &mut StockOrder) -> CanisterResult<()> {
+    if order.reserved_rupees <= 0.0 {
+        return Ok(());
+    }
+
+    let mut user_data = storage::get_user_data(&order.wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let mut writes = PendingWrites::default();
+    currency::credit(&mut user_data, &mut writes, currency::RUPEES, order.reserved_rupees)?;
+    storage::commit_user_data(&order.wallet_address, user_data, writes)?;
+
+    order.reserved_rupees = 0.0;
+    Ok(())
+}
+
+fn validate_request(request: &StockOrderRequest) -> CanisterResult<()> {
+    trading::validate_order(request.quantity, request.trigger_price)?;
+    if request.order_type != StockOrderType::Limit && request.side != TradeSide::Sell {
+        return Err(CanisterError::InvalidInput("Stop-loss and take-profit orders must be sells".to_string()));
+    }
+    Ok(())
+}
+
+fn is_triggered(order: &StockOrder, price: f64) -> bool {
+    match (&order.order_type, &order.side) {
+        (StockOrderType::Limit, TradeSide::Buy) | (StockOrderType::StopLoss, _) => price <= order.trigger_price,
+        (StockOrderType::Limit, TradeSide::Sell) | (StockOrderType::TakeProfit, _) => price >= order.trigger_price,
+    }
+}
+
+fn is_expired(order: &StockOrder, now: u64) -> bool {
+    order.expires_at.is_some_and(|expires_at| expires_at <= now)
+}
+
+// Day orders last until the stock's exchange next closes: today's close
+// during or before the session, otherwise the next trading day's
+fn end_of_day(stock_id: &str, now: u64) -> CanisterResult<u64> {
+    let exchange = instruments::lookup(stock_id).exchange;
+    market_calendar::next_close(exchange, now)
+        .ok_or_else(|| CanisterError::MarketClosed(format!("No upcoming {:?} session for a day order", exchange)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: TradeSide, order_type: StockOrderType, trigger_price: f64) -> StockOrder {
+        StockOrder {
+            id: "ord_test".to_string(),
+            wallet_address: "0xabc".to_string(),
+            stock_id: "TCS".to_string(),
+            side,
+            order_type,
+            quantity: 10,
+            trigger_price,
+            time_in_force: TimeInForce::GoodTillCancelled,
+            reserved_rupees: 0.0,
+            status: StockOrderStatus::Open,
+            created_at: 0,
+            expires_at: None,
+            closed_at: None,
+            fill_price: None,
+            trade_id: None,
+            status_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_trigger_conditions() {
+        let limit_buy = order(TradeSide::Buy, StockOrderType::Limit, 100.0);
+        assert!(is_triggered(&limit_buy, 99.5));
+        assert!(!is_triggered(&limit_buy, 100.5));
+
+        let limit_sell = order(TradeSide::Sell, StockOrderType::Limit, 100.0);
+        assert!(is_triggered(&limit_sell, 100.0));
+        assert!(!is_triggered(&limit_sell, 99.0));
+
+        let stop_loss = order(TradeSide::Sell, StockOrderType::StopLoss, 90.0);
+        assert!(is_triggered(&stop_loss, 85.0));
+        assert!(!is_triggered(&stop_loss, 95.0));
+
+        let take_profit = order(TradeSide::Sell, StockOrderType::TakeProfit, 120.0);
+        assert!(is_triggered(&take_profit, 125.0));
+        assert!(!is_triggered(&take_profit, 110.0));
+    }
+
+    #[test]
+    fn test_day_orders_expire_at_the_close() {
+        // NSE closes at 15:30 IST, 10:00 UTC. Wednesday 10 January 2024:
+        let wednesday = utils::parse_utc_timestamp("2024-01-10T04:00:00Z").unwrap();
+        let close = utils::parse_utc_timestamp("2024-01-10T10:00:00Z").unwrap();
+        assert_eq!(end_of_day("TCS", wednesday).unwrap(), close);
+
+        // After the close, the order lasts through Thursday's session
+        let evening = utils::parse_utc_timestamp("2024-01-10T18:00:00Z").unwrap();
+        let next_close = utils::parse_utc_timestamp("2024-01-11T10:00:00Z").unwrap();
+        assert_eq!(end_of_day("TCS", evening).unwrap(), next_close);
+
+        let expires_at = close;
+
+        let mut day_order = order(TradeSide::Buy, StockOrderType::Limit, 100.0);
+        day_order.expires_at = Some(expires_at);
+        assert!(!is_expired(&day_order, expires_at - 1));
+        assert!(is_expired(&day_order, expires_at));
+        assert!(!is_expired(&order(TradeSide::Buy, StockOrderType::Limit, 100.0), u64::MAX));
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let mut request = StockOrderRequest {
+            stock_id: "TCS".to_string(),
+            side: TradeSide::Sell,
+            order_type: StockOrderType::StopLoss,
+            quantity: 5,
+            trigger_price: 3000.0,
+            time_in_force: TimeInForce::Day,
+        };
+        assert!(validate_request(&request).is_ok());
+
+        request.side = TradeSide::Buy;
+        assert!(validate_request(&request).is_err());
+
+        request.order_type = StockOrderType::Limit;
+        request.trigger_price = 0.0;
+        assert!(validate_request(&request).is_err());
+    }
+}
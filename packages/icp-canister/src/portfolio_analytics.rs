@@ -0,0 +1,273 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::trading;
+use crate::central_bank;
+use crate::instruments;
+use crate::http_client;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Portfolios are valued once a day; the timer checks hourly so a missed
+// tick (or an upgrade) only delays the next snapshot.
+const SNAPSHOT_CHECK_INTERVAL_SECONDS: u64 = 60 * 60;
+const SNAPSHOT_INTERVAL_NANOS: u64 = crate::utils::NANOS_PER_DAY;
+const PERIODS_PER_YEAR: f64 = 365.0;
+
+thread_local! {
+    static SNAPSHOT_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_snapshots() {
+    SNAPSHOT_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(SNAPSHOT_CHECK_INTERVAL_SECONDS), || {
+            let taken = snapshot_portfolios(ic_cdk::api::time());
+            if taken > 0 {
+                ic_cdk::println!("📸 Snapshotted {} portfolios", taken);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Value every wallet that has traded and is due a snapshot. Returns the number taken.
+pub fn snapshot_portfolios(now: u64) -> usize {
+    let mut taken = 0;
+
+    for wallet_address in storage::get_all_user_addresses() {
+        let last = storage::get_latest_portfolio_snapshot(&wallet_address);
+        if last.as_ref().is_some_and(|snapshot| now.saturating_sub(snapshot.timestamp) < SNAPSHOT_INTERVAL_NANOS) {
+            continue;
+        }
+        if storage::get_holdings_for_wallet(&wallet_address).is_empty() {
+            continue;
+        }
+
+        match snapshot_wallet(&wallet_address, last.map(|snapshot| snapshot.timestamp).unwrap_or(0), now) {
+            Ok(()) => taken += 1,
+            Err(e) => ic_cdk::println!("⚠️ Portfolio snapshot failed for {}: {}", wallet_address, e),
+        }
+    }
+
+    taken
+}
+
+fn snapshot_wallet(wallet_address: &str, since: u64, now: u64) -> CanisterResult<()> {
+    let portfolio = trading::get_portfolio(wallet_address.to_string())?;
+
+    storage::insert_portfolio_snapshot(&PortfolioSnapshot {
+        wallet_address: wallet_address.to_string(),
+        timestamp: now,
+        holdings_value: portfolio.holdings_value,
+        cost_basis: portfolio.cost_basis,
+        cash_balance: portfolio.cash_balance,
+        net_flow: net_flow(&storage::get_stock_trades(wallet_address), since, now),
+    });
+
+    Ok(())
+}
+
+// Valuation series within the window (defaults to all history)
+pub fn get_portfolio_history(wallet_address: String, start: Option<u64>, end: Option<u64>) -> Vec<PortfolioSnapshot> {
+    storage::get_portfolio_snapshots(&wallet_address, start.unwrap_or(0), end.unwrap_or(u64::MAX))
+}
+
+// Risk and return over the window, measured on the stock holdings. Sharpe
+// uses the central bank policy rate as the risk-free rate.
+pub fn get_portfolio_performance(wallet_address: String, start: Option<u64>, end: Option<u64>) -> CanisterResult<PortfolioPerformance> {
+    storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let snapshots = get_portfolio_history(wallet_address.clone(), start, end);
+    let (first, last) = match (snapshots.first(), snapshots.last()) {
+        (Some(first), Some(last)) if snapshots.len() >= 2 => (first.timestamp, last.timestamp),
+        _ => return Err(CanisterError::NotFound("Not enough portfolio history yet".to_string())),
+    };
+
+    let returns = period_returns(&snapshots);
+    let risk_free_rate_percent = central_bank::get_monetary_policy().policy_rate_percent;
+
+    Ok(PortfolioPerformance {
+        wallet_address,
+        start: first,
+        end: last,
+        snapshot_count: snapshots.len() as u32,
+        time_weighted_return_percent: time_weighted_return(&returns) * 100.0,
+        annualized_volatility_percent: http_client::annualized_volatility(&returns, PERIODS_PER_YEAR) * 100.0,
+        sharpe_ratio: sharpe_ratio(&returns, risk_free_rate_percent, PERIODS_PER_YEAR),
+        max_drawdown_percent: max_drawdown(&returns) * 100.0,
+        risk_free_rate_percent,
+    })
+}
+
+// Current holdings grouped by sector, largest first
+pub fn get_sector_allocation(wallet_address: String) -> CanisterResult<Vec<SectorAllocation>> {
+    let portfolio = trading::get_portfolio(wallet_address)?;
+    Ok(allocate_by_sector(&portfolio.positions))
+}
+
+// Rupees put into stocks (buys) less rupees taken out (sells) in (after, until]
+fn net_flow(trades: &[StockTrade], after: u64, until: u64) -> f64 {
+    trades
+        .iter()
+        .filter(|trade| trade.timestamp > after && trade.timestamp <= until)
+        .map(|trade| match trade.side {
+            TradeSide::Buy => trade.total,
+            TradeSide::Sell => -trade.total,
+        })
+        .sum()
+}
+
+// Return of each period between consecutive snapshots with flows treated as
+// happening at the end of the period, so buying or selling is not mistaken
+// for gains or losses. Periods starting from an empty portfolio are skipped.
+fn period_returns(snapshots: &[PortfolioSnapshot]) -> Vec<f64> {
+    snapshots
+        .windows(2)
+        .filter(|pair| pair[0].holdings_value > 0.0)
+        .map(|pair| (pair[1].holdings_value - pair[1].net_flow) / pair[0].holdings_value - 1.0)
+        .collect()
+}
+
+fn time_weighted_return(returns: &[f64]) -> f64 {
+    returns.iter().fold(1.0, |growth, r| growth * (1.0 + r)) - 1.0
+}
+
+// Largest peak-to-trough fall of the growth index, as a fraction of the peak
+fn max_drawdown(returns: &[f64]) -> f64 {
+    let mut index = 1.0_f64;
+    let mut peak = 1.0_f64;
+    let mut drawdown = 0.0_f64;
+
+    for r in returns {
+        index *= 1.0 + r;
+        peak = peak.max(index);
+        drawdown = drawdown.max((peak - index) / peak);
+    }
+
+    drawdown
+}
+
+// Annualized excess return per unit of annualized volatility
+fn sharpe_ratio(returns: &[f64], risk_free_rate_percent: f64, periods_per_year: f64) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+
+    let volatility = http_client::annualized_volatility(returns, periods_per_year);
+    if volatility <= 0.0 {
+        return None;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    Some((mean * periods_per_year - risk_free_rate_percent / 100.0) / volatility)
+}
+
+fn allocate_by_sector(positions: &[PortfolioPosition]) -> Vec<SectorAllocation> {
+    let total: f64 = positions.iter().map(|position| position.market_value).sum();
+
+    let mut allocations: Vec<SectorAllocation> = Vec::new();
+    for position in positions {
+        let sector = instruments::sector_of(&position.stock_id);
+        match allocations.iter_mut().find(|allocation| allocation.sector == sector) {
+            Some(allocation) => {
+                allocation.market_value += position.market_value;
+                allocation.position_count += 1;
+            }
+            None => allocations.push(SectorAllocation {
+                sector,
+                market_value: position.market_value,
+                weight_percent: 0.0,
+                position_count: 1,
+            }),
+        }
+    }
+
+    for allocation in allocations.iter_mut() {
+        allocation.weight_percent = if total > 0.0 { allocation.market_value / total * 100.0 } else { 0.0 };
+    }
+    allocations.sort_by(|a, b| b.market_value.total_cmp(&a.market_value));
+
+    allocations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp: u64, holdings_value: f64, net_flow: f64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            wallet_address: "0xabc".to_string(),
+            timestamp,
+            holdings_value,
+            cost_basis: 0.0,
+            cash_balance: 0.0,
+            net_flow,
+        }
+    }
+
+    #[test]
+    fn test_returns_ignore_cash_flows() {
+        let snapshots = vec![
+            snapshot(1, 0.0, 1_000.0),   // first purchase
+            snapshot(2, 1_100.0, 0.0),   // +10%
+            snapshot(3, 1_600.0, 500.0), // bought 500 more, flat otherwise
+            snapshot(4, 0.0, -1_760.0),  // sold everything at +10%
+        ];
+
+        let returns = period_returns(&snapshots);
+        assert_eq!(returns.len(), 2);
+        assert!((returns[0] - 0.0).abs() < 1e-12);
+        assert!((returns[1] - 0.1).abs() < 1e-12);
+
+        assert!((time_weighted_return(&returns) - 0.1).abs() < 1e-12);
+        assert!((time_weighted_return(&[0.1, 0.1]) - 0.21).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_max_drawdown() {
+        // 100 -> 120 -> 90 -> 108: worst fall is 120 -> 90
+        let returns = vec![0.2, -0.25, 0.2];
+        assert!((max_drawdown(&returns) - 0.25).abs() < 1e-12);
+        assert_eq!(max_drawdown(&[0.1, 0.05]), 0.0);
+    }
+
+    #[test]
+    fn test_sharpe_ratio() {
+        assert!(sharpe_ratio(&[0.01], 6.5, 365.0).is_none());
+        assert!(sharpe_ratio(&[0.01, 0.01, 0.01], 6.5, 365.0).is_none()); // no volatility
+
+        let returns = vec![0.02, -0.01, 0.015, -0.005];
+        let sharpe = sharpe_ratio(&returns, 0.0, 365.0).unwrap();
+        let expected = (0.005 * 365.0) / http_client::annualized_volatility(&returns, 365.0);
+        assert!((sharpe - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_allocate_by_sector() {
+        let position = |stock_id: &str, market_value: f64| PortfolioPosition {
+            stock_id: stock_id.to_string(),
+            name: stock_id.to_string(),
+            quantity: 1,
+            average_cost: market_value,
+            current_price: market_value,
+            cost_basis: market_value,
+            market_value,
+            unrealized_pnl: 0.0,
+            unrealized_pnl_percent: 0.0,
+            realized_pnl: 0.0,
+            priced: true,
+        };
+
+        let allocations = allocate_by_sector(&[position("TCS", 300.0), position("SBI", 600.0), position("INFY", 200.0)]);
+        assert_eq!(allocations[0].sector, "Financials");
+        assert_eq!(allocations[1].sector, "Technology");
+        assert_eq!(allocations[1].position_count, 2);
+        assert!((allocations[1].weight_percent - 500.0 / 11.0).abs() < 1e-9);
+    }
+}
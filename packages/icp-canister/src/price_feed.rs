@@ -1,8 +1,9 @@
 use crate::storage;
+use crate::candles;
 use crate::error::*;
 
 // Authorized oracle principal (example, replace with your oracle principal)
-const ORACLE_PRINCIPAL: &str = "aaaaa-aa"; // replace with real principal
+pub const ORACLE_PRINCIPAL: &str = "aaaaa-aa"; // replace with real principal
 
 // Submit a price update for a symbol (USD). Only callable by authorized oracle.
 pub async fn submit_price(symbol: String, price_usd: f64) -> CanisterResult<()> {
@@ -21,6 +22,7 @@ pub async fn submit_price(symbol: String, price_usd: f64) -> CanisterResult<()>
     }
 
     storage::set_price_feed(&symbol, price_usd);
+    candles::record_price(&symbol, price_usd, 0.0, ic_cdk::api::time());
     Ok(())
 }
 
@@ -0,0 +1,366 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::banking;
+use crate::currency;
+
+const MAX_GOALS_PER_WALLET: usize = 10;
+const MAX_GOAL_NAME_LENGTH: usize = 64;
+const SUPER_SAVER_GOAL_COUNT: usize = 5;
+
+// Create a savings goal
+pub fn create_savings_goal(
+    wallet_address: String,
+    name: String,
+    target_amount: f64,
+    deadline: Option<u64>,
+    auto_sweep_percent: Option<f64>,
+) -> CanisterResult<SavingsGoal> {
+    // Verify session
+    auth::verify_session(&wallet_address)?;
+
+    storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let now = ic_cdk::api::time();
+    let name = validate_name(&name)?;
+    utils::validate_amount(target_amount)?;
+    validate_deadline(deadline, now)?;
+    let auto_sweep_percent = validate_sweep_percent(auto_sweep_percent)?;
+
+    if storage::get_savings_goals_for_wallet(&wallet_address).len() >= MAX_GOALS_PER_WALLET {
+        return Err(CanisterError::RateLimited("Too many savings goals".to_string()));
+    }
+
+    let goal = SavingsGoal {
+        id: utils::generate_goal_id(),
+        wallet_address,
+        name,
+        target_amount,
+        saved_amount: 0.0,
+        deadline,
+        auto_sweep_percent,
+        status: SavingsGoalStatus::Active,
+        created_at: now,
+        updated_at: now,
+        completed_at: None,
+    };
+
+    storage::set_savings_goal(&goal);
+
+    Ok(goal)
+}
+
+// Get all savings goals for a wallet, oldest first
+pub fn get_savings_goals(wallet_address: String) -> Vec<SavingsGoal> {
+    let mut goals = storage::get_savings_goals_for_wallet(&wallet_address);
+    goals.sort_by_key(|goal| goal.created_at);
+    goals
+}
+
+// Update a goal's name, target, deadline or auto-sweep rule
+pub fn update_savings_goal(
+    wallet_address: String,
+    goal_id: String,
+    update: SavingsGoalUpdate,
+) -> CanisterResult<SavingsGoal> {
+    // Verify session
+    auth::verify_session(&wallet_address)?;
+
+    let mut goal = get_owned_goal(&wallet_address, &goal_id)?;
+    let now = ic_cdk::api::time();
+
+    if let Some(name) = update.name {
+        goal.name = validate_name(&name)?;
+    }
+    if let Some(target_amount) = update.target_amount {
+        utils::validate_amount(target_amount)?;
+        goal.target_amount = target_amount;
+    }
+    if update.deadline.is_some() {
+        validate_deadline(update.deadline, now)?;
+        goal.deadline = update.deadline;
+    }
+    if update.auto_sweep_percent.is_some() {
+        goal.auto_sweep_percent = validate_sweep_percent(update.auto_sweep_percent)?;
+    }
+    goal.updated_at = now;
+
+    let mut user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    // A lowered target may complete the goal on the spot
+    if check_completion(&mut goal, &mut user_data, now) {
+        storage::update_user_data(&wallet_address, user_data)?;
+    }
+
+    storage::set_savings_goal(&goal);
+
+    Ok(goal)
+}
+
+// Delete a goal, releasing its ring-fenced rupees back to the balance.
+// Returns the amount released.
+pub fn delete_savings_goal(wallet_address: String, goal_id: String) -> CanisterResult<f64> {
+    // Verify session
+    auth::verify_session(&wallet_address)?;
+
+    let goal = get_owned_goal(&wallet_address, &goal_id)?;
+
+    let mut user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let mut writes = PendingWrites::default();
+    if goal.saved_amount > 0.0 {
+        currency::credit(&mut user_data, &mut writes, currency::RUPEES, goal.saved_amount)?;
+    }
+    user_data.last_activity = ic_cdk::api::time();
+
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::remove_savings_goal(&goal_id);
+
+    Ok(goal.saved_amount)
+}
+
+// Move rupees from the spendable balance into a goal
+pub fn contribute_to_savings_goal(
+    wallet_address: String,
+    goal_id: String,
+    amount: f64,
+) -> CanisterResult<SavingsGoal> {
+    // Verify session
+    auth::verify_session(&wallet_address)?;
+
+    utils::validate_amount(amount)?;
+
+    let mut goal = get_owned_goal(&wallet_address, &goal_id)?;
+    if goal.status != SavingsGoalStatus::Active {
+        return Err(CanisterError::InvalidInput("Savings goal is already complete".to_string()));
+    }
+
+    let mut user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    if user_data.dual_balance.rupees_balance < amount {
+        return Err(CanisterError::InsufficientBalance);
+    }
+
+    let now = ic_cdk::api::time();
+    let mut writes = PendingWrites::default();
+    currency::debit(&mut user_data, &mut writes, currency::RUPEES, amount)?;
+    user_data.last_activity = now;
+
+    goal.saved_amount = utils::safe_add(goal.saved_amount, amount)?;
+    goal.updated_at = now;
+    check_completion(&mut goal, &mut user_data, now);
+
+    storage::commit_user_data(&wallet_address, user_data, writes)?;
+    storage::set_savings_goal(&goal);
+
+    Ok(goal)
+}
+
+// Progress report for a single goal
+pub fn get_savings_goal_progress(wallet_address: String, goal_id: String) -> CanisterResult<SavingsGoalProgress> {
+    let goal = get_owned_goal(&wallet_address, &goal_id)?;
+    Ok(calculate_progress(goal, ic_cdk::api::time()))
+}
+
+// Apply auto-sweep rules to a rupee credit that has just been added to
+// `user_data`. The swept share moves from the balance into the wallet's goals.
+// Returns the total amount swept.
+pub fn sweep_incoming_credit(user_data: &mut UserData, writes: &mut PendingWrites, credited_amount: f64) -> CanisterResult<f64> {
+    if credited_amount <= 0.0 {
+        return Ok(0.0);
+    }
+
+    let mut goals: Vec<SavingsGoal> = storage::get_savings_goals_for_wallet(&user_data.wallet_address)
+        .into_iter()
+        .filter(|goal| goal.status == SavingsGoalStatus::Active && goal.auto_sweep_percent.is_some())
+        .collect();
+    if goals.is_empty() {
+        return Ok(0.0);
+    }
+    goals.sort_by_key(|goal| goal.created_at);
+
+    let now = ic_cdk::api::time();
+    let mut total_swept = 0.0;
+
+    for mut goal in goals {
+        let available = credited_amount - total_swept;
+        let amount = sweep_amount(&goal, credited_amount).min(available);
+        if amount <= 0.0 {
+            continue;
+        }
+
+        currency::debit(user_data, writes, currency::RUPEES, amount)?;
+        goal.saved_amount = utils::safe_add(goal.saved_amount, amount)?;
+        goal.updated_at = now;
+        check_completion(&mut goal, user_data, now);
+        storage::set_savings_goal(&goal);
+
+        total_swept += amount;
+    }
+
+    Ok(total_swept)
+}
+
+// Share of a credit a goal's sweep rule claims, capped at what the goal still needs
+fn sweep_amount(goal: &SavingsGoal, credited_amount: f64) -> f64 {
+    let percent = goal.auto_sweep_percent.unwrap_or(0.0);
+    let remaining = (goal.target_amount - goal.saved_amount).max(0.0);
+    (credited_amount * percent / 100.0).min(remaining)
+}
+
+// Mark a goal complete once its target is reached and unlock Saving achievements.
+// Returns true if the goal was completed by this call.
+fn check_completion(goal: &mut SavingsGoal, user_data: &mut UserData, now: u64) -> bool {
+    if goal.status != SavingsGoalStatus::Active || goal.saved_amount < goal.target_amount {
+        return false;
+    }
+
+    goal.status = SavingsGoalStatus::Completed;
+    goal.completed_at = Some(now);
+
+    ic_cdk::println!("🎯 Savings goal {} completed for {}", goal.id, goal.wallet_address);
+
+    banking::unlock_achievement(user_data, "goal_getter");
+
+    // Count the goal being completed now in addition to those already stored
+    let completed = storage::get_savings_goals_for_wallet(&goal.wallet_address)
+        .iter()
+        .filter(|g| g.id != goal.id && g.status == SavingsGoalStatus::Completed)
+        .count() + 1;
+    if completed >= SUPER_SAVER_GOAL_COUNT {
+        banking::unlock_achievement(user_data, "super_saver");
+    }
+
+    true
+}
+
+fn calculate_progress(goal: SavingsGoal, now: u64) -> SavingsGoalProgress {
+    let remaining_amount = (goal.target_amount - goal.saved_amount).max(0.0);
+    let percent_complete = if goal.target_amount > 0.0 {
+        (goal.saved_amount / goal.target_amount * 100.0).min(100.0)
+    } else {
+        100.0
+    };
+
+    let days_remaining = goal.deadline.map(|deadline| deadline.saturating_sub(now) / utils::NANOS_PER_DAY);
+
+    let required_daily_saving = match days_remaining {
+        Some(days) if remaining_amount > 0.0 => Some(remaining_amount / days.max(1) as f64),
+        _ => None,
+    };
+
+    // On track if saving keeps pace with elapsed time towards the deadline
+    let on_track = match goal.deadline {
+        _ if remaining_amount <= 0.0 => true,
+        Some(deadline) if deadline <= now => false,
+        Some(deadline) if deadline > goal.created_at => {
+            let elapsed = (now.saturating_sub(goal.created_at)) as f64 / (deadline - goal.created_at) as f64;
+            percent_complete / 100.0 >= elapsed
+        }
+        _ => true,
+    };
+
+    SavingsGoalProgress {
+        goal,
+        percent_complete,
+        remaining_amount,
+        days_remaining,
+        required_daily_saving,
+        on_track,
+    }
+}
+
+fn get_owned_goal(wallet_address: &str, goal_id: &str) -> CanisterResult<SavingsGoal> {
+    let goal = storage::get_savings_goal(goal_id)
+        .ok_or(CanisterError::NotFound(format!("Savings goal {}", goal_id)))?;
+
+    if goal.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+
+    Ok(goal)
+}
+
+fn validate_name(name: &str) -> CanisterResult<String> {
+    let name = utils::sanitize_string(name.trim());
+    if name.is_empty() {
+        return Err(CanisterError::InvalidInput("Goal name cannot be empty".to_string()));
+    }
+    Ok(name.chars().take(MAX_GOAL_NAME_LENGTH).collect())
+}
+
+fn validate_deadline(deadline: Option<u64>, now: u64) -> CanisterResult<()> {
+    match deadline {
+        Some(deadline) if deadline <= now => {
+            Err(CanisterError::InvalidInput("Deadline must be in the future".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_sweep_percent(percent: Option<f64>) -> CanisterResult<Option<f64>> {
+    match percent {
+        None => Ok(None),
+        Some(0.0) => Ok(None),
+        Some(p) if p.is_finite() && p > 0.0 && p <= 100.0 => Ok(Some(p)),
+        Some(_) => Err(CanisterError::InvalidInput("Auto-sweep percent must be between 0 and 100".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = utils::NANOS_PER_DAY;
+
+    fn test_goal(target_amount: f64, saved_amount: f64, deadline: Option<u64>) -> SavingsGoal {
+        SavingsGoal {
+            id: "goal_test".to_string(),
+            wallet_address: "0xabc".to_string(),
+            name: "Bike".to_string(),
+            target_amount,
+            saved_amount,
+            deadline,
+            auto_sweep_percent: Some(10.0),
+            status: SavingsGoalStatus::Active,
+            created_at: 0,
+            updated_at: 0,
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_sweep_amount_capped_by_remaining() {
+        assert_eq!(sweep_amount(&test_goal(1000.0, 0.0, None), 500.0), 50.0);
+        assert_eq!(sweep_amount(&test_goal(1000.0, 980.0, None), 500.0), 20.0);
+        assert_eq!(sweep_amount(&test_goal(1000.0, 1000.0, None), 500.0), 0.0);
+    }
+
+    #[test]
+    fn test_progress_with_deadline() {
+        let progress = calculate_progress(test_goal(1000.0, 250.0, Some(10 * DAY)), 5 * DAY);
+        assert_eq!(progress.percent_complete, 25.0);
+        assert_eq!(progress.remaining_amount, 750.0);
+        assert_eq!(progress.days_remaining, Some(5));
+        assert_eq!(progress.required_daily_saving, Some(150.0));
+        assert!(!progress.on_track); // half the time gone, a quarter saved
+
+        let progress = calculate_progress(test_goal(1000.0, 600.0, Some(10 * DAY)), 5 * DAY);
+        assert!(progress.on_track);
+    }
+
+    #[test]
+    fn test_validate_sweep_percent() {
+        assert_eq!(validate_sweep_percent(None).unwrap(), None);
+        assert_eq!(validate_sweep_percent(Some(0.0)).unwrap(), None);
+        assert_eq!(validate_sweep_percent(Some(10.0)).unwrap(), Some(10.0));
+        assert!(validate_sweep_percent(Some(150.0)).is_err());
+        assert!(validate_sweep_percent(Some(-5.0)).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::types::*;
+use crate::error::*;
+use crate::instruments;
+use crate::stock_sse;
+use crate::trading;
+use crate::utils::NANOS_PER_DAY;
+
+const DEFAULT_SCREEN_LIMIT: u32 = 20;
+const MAX_SCREEN_LIMIT: u32 = 100;
+const DEFAULT_CHANGE_DAYS: u32 = 5;
+const MAX_CHANGE_DAYS: u32 = 30;
+// Keeps a query's evaluation cost bounded
+const MAX_CONDITION_NODES: usize = 32;
+const MAX_CONDITION_DEPTH: usize = 4;
+
+// Filter and sort the cached stocks; reads only what refreshes already stored
+pub fn screen_stocks(query: ScreenerQuery) -> CanisterResult<ScreenerPage> {
+    screen(stock_sse::cached_stocks(), &query, ic_cdk::api::time())
+}
+
+fn screen(stocks: Vec<Stock>, query: &ScreenerQuery, now: u64) -> CanisterResult<ScreenerPage> {
+    validate_query(query)?;
+    let change_days = query.change_days.unwrap_or(DEFAULT_CHANGE_DAYS);
+
+    let mut rows: Vec<ScreenerRow> = stocks
+        .into_iter()
+        .map(|stock| row(stock, change_days, now))
+        .filter(|row| query.condition.as_ref().is_none_or(|condition| matches(condition, row)))
+        .collect();
+
+    rows.sort_by(|a, b| {
+        let by_field = match query.sort_by {
+            Some(field) => compare_values(field_value(a, field), field_value(b, field), query.descending),
+            None => std::cmp::Ordering::Equal,
+        };
+        by_field.then_with(|| a.symbol.cmp(&b.symbol))
+    });
+    if query.sort_by.is_none() && query.descending {
+        rows.reverse();
+    }
+
+    let total_matches = rows.len();
+    let offset = query.offset.unwrap_or(0) as usize;
+    let limit = query.limit.unwrap_or(DEFAULT_SCREEN_LIMIT).clamp(1, MAX_SCREEN_LIMIT) as usize;
+    let rows: Vec<ScreenerRow> = rows.into_iter().skip(offset).take(limit).collect();
+    let next_offset = (offset + rows.len() < total_matches).then(|| (offset + rows.len()) as u32);
+
+    Ok(ScreenerPage {
+        rows,
+        total_matches: total_matches as u32,
+        next_offset,
+    })
+}
+
+// Prices and market caps are compared in rupees, so listings quoted in
+// different currencies screen against the same thresholds
+fn row(stock: Stock, change_days: u32, now: u64) -> ScreenerRow {
+    let instrument = instruments::lookup(&stock.id);
+    let fx_rate = trading::rupee_rate(&stock.id, now).ok().map(|(_, fx_rate)| fx_rate);
+    ScreenerRow {
+        price_rupees: fx_rate.map(|fx_rate| stock.current_price * fx_rate),
+        market_cap_rupees: fx_rate.map(|fx_rate| stock.metrics.market_cap * fx_rate),
+        price_change_percent: price_change_percent(&stock, change_days),
+        exchange: stock.exchange.unwrap_or(instrument.exchange),
+        currency: stock.currency.unwrap_or(instrument.currency),
+        symbol: stock.id,
+        name: stock.name,
+        price: stock.current_price,
+        metrics: stock.metrics,
+        last_update: stock.last_update,
+    }
+}
+
+// Change from the last close at least `days` before the newest close, or
+// None when the history does not reach back that far
+fn price_change_percent(stock: &Stock, days: u32) -> Option<f64> {
+    let newest = stock.price_history.iter().map(|price| price.timestamp).max()?;
+    let cutoff = newest.checked_sub(days as u64 * NANOS_PER_DAY)?;
+    let base = stock
+        .price_history
+        .iter()
+        .filter(|price| price.timestamp <= cutoff)
+        .max_by_key(|price| price.timestamp)?;
+
+    if base.close <= 0.0 || !base.close.is_finite() {
+        return None;
+    }
+    Some((stock.current_price - base.close) / base.close * 100.0)
+}
+
+// P/E based fields need positive earnings to mean anything, and debt/equity
+// needs positive equity. A market cap of zero means the provider had no share
+// count, so it is treated as missing, as are rupee values without a fresh rate.
+fn field_value(row: &ScreenerRow, field: ScreenerField) -> Option<f64> {
+    let metrics = &row.metrics;
+    let value = match field {
+        ScreenerField::Price => row.price_rupees,
+        ScreenerField::PriceChangePercent => row.price_change_percent,
+        ScreenerField::MarketCap => row.market_cap_rupees.filter(|market_cap| *market_cap > 0.0),
+        ScreenerField::PeRatio => (metrics.pe_ratio > 0.0).then_some(metrics.pe_ratio),
+        ScreenerField::PeToIndustryAvg => (metrics.pe_ratio > 0.0 && metrics.industry_avg_pe > 0.0)
+            .then(|| metrics.pe_ratio / metrics.industry_avg_pe),
+        ScreenerField::Eps => Some(metrics.eps),
+        ScreenerField::DebtEquityRatio => {
+            let unavailable = metrics.provenance.as_ref()
+                .is_some_and(|provenance| provenance.debt_equity_ratio == MetricSource::Unavailable);
+            (!unavailable).then_some(metrics.debt_equity_ratio)
+        }
+        ScreenerField::BusinessGrowth => Some(metrics.business_growth),
+        ScreenerField::Volatility => Some(metrics.volatility),
+    };
+    value.filter(|value| value.is_finite())
+}
+
+fn matches(condition: &ScreenerCondition, row: &ScreenerRow) -> bool {
+    match condition {
+        ScreenerCondition::Above(field, min) => field_value(row, *field).is_some_and(|value| value >= *min),
+        ScreenerCondition::Below(field, max) => field_value(row, *field).is_some_and(|value| value <= *max),
+        ScreenerCondition::Between(field, min, max) => {
+            field_value(row, *field).is_some_and(|value| value >= *min && value <= *max)
+        }
+        ScreenerCondition::All(conditions) => conditions.iter().all(|condition| matches(condition, row)),
+        ScreenerCondition::Any(conditions) => conditions.iter().any(|condition| matches(condition, row)),
+        ScreenerCondition::Not(condition) => !matches(condition, row),
+    }
+}
+
+// Missing values sort last in either direction
+fn compare_values(a: Option<f64>, b: Option<f64>, descending: bool) -> std::cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.total_cmp(&a),
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    }
+}
+
+fn validate_query(query: &ScreenerQuery) -> CanisterResult<()> {
+    if let Some(days) = query.change_days {
+        if days == 0 || days > MAX_CHANGE_DAYS {
+            return Err(CanisterError::InvalidInput(format!("change_days must be between 1 and {}", MAX_CHANGE_DAYS)));
+        }
+    }
+    if let Some(condition) = &query.condition {
+        let mut nodes = 0;
+        validate_condition(condition, 1, &mut nodes)?;
+    }
+    Ok(())
+}
+
+fn validate_condition(condition: &ScreenerCondition, depth: usize, nodes: &mut usize) -> CanisterResult<()> {
+    *nodes += 1;
+    if *nodes > MAX_CONDITION_NODES {
+        return Err(CanisterError::InvalidInput(format!("Screens are limited to {} conditions", MAX_CONDITION_NODES)));
+    }
+    if depth > MAX_CONDITION_DEPTH {
+        return Err(CanisterError::InvalidInput(format!("Conditions nest at most {} deep", MAX_CONDITION_DEPTH)));
+    }
+
+    match condition {
+        ScreenerCondition::Above(_, bound) | ScreenerCondition::Below(_, bound) if !bound.is_finite() => {
+            Err(CanisterError::InvalidInput("Screener bounds must be finite".to_string()))
+        }
+        ScreenerCondition::Between(_, min, max) if !min.is_finite() || !max.is_finite() || min > max => {
+            Err(CanisterError::InvalidInput("Between needs finite bounds with min <= max".to_string()))
+        }
+        ScreenerCondition::All(conditions) | ScreenerCondition::Any(conditions) => {
+            if conditions.is_empty() {
+                return Err(CanisterError::InvalidInput("All and Any need at least one condition".to_string()));
+            }
+            conditions.iter().try_for_each(|condition| validate_condition(condition, depth + 1, nodes))
+        }
+        ScreenerCondition::Not(condition) => validate_condition(condition, depth + 1, nodes),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+
+    fn stock(id: &str, price: f64, week_ago_close: f64, pe_ratio: f64, industry_avg_pe: f64) -> Stock {
+        let close = |timestamp: u64, close: f64| StockPrice {
+            timestamp,
+            price: close,
+            volume: 0,
+            high: close,
+            low: close,
+            open: close,
+            close,
+        };
+        Stock {
+            id: id.to_string(),
+            name: id.to_string(),
+            symbol: id.to_string(),
+            current_price: price,
+            price_history: vec![close(NANOS_PER_DAY, week_ago_close), close(8 * NANOS_PER_DAY, price)],
+            metrics: StockMetrics {
+                market_cap: price * 1_000_000.0,
+                pe_ratio,
+                eps: price / pe_ratio,
+                debt_equity_ratio: 0.5,
+                business_growth: 10.0,
+                industry_avg_pe,
+                outstanding_shares: 1_000_000,
+                volatility: 0.2,
+                provenance: None,
+            },
+            news: Vec::new(),
+            last_update: 0,
+            currency: None,
+            exchange: None,
+        }
+    }
+
+    fn query(condition: Option<ScreenerCondition>) -> ScreenerQuery {
+        ScreenerQuery {
+            condition,
+            change_days: Some(7),
+            sort_by: None,
+            descending: false,
+            offset: None,
+            limit: None,
+        }
+    }
+
+    fn symbols(page: &ScreenerPage) -> Vec<&str> {
+        page.rows.iter().map(|row| row.symbol.as_str()).collect()
+    }
+
+    const NOW: u64 = 10 * NANOS_PER_DAY;
+
+    // A fresh USD/INR rate, so AAPL screens in rupees alongside TCS and INFY
+    fn universe() -> Vec<Stock> {
+        storage::set_fx_rate(&FxRate {
+            base: "USD".to_string(),
+            quote: "INR".to_string(),
+            rate: 83.0,
+            source: FxRateSource::Provider,
+            updated_at: NOW,
+        });
+        vec![
+            stock("TCS", 3300.0, 3000.0, 20.0, 25.0),
+            stock("AAPL", 180.0, 200.0, 30.0, 28.5),
+            stock("INFY", 1400.0, 1400.0, 18.0, 25.0),
+        ]
+    }
+
+    #[test]
+    fn test_undervalued_screen() {
+        let undervalued = ScreenerCondition::All(vec![
+            ScreenerCondition::Below(ScreenerField::PeToIndustryAvg, 1.0),
+            ScreenerCondition::Above(ScreenerField::PriceChangePercent, 5.0),
+        ]);
+        let page = screen(universe(), &query(Some(undervalued)), NOW).unwrap();
+        assert_eq!(symbols(&page), vec!["TCS"]);
+        assert!((page.rows[0].price_change_percent.unwrap() - 10.0).abs() < 1e-9);
+
+        let not_cheap = ScreenerCondition::Not(Box::new(ScreenerCondition::Below(ScreenerField::PeToIndustryAvg, 1.0)));
+        assert_eq!(symbols(&screen(universe(), &query(Some(not_cheap)), NOW).unwrap()), vec!["AAPL"]);
+    }
+
+    #[test]
+    fn test_sort_and_paginate() {
+        let mut by_change = query(None);
+        by_change.sort_by = Some(ScreenerField::PriceChangePercent);
+        by_change.descending = true;
+        by_change.limit = Some(2);
+
+        let first = screen(universe(), &by_change, NOW).unwrap();
+        assert_eq!(symbols(&first), vec!["TCS", "INFY"]);
+        assert_eq!(first.total_matches, 3);
+        assert_eq!(first.next_offset, Some(2));
+
+        by_change.offset = first.next_offset;
+        let second = screen(universe(), &by_change, NOW).unwrap();
+        assert_eq!(symbols(&second), vec!["AAPL"]);
+        assert_eq!(second.next_offset, None);
+    }
+
+    #[test]
+    fn test_price_change_needs_enough_history() {
+        let mut long_window = query(Some(ScreenerCondition::Above(ScreenerField::PriceChangePercent, -100.0)));
+        long_window.change_days = Some(10);
+        assert!(screen(universe(), &long_window, NOW).unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_market_cap_is_missing() {
+        let mut stocks = universe();
+        stocks[0].metrics.market_cap = 0.0;
+        let small_caps = query(Some(ScreenerCondition::Below(ScreenerField::MarketCap, 1e12)));
+        assert_eq!(symbols(&screen(stocks, &small_caps, NOW).unwrap()), vec!["AAPL", "INFY"]);
+    }
+
+    #[test]
+    fn test_unavailable_debt_equity_is_missing() {
+        let mut stocks = universe();
+        let mut provenance = MetricProvenance::estimated();
+        provenance.debt_equity_ratio = MetricSource::Unavailable;
+        stocks[0].metrics.debt_equity_ratio = 0.0;
+        stocks[0].metrics.provenance = Some(provenance);
+        let low_debt = query(Some(ScreenerCondition::Below(ScreenerField::DebtEquityRatio, 1.0)));
+        assert_eq!(symbols(&screen(stocks, &low_debt, NOW).unwrap()), vec!["AAPL", "INFY"]);
+    }
+
+    #[test]
+    fn test_price_and_market_cap_compare_in_rupees() {
+        // AAPL at $180 is ₹14,940, above TCS at ₹3,300
+        let mut by_price = query(Some(ScreenerCondition::Above(ScreenerField::Price, 3_000.0)));
+        by_price.sort_by = Some(ScreenerField::Price);
+        by_price.descending = true;
+        assert_eq!(symbols(&screen(universe(), &by_price, NOW).unwrap()), vec!["AAPL", "TCS"]);
+
+        let large_caps = query(Some(ScreenerCondition::Above(ScreenerField::MarketCap, 1e10)));
+        assert_eq!(symbols(&screen(universe(), &large_caps, NOW).unwrap()), vec!["AAPL"]);
+
+        // Without a fresh rate AAPL has no rupee values and drops out
+        let stale = NOW + 7 * NANOS_PER_DAY;
+        assert_eq!(symbols(&screen(universe(), &by_price, stale).unwrap()), vec!["TCS"]);
+    }
+
+    #[test]
+    fn test_validate_query() {
+        let mut bad_days = query(None);
+        bad_days.change_days = Some(0);
+        assert!(validate_query(&bad_days).is_err());
+
+        let inverted = ScreenerCondition::Between(ScreenerField::Volatility, 0.5, 0.1);
+        assert!(validate_query(&query(Some(inverted))).is_err());
+        assert!(validate_query(&query(Some(ScreenerCondition::Any(Vec::new())))).is_err());
+
+        let mut nested = ScreenerCondition::Above(ScreenerField::Eps, 0.0);
+        for _ in 0..MAX_CONDITION_DEPTH {
+            nested = ScreenerCondition::Not(Box::new(nested));
+        }
+        assert!(validate_query(&query(Some(nested))).is_err());
+    }
+}
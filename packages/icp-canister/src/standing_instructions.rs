@@ -0,0 +1,378 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::banking;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Executor configuration
+const EXECUTOR_INTERVAL_SECONDS: u64 = 60;
+const MAX_EXECUTIONS_PER_TICK: usize = 50;
+const MAX_INSTRUCTIONS_PER_WALLET: usize = 20;
+
+thread_local! {
+    static EXECUTOR_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+// Create a standing instruction for a wallet
+pub fn create_standing_instruction(
+    wallet_address: String,
+    action: InstructionAction,
+    recurrence: Recurrence,
+    start_at: u64,
+    end_at: Option<u64>,
+    retry_policy: Option<RetryPolicy>,
+) -> CanisterResult<StandingInstruction> {
+    // Verify session
+    auth::verify_session(&wallet_address)?;
+
+    validate_action(&wallet_address, &action)?;
+
+    let now = ic_cdk::api::time();
+    if start_at < now {
+        return Err(CanisterError::InvalidInput("Start time must be in the future".to_string()));
+    }
+    if let Some(end) = end_at {
+        if end < start_at {
+            return Err(CanisterError::InvalidInput("End time must be after start time".to_string()));
+        }
+    }
+
+    if storage::get_standing_instructions_for_wallet(&wallet_address).iter()
+        .filter(|i| matches!(i.status, InstructionStatus::Active | InstructionStatus::Paused))
+        .count() >= MAX_INSTRUCTIONS_PER_WALLET
+    {
+        return Err(CanisterError::RateLimited("Too many standing instructions".to_string()));
+    }
+
+    let instruction = StandingInstruction {
+        id: utils::generate_instruction_id(),
+        wallet_address,
+        action,
+        recurrence,
+        scheduled_at: start_at,
+        next_run_at: start_at,
+        end_at,
+        status: InstructionStatus::Active,
+        retry_policy: retry_policy.unwrap_or_default(),
+        retry_count: 0,
+        execution_count: 0,
+        last_run_at: None,
+        last_error: None,
+        created_at: now,
+    };
+
+    storage::set_standing_instruction(&instruction);
+
+    Ok(instruction)
+}
+
+// List a wallet's standing instructions, most recently created first
+pub fn list_standing_instructions(wallet_address: String) -> Vec<StandingInstruction> {
+    let mut instructions = storage::get_standing_instructions_for_wallet(&wallet_address);
+    instructions.sort_by_key(|instruction| std::cmp::Reverse(instruction.created_at));
+    instructions
+}
+
+pub fn pause_standing_instruction(wallet_address: String, instruction_id: String) -> CanisterResult<StandingInstruction> {
+    update_status(&wallet_address, &instruction_id, |instruction| {
+        if instruction.status != InstructionStatus::Active {
+            return Err(CanisterError::InvalidInput("Only active instructions can be paused".to_string()));
+        }
+        instruction.status = InstructionStatus::Paused;
+        Ok(())
+    })
+}
+
+pub fn resume_standing_instruction(wallet_address: String, instruction_id: String) -> CanisterResult<StandingInstruction> {
+    let now = ic_cdk::api::time();
+    update_status(&wallet_address, &instruction_id, |instruction| {
+        if instruction.status != InstructionStatus::Paused {
+            return Err(CanisterError::InvalidInput("Only paused instructions can be resumed".to_string()));
+        }
+
+        // Skip occurrences missed while paused
+        while instruction.scheduled_at < now {
+            match next_occurrence(&instruction.recurrence, instruction.scheduled_at) {
+                Some(next) => instruction.scheduled_at = next,
+                None => break,
+            }
+        }
+        instruction.next_run_at = instruction.scheduled_at.max(now);
+        instruction.retry_count = 0;
+
+        if is_past_end(instruction, instruction.scheduled_at) {
+            instruction.status = InstructionStatus::Completed;
+        } else {
+            instruction.status = InstructionStatus::Active;
+        }
+        Ok(())
+    })
+}
+
+pub fn cancel_standing_instruction(wallet_address: String, instruction_id: String) -> CanisterResult<StandingInstruction> {
+    update_status(&wallet_address, &instruction_id, |instruction| {
+        if !matches!(instruction.status, InstructionStatus::Active | InstructionStatus::Paused) {
+            return Err(CanisterError::InvalidInput("Instruction is no longer running".to_string()));
+        }
+        instruction.status = InstructionStatus::Cancelled;
+        Ok(())
+    })
+}
+
+fn update_status<F>(wallet_address: &str, instruction_id: &str, apply: F) -> CanisterResult<StandingInstruction>
+where
+    F: FnOnce(&mut StandingInstruction) -> CanisterResult<()>,
+{
+    // Verify session
+    auth::verify_session(wallet_address)?;
+
+    let mut instruction = storage::get_standing_instruction(instruction_id)
+        .ok_or(CanisterError::NotFound(format!("Standing instruction {}", instruction_id)))?;
+
+    if instruction.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+
+    apply(&mut instruction)?;
+    storage::set_standing_instruction(&instruction);
+
+    Ok(instruction)
+}
+
+fn validate_action(wallet_address: &str, action: &InstructionAction) -> CanisterResult<()> {
+    match action {
+        InstructionAction::Transfer { to, currency, amount } => {
+            utils::validate_amount(*amount)?;
+            utils::validate_currency(currency)?;
+            if to == wallet_address {
+                return Err(CanisterError::InvalidInput("Cannot transfer to the same wallet".to_string()));
+            }
+            if storage::get_user_data(to).is_none() {
+                return Err(CanisterError::UserNotFound);
+            }
+        }
+        InstructionAction::Exchange { from_currency, to_currency, amount } => {
+            utils::validate_amount(*amount)?;
+            utils::validate_currency(from_currency)?;
+            utils::validate_currency(to_currency)?;
+            if from_currency.to_lowercase() == to_currency.to_lowercase() {
+                return Err(CanisterError::InvalidInput("Cannot exchange same currency".to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Start the periodic executor. Timers do not survive upgrades, so this is
+// called from both init and post_upgrade.
+pub fn start_executor() {
+    EXECUTOR_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(EXECUTOR_INTERVAL_SECONDS), || {
+            let executed = run_due_instructions(ic_cdk::api::time());
+            if executed > 0 {
+                ic_cdk::println!("⏰ Executed {} standing instructions", executed);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+// Execute every due instruction (bounded per tick). Returns the number attempted.
+pub fn run_due_instructions(now: u64) -> usize {
+    let mut due = storage::get_due_standing_instructions(now);
+    due.sort_by_key(|instruction| instruction.next_run_at);
+    due.truncate(MAX_EXECUTIONS_PER_TICK);
+
+    let attempted = due.len();
+    for mut instruction in due {
+        let outcome = execute_action(&instruction);
+        if let Err(ref error) = outcome {
+            record_failed_transaction(&instruction, now, error);
+        }
+        apply_outcome(&mut instruction, outcome.map(|_| ()), now);
+        storage::set_standing_instruction(&instruction);
+    }
+
+    attempted
+}
+
+fn execute_action(instruction: &StandingInstruction) -> CanisterResult<Web3Transaction> {
+    match &instruction.action {
+        InstructionAction::Transfer { to, currency, amount } => {
+            banking::execute_transfer(&instruction.wallet_address, to, currency, *amount)
+        }
+        InstructionAction::Exchange { from_currency, to_currency, amount } => {
+            let result = banking::execute_exchange(
+                instruction.wallet_address.clone(),
+                from_currency.to_lowercase(),
+                to_currency.to_lowercase(),
+                *amount,
+            )?;
+            result.transaction.ok_or(CanisterError::TransactionFailed)
+        }
+    }
+}
+
+fn record_failed_transaction(instruction: &StandingInstruction, now: u64, error: &CanisterError) {
+    let (transaction_type, to, amount) = match &instruction.action {
+        InstructionAction::Transfer { to, amount, .. } => (TransactionType::Transfer, Some(to.clone()), *amount),
+        InstructionAction::Exchange { amount, .. } => (TransactionType::Exchange, None, *amount),
+    };
+
+    ic_cdk::println!("❌ Standing instruction {} failed: {}", instruction.id, error);
+
+    storage::append_transaction(&instruction.wallet_address, Web3Transaction {
+        id: utils::generate_transaction_id(),
+        from: instruction.wallet_address.clone(),
+        to,
+        amount,
+        transaction_type,
+        timestamp: now,
+        status: TransactionStatus::Failed,
+        hash: None,
+    });
+}
+
+// Advance an instruction's schedule after an execution attempt
+fn apply_outcome(instruction: &mut StandingInstruction, outcome: CanisterResult<()>, now: u64) {
+    instruction.last_run_at = Some(now);
+
+    match outcome {
+        Ok(()) => {
+            instruction.execution_count += 1;
+            instruction.last_error = None;
+        }
+        Err(error) => {
+            let retryable = error == CanisterError::InsufficientBalance
+                && instruction.retry_count < instruction.retry_policy.max_retries;
+            instruction.last_error = Some(error.to_string());
+
+            if retryable {
+                instruction.retry_count += 1;
+                instruction.next_run_at = now.saturating_add(instruction.retry_policy.retry_interval);
+
+                // Give up on this occurrence once retries would overrun the next one
+                let next = next_occurrence(&instruction.recurrence, instruction.scheduled_at);
+                if next.is_none_or(|next| instruction.next_run_at < next) {
+                    return;
+                }
+            } else if instruction.recurrence == Recurrence::Once {
+                instruction.status = InstructionStatus::Failed;
+                return;
+            }
+        }
+    }
+
+    // Move on to the next occurrence
+    instruction.retry_count = 0;
+    match next_occurrence(&instruction.recurrence, instruction.scheduled_at) {
+        Some(next) if !is_past_end(instruction, next) => {
+            instruction.scheduled_at = next;
+            instruction.next_run_at = next;
+        }
+        _ => {
+            instruction.status = if instruction.execution_count > 0 {
+                InstructionStatus::Completed
+            } else {
+                InstructionStatus::Failed
+            };
+        }
+    }
+}
+
+fn next_occurrence(recurrence: &Recurrence, from: u64) -> Option<u64> {
+    match recurrence {
+        Recurrence::Once => None,
+        Recurrence::Daily => Some(from + utils::NANOS_PER_DAY),
+        Recurrence::Weekly => Some(from + 7 * utils::NANOS_PER_DAY),
+        Recurrence::Monthly => Some(utils::add_months(from, 1)),
+    }
+}
+
+fn is_past_end(instruction: &StandingInstruction, at: u64) -> bool {
+    instruction.end_at.is_some_and(|end| at > end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = utils::NANOS_PER_DAY;
+
+    fn test_instruction(recurrence: Recurrence, end_at: Option<u64>) -> StandingInstruction {
+        StandingInstruction {
+            id: "si_test".to_string(),
+            wallet_address: "0xabc".to_string(),
+            action: InstructionAction::Exchange {
+                from_currency: "rupees".to_string(),
+                to_currency: "tokens".to_string(),
+                amount: 100.0,
+            },
+            recurrence,
+            scheduled_at: DAY,
+            next_run_at: DAY,
+            end_at,
+            status: InstructionStatus::Active,
+            retry_policy: RetryPolicy { max_retries: 2, retry_interval: DAY / 24 },
+            retry_count: 0,
+            execution_count: 0,
+            last_run_at: None,
+            last_error: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_once_completes_after_success() {
+        let mut instruction = test_instruction(Recurrence::Once, None);
+        apply_outcome(&mut instruction, Ok(()), DAY);
+        assert_eq!(instruction.status, InstructionStatus::Completed);
+        assert_eq!(instruction.execution_count, 1);
+    }
+
+    #[test]
+    fn test_recurring_advances_until_end() {
+        let mut instruction = test_instruction(Recurrence::Daily, Some(2 * DAY));
+        apply_outcome(&mut instruction, Ok(()), DAY);
+        assert_eq!(instruction.status, InstructionStatus::Active);
+        assert_eq!(instruction.next_run_at, 2 * DAY);
+
+        apply_outcome(&mut instruction, Ok(()), 2 * DAY);
+        assert_eq!(instruction.status, InstructionStatus::Completed);
+    }
+
+    #[test]
+    fn test_insufficient_balance_retries_then_skips() {
+        let mut instruction = test_instruction(Recurrence::Weekly, None);
+
+        apply_outcome(&mut instruction, Err(CanisterError::InsufficientBalance), DAY);
+        assert_eq!(instruction.retry_count, 1);
+        assert_eq!(instruction.scheduled_at, DAY);
+        assert_eq!(instruction.next_run_at, DAY + DAY / 24);
+
+        apply_outcome(&mut instruction, Err(CanisterError::InsufficientBalance), DAY + DAY / 24);
+        assert_eq!(instruction.retry_count, 2);
+
+        // Retries exhausted: skip to next week
+        apply_outcome(&mut instruction, Err(CanisterError::InsufficientBalance), DAY + DAY / 12);
+        assert_eq!(instruction.retry_count, 0);
+        assert_eq!(instruction.status, InstructionStatus::Active);
+        assert_eq!(instruction.next_run_at, 8 * DAY);
+    }
+
+    #[test]
+    fn test_once_fails_on_non_retryable_error() {
+        let mut instruction = test_instruction(Recurrence::Once, None);
+        apply_outcome(&mut instruction, Err(CanisterError::UserNotFound), DAY);
+        assert_eq!(instruction.status, InstructionStatus::Failed);
+        assert!(instruction.last_error.is_some());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::storage::{get_stock_cache, set_stock_cache, set_stock_subscription, get_sse_room, set_sse_room};
-use crate::types::{Stock, StockCache, StockPrice, StockMetrics, StockSubscription};
+use crate::types::{Stock, StockCache, StockPrice, StockMetrics, StockSubscription, MetricProvenance};
 use crate::error::CanisterError;
 use ic_cdk::api::time;
 use std::collections::HashMap;
@@ -10,6 +10,7 @@ const STOCK_RATE_LIMIT: u64 = 30_000_000_000; // 30 seconds between requests for
 const MAX_PRICE_HISTORY_DAYS: usize = 7; // One week of daily data
 const MAX_ACCESS_COUNT_PER_PERIOD: u32 = 100; // Rate limiting
 const USER_ACTIVITY_WINDOW: u64 = 6 * 60 * 60 * 1_000_000_000; // 6 hours in nanoseconds
+const STOCK_HEADLINE_COUNT: usize = 6; // Headlines carried on Stock.news
 
 // Global market summary cache (shared across all users)
 use std::cell::RefCell;
@@ -100,6 +101,7 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
             industry_avg_pe: 15.2,
             outstanding_shares: 6_000_000_000,
             volatility: 0.25,
+            provenance: Some(MetricProvenance::estimated()),
         },
         "TCS" => StockMetrics {
             market_cap: 12_000_000_000_000.0, // 12 trillion
@@ -110,6 +112,7 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
             industry_avg_pe: 25.8,
             outstanding_shares: 3_750_000_000,
             volatility: 0.22,
+            provenance: Some(MetricProvenance::estimated()),
         },
         "INFY" => StockMetrics {
             market_cap: 6_000_000_000_000.0, // 6 trillion
@@ -120,6 +123,7 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
             industry_avg_pe: 25.8,
             outstanding_shares: 4_150_000_000,
             volatility: 0.28,
+            provenance: Some(MetricProvenance::estimated()),
         },
         _ => StockMetrics {
             market_cap: 2_000_000_000_000.0, // 2 trillion default
@@ -130,6 +134,7 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
             industry_avg_pe: 20.5,
             outstanding_shares: 2_000_000_000,
             volatility: 0.32,
+            provenance: Some(MetricProvenance::estimated()),
         },
     };
     
@@ -147,12 +152,8 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
         _ => stock_id,
     };
     
-    // Generate mock news based on stock performance
-    let news = vec![
-        format!("{} reports strong quarterly results with {}% growth", stock_name, metrics.business_growth),
-        format!("Analysts upgrade {} target price citing robust fundamentals", stock_name),
-        format!("{} announces new strategic initiatives for digital transformation", stock_name),
-    ];
+    let news = crate::news::headlines(stock_id, STOCK_HEADLINE_COUNT);
+    let instrument = crate::instruments::lookup(stock_id);
     
     Ok(Stock {
         id: stock_id.to_string(),
@@ -163,6 +164,8 @@ pub fn generate_mock_stock_data(stock_id: &str) -> Result<Stock, CanisterError>
         metrics,
         news,
         last_update: current_time,
+        currency: Some(instrument.currency),
+        exchange: Some(instrument.exchange),
     })
 }
 
@@ -195,7 +198,7 @@ pub async fn fetch_real_stock_data(stock_id: &str) -> Result<Stock, CanisterErro
     ic_cdk::println!("🚀 Fetching REAL data for {} from Polygon.io", stock_id);
     
     // 1. Fetch 7-day historical price data
-    let price_history = crate::http_client::fetch_polygon_historical(stock_id, 7).await
+    let price_history = crate::market_data::stock_history(stock_id, 7).await
         .map_err(|e| {
             ic_cdk::println!("❌ Failed to fetch historical data for {}: {}", stock_id, e);
             CanisterError::internal_error(format!("HTTP outcall failed: {}", e))
@@ -206,34 +209,29 @@ pub async fn fetch_real_stock_data(stock_id: &str) -> Result<Stock, CanisterErro
     }
     
     // 2. Fetch stock details (market cap, shares, name)
-    let details = crate::http_client::fetch_polygon_stock_details(stock_id).await
+    let details = crate::market_data::stock_details(stock_id).await
         .map_err(|e| {
             ic_cdk::println!("❌ Failed to fetch stock details for {}: {}", stock_id, e);
             CanisterError::internal_error(format!("HTTP outcall failed: {}", e))
         })?;
     
-    // 3. Calculate real metrics from historical data
+    // 3. Calculate real metrics from historical data and financial statements
     let current_price = price_history.last().unwrap().close;
+    let fundamentals = crate::fundamentals::latest(stock_id).await;
     let metrics = crate::http_client::calculate_metrics(
         stock_id,
         current_price,
         &price_history,
         &details,
+        fundamentals.as_ref(),
     );
     
-    // 4. Generate realistic news (this can stay mock for now)
-    let stock_name = &details.name;
-    let news = vec![
-        format!("{} reports strong quarterly earnings, beats analyst expectations", stock_name),
-        format!("Market analysts maintain 'Buy' rating on {} with price target of ${:.2}", stock_name, current_price * 1.15),
-        format!("{} announces $2B share buyback program", stock_name),
-        format!("Institutional investors increase stake in {} by 12%", stock_name),
-        format!("Analysts upgrade {} target price citing robust fundamentals", stock_name),
-        format!("{} announces new strategic initiatives for digital transformation", stock_name),
-    ];
+    // 4. Latest stored headlines
+    let news = crate::news::headlines(stock_id, STOCK_HEADLINE_COUNT);
+    let instrument = crate::instruments::lookup(stock_id);
     
-    ic_cdk::println!("✅ Successfully fetched REAL data for {}: price=${:.2}, market_cap=${:.0}, pe={:.2}", 
-        stock_id, current_price, metrics.market_cap, metrics.pe_ratio);
+    ic_cdk::println!("✅ Successfully fetched REAL data for {}: price={:.2} {}, market_cap={:.0}, pe={:.2}", 
+        stock_id, current_price, instrument.currency, metrics.market_cap, metrics.pe_ratio);
     
     Ok(Stock {
         id: stock_id.to_string(),
@@ -244,6 +242,8 @@ pub async fn fetch_real_stock_data(stock_id: &str) -> Result<Stock, CanisterErro
         metrics,
         news,
         last_update: current_time,
+        currency: Some(instrument.currency),
+        exchange: Some(instrument.exchange),
     })
 }
 
@@ -280,6 +280,7 @@ pub async fn get_cached_stock_data_async(stock_id: &str) -> Result<Stock, Canist
     };
     
     set_stock_cache(stock_id, &cache);
+    crate::orders::evaluate_orders(stock_id, stock_data.current_price, current_time);
     
     Ok(stock_data)
 }
@@ -336,7 +337,7 @@ pub fn broadcast_stock_update(stock_id: &str, stock_data: &Stock) -> Result<usiz
 }
 
 // Broadcast stock news update
-pub fn broadcast_stock_news(stock_id: &str, news: &[String]) -> Result<usize, CanisterError> {
+pub fn broadcast_stock_news(stock_id: &str, news: &[crate::types::NewsItem]) -> Result<usize, CanisterError> {
     let room_id = format!("stock_{}", stock_id);
     
     let event_data = serde_json::json!({
@@ -352,6 +353,30 @@ pub fn broadcast_stock_news(stock_id: &str, news: &[String]) -> Result<usize, Ca
     Ok(connection_ids.len())
 }
 
+// Broadcast a conditional order fill to the stock's room
+pub fn broadcast_order_fill(order: &crate::types::StockOrder) -> Result<usize, CanisterError> {
+    let room_id = format!("stock_{}", order.stock_id);
+
+    let event_data = serde_json::json!({
+        "type": "order_filled",
+        "stock_id": order.stock_id,
+        "order_id": order.id,
+        "wallet_address": order.wallet_address,
+        "side": format!("{:?}", order.side),
+        "order_type": format!("{:?}", order.order_type),
+        "quantity": order.quantity,
+        "trigger_price": order.trigger_price,
+        "fill_price": order.fill_price,
+        "trade_id": order.trade_id,
+        "timestamp": ic_cdk::api::time()
+    });
+
+    let event_type = crate::types::SseEventType::RoomState; // Reuse existing type
+
+    let connection_ids = crate::sse::broadcast_event(&room_id, event_type, event_data)?;
+    Ok(connection_ids.len())
+}
+
 // Get stock data with caching (SYNC version for backward compatibility)
 pub fn get_cached_stock_data(stock_id: &str) -> Result<Stock, CanisterError> {
     // For sync calls, return cached data or mock fallback
@@ -361,8 +386,14 @@ pub fn get_cached_stock_data(stock_id: &str) -> Result<Stock, CanisterError> {
         }
     }
     
-    // Fallback to mock data if cache miss and async not available
-    generate_mock_stock_data(stock_id)
+    // Fallback to mock data if cache miss and async not available,
+    // priced by the market simulator when it covers this stock
+    let mut stock = generate_mock_stock_data(stock_id)?;
+    if let Some((current_price, price_history)) = crate::market_sim::simulated_prices(stock_id, MAX_PRICE_HISTORY_DAYS as u32, time()) {
+        stock.current_price = current_price;
+        stock.price_history = price_history;
+    }
+    Ok(stock)
 }
 
 // Start periodic refresh timer (30 minutes)
@@ -384,6 +415,10 @@ fn start_periodic_refresh() {
                     match fetch_all_real_market_data().await {
                         Ok(market_data) => {
                             let now = time();
+                            let filled = crate::orders::evaluate_market(&market_data, now);
+                            if filled > 0 {
+                                ic_cdk::println!("📈 Filled {} stock orders on refresh", filled);
+                            }
                             MARKET_SUMMARY_CACHE.with(|cache| {
                                 *cache.borrow_mut() = Some((market_data, now));
                             });
@@ -410,21 +445,70 @@ fn start_periodic_refresh() {
     });
 }
 
-// Fetch all real market data from Polygon.io (NO MOCK DATA)
+// Data for a stock that prices can be traded on: the last fetched data,
+// priced by the market simulator when it covers this stock. Unlike
+// get_cached_stock_data this never falls back to generated mock prices.
+pub fn get_quoted_stock(stock_id: &str) -> Result<Stock, CanisterError> {
+    let simulated = crate::market_sim::simulated_prices(stock_id, MAX_PRICE_HISTORY_DAYS as u32, time());
+    let mut stock = match (last_fetched_stock(stock_id), &simulated) {
+        (Some(stock), _) => stock,
+        (None, Some(_)) => generate_mock_stock_data(stock_id)?,
+        (None, None) => return Err(CanisterError::NotFound(format!("No market data for {}", stock_id))),
+    };
+    if let Some((current_price, price_history)) = simulated {
+        stock.current_price = current_price;
+        stock.price_history = price_history;
+    }
+    Ok(stock)
+}
+
+// Last fetched data for a stock, from the market summary or the stock cache
+fn last_fetched_stock(stock_id: &str) -> Option<Stock> {
+    let summarized = MARKET_SUMMARY_CACHE.with(|cache| {
+        cache.borrow().as_ref().and_then(|(data, _)| data.get(stock_id).cloned())
+    });
+    summarized.or_else(|| get_stock_cache(stock_id).map(|cache| cache.stock_data))
+}
+
+// Every stock held in the market summary or the stock cache, whatever its
+// age; the summary's copy wins when both hold a stock. Never makes outcalls.
+pub fn cached_stocks() -> Vec<Stock> {
+    let mut stocks: HashMap<String, Stock> = crate::storage::get_all_cached_stock_ids()
+        .into_iter()
+        .filter_map(|stock_id| get_stock_cache(&stock_id).map(|cache| (stock_id, cache.stock_data)))
+        .collect();
+    MARKET_SUMMARY_CACHE.with(|cache| {
+        if let Some((data, _)) = &*cache.borrow() {
+            stocks.extend(data.iter().map(|(stock_id, stock)| (stock_id.clone(), stock.clone())));
+        }
+    });
+    stocks.into_values().collect()
+}
+
+// Fetch all real market data from Polygon.io and the NSE/BSE feed (NO MOCK DATA)
+// Covers the enabled stock universe in priority order. Stocks whose exchange
+// has not traded since they were last fetched keep that data, saving outcalls.
 async fn fetch_all_real_market_data() -> Result<HashMap<String, Stock>, CanisterError> {
-    let popular_stocks = vec![
-        "AAPL", "GOOGL", "MSFT", "AMZN", "TSLA",
-        "NVDA", "META", "NFLX", "AMD", "INTC"
-    ];
+    let universe = crate::universe::refreshed_stocks();
+    let now = time();
     
     let mut market_data = HashMap::new();
     let mut failed_stocks = Vec::new();
     
     // Fetch REAL data for each stock (NO MOCK FALLBACK)
-    for stock_id in popular_stocks {
+    for entry in &universe {
+        let stock_id = entry.symbol.as_str();
+        if let Some(stock_data) = last_fetched_stock(stock_id) {
+            if !crate::market_calendar::needs_refresh(stock_id, stock_data.last_update, now) {
+                ic_cdk::println!("💤 Market closed for {}, keeping last fetched data", stock_id);
+                market_data.insert(stock_id.to_string(), stock_data);
+                continue;
+            }
+        }
         ic_cdk::println!("🔄 Fetching REAL data for {}", stock_id);
         match fetch_real_stock_data(stock_id).await {
-            Ok(stock_data) => {
+            Ok(mut stock_data) => {
+                crate::universe::apply_display(entry, &mut stock_data);
                 market_data.insert(stock_id.to_string(), stock_data);
                 ic_cdk::println!("✅ Successfully fetched {}", stock_id);
             }
@@ -494,6 +578,7 @@ pub async fn get_market_summary_async() -> Result<HashMap<String, Stock>, Canist
     MARKET_SUMMARY_CACHE.with(|cache| {
         *cache.borrow_mut() = Some((market_data.clone(), now));
     });
+    crate::orders::evaluate_market(&market_data, now);
     
     // Start periodic 30-minute refresh
     start_periodic_refresh();
@@ -520,15 +605,11 @@ pub fn get_market_summary() -> Result<HashMap<String, Stock>, CanisterError> {
         
         // No cache available - generate mock data
         ic_cdk::println!("⚠️  No cache available, returning mock data");
-        let popular_stocks = vec![
-            "AAPL", "GOOGL", "MSFT", "AMZN", "TSLA",
-            "NVDA", "META", "NFLX", "AMD", "INTC"
-        ];
-        
         let mut market_data = HashMap::new();
-        for stock_id in popular_stocks {
-            if let Ok(stock_data) = get_cached_stock_data(stock_id) {
-                market_data.insert(stock_id.to_string(), stock_data);
+        for entry in crate::universe::enabled_stocks() {
+            if let Ok(mut stock_data) = get_cached_stock_data(&entry.symbol) {
+                crate::universe::apply_display(&entry, &mut stock_data);
+                market_data.insert(entry.symbol.clone(), stock_data);
             }
         }
         
@@ -543,6 +624,21 @@ pub fn broadcast_market_summary() -> Result<usize, CanisterError> {
     let event_data = serde_json::json!({
         "type": "market_summary",
         "stocks": market_data,
+        "indices": crate::indices::list_index_values(),
+        "timestamp": ic_cdk::api::time()
+    });
+    
+    let event_type = crate::types::SseEventType::RoomState; // Reuse existing type
+    
+    let connection_ids = crate::sse::broadcast_event("market_global", event_type, event_data)?;
+    Ok(connection_ids.len())
+}
+
+// Broadcast index values to the market room
+pub fn broadcast_index_values(values: &[crate::types::IndexValue]) -> Result<usize, CanisterError> {
+    let event_data = serde_json::json!({
+        "type": "index_update",
+        "indices": values,
         "timestamp": ic_cdk::api::time()
     });
     
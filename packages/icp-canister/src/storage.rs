@@ -1,9 +1,10 @@
 use crate::types::*;
 use crate::error::*;
 use std::cell::RefCell;
+use std::collections::HashMap;
 // Removed unused import
 use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
-use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell};
 
 type Memory = VirtualMemory<DefaultMemoryImpl>;
 
@@ -65,6 +66,261 @@ thread_local! {
             MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7)))
         )
     );
+
+    // Transaction log ((wallet, sequence) -> transaction)
+    static TRANSACTION_LOG: RefCell<StableBTreeMap<TransactionKey, Web3Transaction, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(8)))
+        )
+    );
+
+    // Next transaction sequence per wallet
+    static TRANSACTION_COUNTERS: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9)))
+        )
+    );
+
+    // Standing instruction storage (instruction id -> instruction)
+    static STANDING_INSTRUCTION_STORAGE: RefCell<StableBTreeMap<String, StandingInstruction, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10)))
+        )
+    );
+
+    // Savings goal storage (goal id -> goal)
+    static SAVINGS_GOAL_STORAGE: RefCell<StableBTreeMap<String, SavingsGoal, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11)))
+        )
+    );
+
+    // Currency registry (symbol -> definition)
+    static CURRENCY_REGISTRY: RefCell<StableBTreeMap<String, CurrencyDefinition, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(12)))
+        )
+    );
+
+    // Balances of registry currencies ((wallet, symbol) -> amount).
+    // Rupees and tokens stay in UserData.dual_balance.
+    static ASSET_BALANCES: RefCell<StableBTreeMap<BalanceKey, f64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(13)))
+        )
+    );
+
+    // Savings-account interest configuration
+    static INTEREST_CONFIG: RefCell<StableCell<InterestConfig, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(14))),
+            InterestConfig::default(),
+        ).expect("Failed to initialize interest config")
+    );
+
+    // Per-wallet interest accrual state
+    static INTEREST_ACCOUNTS: RefCell<StableBTreeMap<String, InterestAccount, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(15)))
+        )
+    );
+
+    // Central bank policy currently in force
+    static MONETARY_POLICY: RefCell<StableCell<MonetaryPolicy, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(16))),
+            MonetaryPolicy::default(),
+        ).expect("Failed to initialize monetary policy")
+    );
+
+    // Scheduled and past rate decisions (decision id -> decision)
+    static RATE_DECISIONS: RefCell<StableBTreeMap<String, RateDecision, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(17)))
+        )
+    );
+
+    // Rate history ((effective timestamp, sequence) -> rates in force from then
+    // on). Decisions applied in the same tick share a timestamp and are told
+    // apart by the sequence.
+    static RATE_HISTORY: RefCell<StableBTreeMap<(u64, u64), RateRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(18)))
+        )
+    );
+
+    // When each price feed was last updated (symbol -> timestamp)
+    static PRICE_FEED_UPDATED_AT: RefCell<StableBTreeMap<String, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(19)))
+        )
+    );
+
+    // Oracle peg configuration for the rupee/token rate
+    static PEG_CONFIG: RefCell<StableCell<PegConfig, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(20))),
+            PegConfig::default(),
+        ).expect("Failed to initialize peg config")
+    );
+
+    // Paper trading holdings ((wallet, stock) -> holding)
+    static HOLDINGS: RefCell<StableBTreeMap<HoldingKey, Holding, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(21)))
+        )
+    );
+
+    // Paper trading fills ((wallet, sequence) -> trade)
+    static STOCK_TRADES: RefCell<StableBTreeMap<TransactionKey, StockTrade, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(22)))
+        )
+    );
+
+    // Conditional stock orders (order_id -> order)
+    static STOCK_ORDERS: RefCell<StableBTreeMap<String, StockOrder, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(23)))
+        )
+    );
+
+    // Player order book orders (order_id -> order). Resting and per-wallet
+    // lookups go through the indexes below.
+    static BOOK_ORDERS: RefCell<StableBTreeMap<String, BookOrder, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(24)))
+        )
+    );
+
+    // Order book trade tape (trade sequence -> trade)
+    static BOOK_TRADES: RefCell<StableBTreeMap<u64, BookTrade, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(25)))
+        )
+    );
+
+    // Resting order book orders by (base, quote, side, price, sequence) -> order_id
+    static BOOK_RESTING_INDEX: RefCell<StableBTreeMap<BookLevelKey, String, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(40)))
+        )
+    );
+
+    // Order book orders by (wallet, order_id)
+    static BOOK_WALLET_INDEX: RefCell<StableBTreeMap<WalletBookKey, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(41)))
+        )
+    );
+
+    // Order book trades by (base, quote, trade_id)
+    static BOOK_TRADE_INDEX: RefCell<StableBTreeMap<BookTradeKey, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(42)))
+        )
+    );
+
+    // Next order book sequence number, shared by all instruments
+    static BOOK_SEQUENCE: RefCell<StableCell<u64, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(26))),
+            0,
+        ).expect("Failed to initialize order book sequence")
+    );
+
+    // Portfolio valuation series ((wallet, timestamp) -> snapshot)
+    static PORTFOLIO_SNAPSHOTS: RefCell<StableBTreeMap<SnapshotKey, PortfolioSnapshot, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(27)))
+        )
+    );
+
+    // Watchlists (watchlist_id -> watchlist)
+    static WATCHLISTS: RefCell<StableBTreeMap<String, Watchlist, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(28)))
+        )
+    );
+
+    // Price alerts (alert_id -> alert)
+    static PRICE_ALERTS: RefCell<StableBTreeMap<String, PriceAlert, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(29)))
+        )
+    );
+
+    // OHLCV candles ((symbol, resolution, bucket_start) -> candle)
+    static CANDLES: RefCell<StableBTreeMap<CandleKey, Candle, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(30)))
+        )
+    );
+
+    // Which market data provider serves stocks and crypto
+    static MARKET_DATA_CONFIG: RefCell<StableCell<MarketDataConfig, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(31))),
+            MarketDataConfig::default(),
+        ).expect("Failed to initialize market data config")
+    );
+
+    // Offline market simulator configuration and progress
+    static SIMULATOR_STATE: RefCell<StableCell<SimulatorState, Memory>> = RefCell::new(
+        StableCell::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(32))),
+            SimulatorState::default(),
+        ).expect("Failed to initialize simulator state")
+    );
+
+    // Corporate actions calendar (action_id -> action)
+    static CORPORATE_ACTIONS: RefCell<StableBTreeMap<String, CorporateAction, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(33)))
+        )
+    );
+
+    // News items ((symbol, published_at, id) -> item)
+    static NEWS_ITEMS: RefCell<StableBTreeMap<NewsKey, NewsItem, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(34)))
+        )
+    );
+
+    // Latest financial statement figures (symbol -> fundamentals)
+    static FUNDAMENTALS: RefCell<StableBTreeMap<String, Fundamentals, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(35)))
+        )
+    );
+
+    // Currency pair rates ("BASE/QUOTE" -> rate)
+    static FX_RATES: RefCell<StableBTreeMap<String, FxRate, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(36)))
+        )
+    );
+
+    // Stocks the market summary covers (symbol -> entry)
+    static STOCK_UNIVERSE: RefCell<StableBTreeMap<String, UniverseStock, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(37)))
+        )
+    );
+
+    // Exchange holidays and half-days ("EXCHANGE/YYYY-MM-DD" -> holiday)
+    static MARKET_HOLIDAYS: RefCell<StableBTreeMap<String, MarketHoliday, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(38)))
+        )
+    );
+
+    // Index and basket definitions (index_id -> definition)
+    static INDICES: RefCell<StableBTreeMap<String, IndexDefinition, Memory>> = RefCell::new(
+        StableBTreeMap::init(
+            MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(39)))
+        )
+    );
 }
 
 // Initialize the canister state
@@ -75,6 +331,7 @@ pub fn init_state() {
         
         // Initialize global settings with default achievement definitions
         state.global_settings = create_default_global_settings();
+        state.global_settings.exchange_rate = get_monetary_policy().exchange_rate;
     });
 }
 
@@ -139,11 +396,41 @@ pub fn restore_state() {
             }
         });
         
+        // Move any transactions still embedded in user data into the log
+        let migrated = migrate_embedded_transactions(&mut state.users);
+        if migrated > 0 {
+            USER_STORAGE.with(|storage| {
+                let mut storage = storage.borrow_mut();
+                for (address, user_data) in &state.users {
+                    storage.insert(address.clone(), user_data.clone());
+                }
+            });
+        }
+        
         // Restore global settings
         state.global_settings = create_default_global_settings();
+        state.global_settings.exchange_rate = get_monetary_policy().exchange_rate;
     });
 }
 
+// Drain legacy `UserData.transactions` vectors into the stable transaction log.
+// Returns the number of transactions migrated.
+fn migrate_embedded_transactions(users: &mut HashMap<String, UserData>) -> usize {
+    let mut migrated = 0;
+    
+    for (address, user_data) in users.iter_mut() {
+        let mut legacy = std::mem::take(&mut user_data.transactions);
+        legacy.sort_by_key(|tx| tx.timestamp);
+        
+        for transaction in legacy {
+            append_transaction(address, transaction);
+            migrated += 1;
+        }
+    }
+    
+    migrated
+}
+
 // User data operations
 pub fn get_user_data(wallet_address: &str) -> Option<UserData> {
     STATE.with(|state| {
@@ -173,6 +460,22 @@ pub fn update_user_data(wallet_address: &str, user_data: UserData) -> CanisterRe
     })
 }
 
+// Store a user record along with the balance changes made to it outside the
+// user entry, so neither lands without the other
+pub fn commit_user_data(wallet_address: &str, user_data: UserData, writes: PendingWrites) -> CanisterResult<()> {
+    update_user_data(wallet_address, user_data)?;
+
+    for (symbol, balance) in writes.asset_balances {
+        set_asset_balance(wallet_address, &symbol, balance);
+    }
+    if writes.accrued_interest > 0.0 {
+        let mut account = get_interest_account(wallet_address).unwrap_or_default();
+        account.accrued_interest += writes.accrued_interest;
+        set_interest_account(wallet_address, &account);
+    }
+    Ok(())
+}
+
 pub fn get_or_create_user_data(wallet_address: &str) -> UserData {
     match get_user_data(wallet_address) {
         Some(user_data) => user_data,
@@ -256,6 +559,12 @@ pub fn get_exchange_rate() -> f64 {
     })
 }
 
+pub fn set_exchange_rate(rate: f64) {
+    STATE.with(|state| {
+        state.borrow_mut().global_settings.exchange_rate = rate;
+    });
+}
+
 // Staking APY removed
 
 // Helper function to create default global settings
@@ -291,6 +600,32 @@ fn create_default_global_settings() -> GlobalSettings {
             }),
         },
 // Staking achievements removed
+        Achievement {
+            id: "goal_getter".to_string(),
+            title: "Goal Getter".to_string(),
+            description: "Complete your first savings goal".to_string(),
+            category: AchievementCategory::Saving,
+            rarity: AchievementRarity::Common,
+            unlocked: false,
+            unlocked_at: None,
+            reward: Some(AchievementReward {
+                reward_type: "rupees".to_string(),
+                amount: 500.0,
+            }),
+        },
+        Achievement {
+            id: "super_saver".to_string(),
+            title: "Super Saver".to_string(),
+            description: "Complete five savings goals".to_string(),
+            category: AchievementCategory::Saving,
+            rarity: AchievementRarity::Epic,
+            unlocked: false,
+            unlocked_at: None,
+            reward: Some(AchievementReward {
+                reward_type: "tokens".to_string(),
+                amount: 25.0,
+            }),
+        },
         Achievement {
             id: "defi_master".to_string(),
             title: "DeFi Master".to_string(),
@@ -329,6 +664,13 @@ pub fn cleanup_expired_sessions() {
     });
 }
 
+// Addresses of every registered user
+pub fn get_all_user_addresses() -> Vec<String> {
+    STATE.with(|state| {
+        state.borrow().users.keys().cloned().collect()
+    })
+}
+
 // Get all users count (for monitoring)
 pub fn get_users_count() -> usize {
     STATE.with(|state| {
@@ -341,6 +683,13 @@ pub fn set_price_feed(symbol: &str, price: f64) {
     PRICE_FEED_STORAGE.with(|storage| {
         storage.borrow_mut().insert(symbol.to_string(), price);
     });
+    PRICE_FEED_UPDATED_AT.with(|updated| {
+        updated.borrow_mut().insert(symbol.to_string(), ic_cdk::api::time());
+    });
+}
+
+pub fn get_price_feed_updated_at(symbol: &str) -> Option<u64> {
+    PRICE_FEED_UPDATED_AT.with(|updated| updated.borrow().get(&symbol.to_string()))
 }
 
 pub fn get_price_feed(symbol: &str) -> Option<f64> {
@@ -352,112 +701,395 @@ pub fn get_all_price_feeds() -> Vec<(String, f64)> {
     PRICE_FEED_STORAGE.with(|storage| storage.borrow().iter().map(|(k, v)| (k.clone(), v)).collect())
 }
 
-// Get active sessions count (for monitoring)
-pub fn get_active_sessions_count() -> usize {
-    STATE.with(|state| {
-        let state = state.borrow();
-        let now = ic_cdk::api::time();
-        let timeout = state.global_settings.session_timeout;
-        
-        state
-            .sessions
-            .values()
-            .filter(|session| now - session.last_activity < timeout)
-            .count()
+// Transaction log operations
+pub fn append_transaction(wallet_address: &str, transaction: Web3Transaction) -> u64 {
+    let sequence = TRANSACTION_COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        let next = counters.get(&wallet_address.to_string()).unwrap_or(0);
+        counters.insert(wallet_address.to_string(), next + 1);
+        next
+    });
+    
+    TRANSACTION_LOG.with(|log| {
+        let key = TransactionKey {
+            wallet_address: wallet_address.to_string(),
+            sequence,
+        };
+        log.borrow_mut().insert(key, transaction);
+    });
+    
+    sequence
+}
+
+// Transactions for a wallet in the order they were recorded
+pub fn get_transactions(wallet_address: &str) -> Vec<Web3Transaction> {
+    let start = TransactionKey {
+        wallet_address: wallet_address.to_string(),
+        sequence: 0,
+    };
+    let end = TransactionKey {
+        wallet_address: wallet_address.to_string(),
+        sequence: u64::MAX,
+    };
+    TRANSACTION_LOG.with(|log| {
+        log.borrow().range(start..=end).map(|(_, tx)| tx).collect()
     })
 }
 
-// SSE storage operations
-pub fn set_sse_room(room_id: &str, room: SseRoom) {
-    SSE_ROOM_STORAGE.with(|storage| {
-        storage.borrow_mut().insert(room_id.to_string(), room);
-    });
+pub fn get_transaction_count(wallet_address: &str) -> u64 {
+    TRANSACTION_COUNTERS.with(|counters| {
+        counters.borrow().get(&wallet_address.to_string()).unwrap_or(0)
+    })
 }
 
-pub fn get_sse_room(room_id: &str) -> Option<SseRoom> {
-    SSE_ROOM_STORAGE.with(|storage| {
-        storage.borrow().get(&room_id.to_string())
+// Every recorded transaction once, taken from the originating wallet's log
+// (transfers are also logged against the recipient)
+pub fn get_all_transactions() -> Vec<Web3Transaction> {
+    TRANSACTION_LOG.with(|log| {
+        log.borrow()
+            .iter()
+            .filter(|(key, tx)| key.wallet_address == tx.from)
+            .map(|(_, tx)| tx)
+            .collect()
     })
 }
 
-pub fn remove_sse_room(room_id: &str) {
-    SSE_ROOM_STORAGE.with(|storage| {
-        storage.borrow_mut().remove(&room_id.to_string());
+pub fn get_total_transactions_count() -> usize {
+    TRANSACTION_LOG.with(|log| log.borrow().len() as usize)
+}
+
+// Standing instruction operations
+pub fn set_standing_instruction(instruction: &StandingInstruction) {
+    STANDING_INSTRUCTION_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(instruction.id.clone(), instruction.clone());
     });
 }
 
-pub fn get_all_sse_room_ids() -> Vec<String> {
-    SSE_ROOM_STORAGE.with(|storage| {
-        storage.borrow().iter().map(|(k, _)| k).collect()
+pub fn get_standing_instruction(instruction_id: &str) -> Option<StandingInstruction> {
+    STANDING_INSTRUCTION_STORAGE.with(|storage| {
+        storage.borrow().get(&instruction_id.to_string())
     })
 }
 
-pub fn set_sse_connection(connection_id: &str, connection: SseConnection) {
-    SSE_CONNECTION_STORAGE.with(|storage| {
-        storage.borrow_mut().insert(connection_id.to_string(), connection);
-    });
+pub fn get_standing_instructions_for_wallet(wallet_address: &str) -> Vec<StandingInstruction> {
+    STANDING_INSTRUCTION_STORAGE.with(|storage| {
+        storage.borrow()
+            .iter()
+            .filter(|(_, instruction)| instruction.wallet_address == wallet_address)
+            .map(|(_, instruction)| instruction)
+            .collect()
+    })
 }
 
-pub fn get_sse_connection(connection_id: &str) -> Option<SseConnection> {
-    SSE_CONNECTION_STORAGE.with(|storage| {
-        storage.borrow().get(&connection_id.to_string())
+pub fn get_due_standing_instructions(now: u64) -> Vec<StandingInstruction> {
+    STANDING_INSTRUCTION_STORAGE.with(|storage| {
+        storage.borrow()
+            .iter()
+            .filter(|(_, instruction)| {
+                instruction.status == InstructionStatus::Active && instruction.next_run_at <= now
+            })
+            .map(|(_, instruction)| instruction)
+            .collect()
     })
 }
 
-pub fn remove_sse_connection(connection_id: &str) {
-    SSE_CONNECTION_STORAGE.with(|storage| {
-        storage.borrow_mut().remove(&connection_id.to_string());
+// Savings goal operations
+pub fn set_savings_goal(goal: &SavingsGoal) {
+    SAVINGS_GOAL_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(goal.id.clone(), goal.clone());
     });
 }
 
-pub fn get_all_sse_connection_ids() -> Vec<String> {
-    SSE_CONNECTION_STORAGE.with(|storage| {
-        storage.borrow().iter().map(|(k, _)| k).collect()
+pub fn get_savings_goal(goal_id: &str) -> Option<SavingsGoal> {
+    SAVINGS_GOAL_STORAGE.with(|storage| {
+        storage.borrow().get(&goal_id.to_string())
     })
 }
 
-// Stock cache storage functions
-pub fn set_stock_cache(stock_id: &str, cache: &StockCache) {
-    STOCK_CACHE_STORAGE.with(|storage| {
-        storage.borrow_mut().insert(stock_id.to_string(), cache.clone());
+pub fn remove_savings_goal(goal_id: &str) {
+    SAVINGS_GOAL_STORAGE.with(|storage| {
+        storage.borrow_mut().remove(&goal_id.to_string());
     });
 }
 
-pub fn get_stock_cache(stock_id: &str) -> Option<StockCache> {
-    STOCK_CACHE_STORAGE.with(|storage| {
-        storage.borrow().get(&stock_id.to_string())
+pub fn get_savings_goals_for_wallet(wallet_address: &str) -> Vec<SavingsGoal> {
+    SAVINGS_GOAL_STORAGE.with(|storage| {
+        storage.borrow()
+            .iter()
+            .filter(|(_, goal)| goal.wallet_address == wallet_address)
+            .map(|(_, goal)| goal)
+            .collect()
     })
 }
 
-pub fn remove_stock_cache(stock_id: &str) {
-    STOCK_CACHE_STORAGE.with(|storage| {
-        storage.borrow_mut().remove(&stock_id.to_string());
+// Currency registry operations
+pub fn set_currency(definition: &CurrencyDefinition) {
+    CURRENCY_REGISTRY.with(|registry| {
+        registry.borrow_mut().insert(definition.symbol.clone(), definition.clone());
     });
 }
 
-#[allow(dead_code)]
-pub fn get_all_cached_stock_ids() -> Vec<String> {
-    STOCK_CACHE_STORAGE.with(|storage| {
-        storage.borrow().iter().map(|(k, _)| k).collect()
+pub fn get_currency(symbol: &str) -> Option<CurrencyDefinition> {
+    CURRENCY_REGISTRY.with(|registry| {
+        registry.borrow().get(&symbol.to_string())
     })
 }
 
-// Stock subscription storage functions
-pub fn set_stock_subscription(connection_id: &str, subscription: &StockSubscription) {
-    STOCK_SUBSCRIPTION_STORAGE.with(|storage| {
-        storage.borrow_mut().insert(connection_id.to_string(), subscription.clone());
-    });
+pub fn get_all_currencies() -> Vec<CurrencyDefinition> {
+    CURRENCY_REGISTRY.with(|registry| {
+        registry.borrow().iter().map(|(_, definition)| definition).collect()
+    })
 }
 
-#[allow(dead_code)]
-pub fn get_stock_subscription(connection_id: &str) -> Option<StockSubscription> {
-    STOCK_SUBSCRIPTION_STORAGE.with(|storage| {
-        storage.borrow().get(&connection_id.to_string())
+// Asset balance operations
+pub fn get_asset_balance(wallet_address: &str, symbol: &str) -> f64 {
+    let key = BalanceKey {
+        wallet_address: wallet_address.to_string(),
+        symbol: symbol.to_string(),
+    };
+    ASSET_BALANCES.with(|balances| {
+        balances.borrow().get(&key).unwrap_or(0.0)
     })
 }
 
-#[allow(dead_code)]
-pub fn remove_stock_subscription(connection_id: &str) {
+pub fn set_asset_balance(wallet_address: &str, symbol: &str, amount: f64) {
+    let key = BalanceKey {
+        wallet_address: wallet_address.to_string(),
+        symbol: symbol.to_string(),
+    };
+    ASSET_BALANCES.with(|balances| {
+        let mut balances = balances.borrow_mut();
+        if amount == 0.0 {
+            balances.remove(&key);
+        } else {
+            balances.insert(key, amount);
+        }
+    });
+}
+
+// All non-zero registry currency balances held by a wallet
+pub fn get_asset_balances_for_wallet(wallet_address: &str) -> Vec<(String, f64)> {
+    let start = BalanceKey {
+        wallet_address: wallet_address.to_string(),
+        symbol: String::new(),
+    };
+    ASSET_BALANCES.with(|balances| {
+        balances.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.wallet_address == wallet_address)
+            .map(|(key, amount)| (key.symbol, amount))
+            .collect()
+    })
+}
+
+// Interest operations
+pub fn get_interest_config() -> InterestConfig {
+    INTEREST_CONFIG.with(|config| config.borrow().get().clone())
+}
+
+pub fn set_interest_config(config: InterestConfig) {
+    INTEREST_CONFIG.with(|cell| {
+        cell.borrow_mut().set(config).expect("Failed to store interest config");
+    });
+}
+
+pub fn get_interest_account(wallet_address: &str) -> Option<InterestAccount> {
+    INTEREST_ACCOUNTS.with(|accounts| {
+        accounts.borrow().get(&wallet_address.to_string())
+    })
+}
+
+pub fn set_interest_account(wallet_address: &str, account: &InterestAccount) {
+    INTEREST_ACCOUNTS.with(|accounts| {
+        accounts.borrow_mut().insert(wallet_address.to_string(), account.clone());
+    });
+}
+
+// Central bank operations
+pub fn get_monetary_policy() -> MonetaryPolicy {
+    MONETARY_POLICY.with(|policy| policy.borrow().get().clone())
+}
+
+pub fn set_monetary_policy(policy: MonetaryPolicy) {
+    MONETARY_POLICY.with(|cell| {
+        cell.borrow_mut().set(policy).expect("Failed to store monetary policy");
+    });
+}
+
+pub fn set_rate_decision(decision: &RateDecision) {
+    RATE_DECISIONS.with(|decisions| {
+        decisions.borrow_mut().insert(decision.id.clone(), decision.clone());
+    });
+}
+
+pub fn get_rate_decision(decision_id: &str) -> Option<RateDecision> {
+    RATE_DECISIONS.with(|decisions| {
+        decisions.borrow().get(&decision_id.to_string())
+    })
+}
+
+pub fn get_all_rate_decisions() -> Vec<RateDecision> {
+    RATE_DECISIONS.with(|decisions| {
+        decisions.borrow().iter().map(|(_, decision)| decision).collect()
+    })
+}
+
+pub fn get_due_rate_decisions(now: u64) -> Vec<RateDecision> {
+    RATE_DECISIONS.with(|decisions| {
+        decisions.borrow()
+            .iter()
+            .filter(|(_, decision)| {
+                decision.status == RateDecisionStatus::Scheduled && decision.effective_at <= now
+            })
+            .map(|(_, decision)| decision)
+            .collect()
+    })
+}
+
+pub fn insert_rate_record(record: &RateRecord) {
+    RATE_HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        let sequence = history.len();
+        history.insert((record.effective_at, sequence), record.clone());
+    });
+}
+
+// Records that took effect within [start, end], in the order they were applied
+pub fn get_rate_records(start: u64, end: u64) -> Vec<RateRecord> {
+    RATE_HISTORY.with(|history| {
+        history.borrow().range((start, 0)..=(end, u64::MAX)).map(|(_, record)| record).collect()
+    })
+}
+
+// The record in force at `timestamp` (the latest one applied at or before it)
+pub fn get_rate_record_at(timestamp: u64) -> Option<RateRecord> {
+    RATE_HISTORY.with(|history| {
+        history.borrow().range(..=(timestamp, u64::MAX)).next_back().map(|(_, record)| record)
+    })
+}
+
+pub fn get_first_rate_record() -> Option<RateRecord> {
+    RATE_HISTORY.with(|history| {
+        history.borrow().first_key_value().map(|(_, record)| record)
+    })
+}
+
+// Oracle peg operations
+pub fn get_peg_config() -> PegConfig {
+    PEG_CONFIG.with(|config| config.borrow().get().clone())
+}
+
+pub fn set_peg_config(config: PegConfig) {
+    PEG_CONFIG.with(|cell| {
+        cell.borrow_mut().set(config).expect("Failed to store peg config");
+    });
+}
+
+// Get active sessions count (for monitoring)
+pub fn get_active_sessions_count() -> usize {
+    STATE.with(|state| {
+        let state = state.borrow();
+        let now = ic_cdk::api::time();
+        let timeout = state.global_settings.session_timeout;
+        
+        state
+            .sessions
+            .values()
+            .filter(|session| now - session.last_activity < timeout)
+            .count()
+    })
+}
+
+// SSE storage operations
+pub fn set_sse_room(room_id: &str, room: SseRoom) {
+    SSE_ROOM_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(room_id.to_string(), room);
+    });
+}
+
+pub fn get_sse_room(room_id: &str) -> Option<SseRoom> {
+    SSE_ROOM_STORAGE.with(|storage| {
+        storage.borrow().get(&room_id.to_string())
+    })
+}
+
+pub fn remove_sse_room(room_id: &str) {
+    SSE_ROOM_STORAGE.with(|storage| {
+        storage.borrow_mut().remove(&room_id.to_string());
+    });
+}
+
+pub fn get_all_sse_room_ids() -> Vec<String> {
+    SSE_ROOM_STORAGE.with(|storage| {
+        storage.borrow().iter().map(|(k, _)| k).collect()
+    })
+}
+
+pub fn set_sse_connection(connection_id: &str, connection: SseConnection) {
+    SSE_CONNECTION_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(connection_id.to_string(), connection);
+    });
+}
+
+pub fn get_sse_connection(connection_id: &str) -> Option<SseConnection> {
+    SSE_CONNECTION_STORAGE.with(|storage| {
+        storage.borrow().get(&connection_id.to_string())
+    })
+}
+
+pub fn remove_sse_connection(connection_id: &str) {
+    SSE_CONNECTION_STORAGE.with(|storage| {
+        storage.borrow_mut().remove(&connection_id.to_string());
+    });
+}
+
+pub fn get_all_sse_connection_ids() -> Vec<String> {
+    SSE_CONNECTION_STORAGE.with(|storage| {
+        storage.borrow().iter().map(|(k, _)| k).collect()
+    })
+}
+
+// Stock cache storage functions
+pub fn set_stock_cache(stock_id: &str, cache: &StockCache) {
+    STOCK_CACHE_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(stock_id.to_string(), cache.clone());
+    });
+}
+
+pub fn get_stock_cache(stock_id: &str) -> Option<StockCache> {
+    STOCK_CACHE_STORAGE.with(|storage| {
+        storage.borrow().get(&stock_id.to_string())
+    })
+}
+
+pub fn remove_stock_cache(stock_id: &str) {
+    STOCK_CACHE_STORAGE.with(|storage| {
+        storage.borrow_mut().remove(&stock_id.to_string());
+    });
+}
+
+pub fn get_all_cached_stock_ids() -> Vec<String> {
+    STOCK_CACHE_STORAGE.with(|storage| {
+        storage.borrow().iter().map(|(k, _)| k).collect()
+    })
+}
+
+// Stock subscription storage functions
+pub fn set_stock_subscription(connection_id: &str, subscription: &StockSubscription) {
+    STOCK_SUBSCRIPTION_STORAGE.with(|storage| {
+        storage.borrow_mut().insert(connection_id.to_string(), subscription.clone());
+    });
+}
+
+#[allow(dead_code)]
+pub fn get_stock_subscription(connection_id: &str) -> Option<StockSubscription> {
+    STOCK_SUBSCRIPTION_STORAGE.with(|storage| {
+        storage.borrow().get(&connection_id.to_string())
+    })
+}
+
+#[allow(dead_code)]
+pub fn remove_stock_subscription(connection_id: &str) {
     STOCK_SUBSCRIPTION_STORAGE.with(|storage| {
         storage.borrow_mut().remove(&connection_id.to_string());
     });
@@ -477,4 +1109,680 @@ pub fn get_stock_subscriptions_for_stock(stock_id: &str) -> Vec<StockSubscriptio
             })
             .collect()
     })
-}
\ No newline at end of file
+}
+
+// Holding storage functions
+pub fn get_holding(wallet_address: &str, stock_id: &str) -> Option<Holding> {
+    let key = HoldingKey {
+        wallet_address: wallet_address.to_string(),
+        stock_id: stock_id.to_string(),
+    };
+    HOLDINGS.with(|holdings| holdings.borrow().get(&key))
+}
+
+pub fn set_holding(holding: &Holding) {
+    let key = HoldingKey {
+        wallet_address: holding.wallet_address.clone(),
+        stock_id: holding.stock_id.clone(),
+    };
+    HOLDINGS.with(|holdings| {
+        holdings.borrow_mut().insert(key, holding.clone());
+    });
+}
+
+// Every holding a wallet has ever had, including closed positions
+pub fn get_holdings_for_wallet(wallet_address: &str) -> Vec<Holding> {
+    let start = HoldingKey {
+        wallet_address: wallet_address.to_string(),
+        stock_id: String::new(),
+    };
+    HOLDINGS.with(|holdings| {
+        holdings.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.wallet_address == wallet_address)
+            .map(|(_, holding)| holding)
+            .collect()
+    })
+}
+
+// Stock trade storage functions
+pub fn append_stock_trade(trade: &StockTrade) -> u64 {
+    let start = TransactionKey {
+        wallet_address: trade.wallet_address.clone(),
+        sequence: 0,
+    };
+    let end = TransactionKey {
+        wallet_address: trade.wallet_address.clone(),
+        sequence: u64::MAX,
+    };
+    STOCK_TRADES.with(|trades| {
+        let mut trades = trades.borrow_mut();
+        let sequence = trades.range(start..=end).next_back().map(|(key, _)| key.sequence + 1).unwrap_or(0);
+        trades.insert(
+            TransactionKey {
+                wallet_address: trade.wallet_address.clone(),
+                sequence,
+            },
+            trade.clone(),
+        );
+        sequence
+    })
+}
+
+pub fn get_stock_trades(wallet_address: &str) -> Vec<StockTrade> {
+    let start = TransactionKey {
+        wallet_address: wallet_address.to_string(),
+        sequence: 0,
+    };
+    let end = TransactionKey {
+        wallet_address: wallet_address.to_string(),
+        sequence: u64::MAX,
+    };
+    STOCK_TRADES.with(|trades| {
+        trades.borrow().range(start..=end).map(|(_, trade)| trade).collect()
+    })
+}
+
+// Stock order storage functions
+pub fn get_holdings_for_stock(stock_id: &str) -> Vec<Holding> {
+    HOLDINGS.with(|holdings| {
+        holdings.borrow()
+            .iter()
+            .filter(|(key, holding)| key.stock_id == stock_id && holding.quantity > 0)
+            .map(|(_, holding)| holding)
+            .collect()
+    })
+}
+
+pub fn set_stock_order(order: &StockOrder) {
+    STOCK_ORDERS.with(|orders| {
+        orders.borrow_mut().insert(order.id.clone(), order.clone());
+    });
+}
+
+pub fn get_stock_order(order_id: &str) -> Option<StockOrder> {
+    STOCK_ORDERS.with(|orders| orders.borrow().get(&order_id.to_string()))
+}
+
+pub fn get_stock_orders_for_wallet(wallet_address: &str) -> Vec<StockOrder> {
+    STOCK_ORDERS.with(|orders| {
+        orders.borrow()
+            .iter()
+            .filter(|(_, order)| order.wallet_address == wallet_address)
+            .map(|(_, order)| order)
+            .collect()
+    })
+}
+
+pub fn get_open_stock_orders(stock_id: &str) -> Vec<StockOrder> {
+    STOCK_ORDERS.with(|orders| {
+        orders.borrow()
+            .iter()
+            .filter(|(_, order)| order.status == StockOrderStatus::Open && order.stock_id == stock_id)
+            .map(|(_, order)| order)
+            .collect()
+    })
+}
+
+pub fn get_expired_stock_orders(now: u64) -> Vec<StockOrder> {
+    STOCK_ORDERS.with(|orders| {
+        orders.borrow()
+            .iter()
+            .filter(|(_, order)| {
+                order.status == StockOrderStatus::Open && order.expires_at.is_some_and(|expires_at| expires_at <= now)
+            })
+            .map(|(_, order)| order)
+            .collect()
+    })
+}
+
+// Order book storage functions
+pub fn next_book_sequence() -> u64 {
+    BOOK_SEQUENCE.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let sequence = *cell.get();
+        cell.set(sequence + 1).expect("Failed to store order book sequence");
+        sequence
+    })
+}
+
+pub fn set_book_order(order: &BookOrder) {
+    let previous = BOOK_ORDERS.with(|orders| orders.borrow_mut().insert(order.id.clone(), order.clone()));
+    BOOK_RESTING_INDEX.with(|index| {
+        let mut index = index.borrow_mut();
+        if let Some(previous) = previous.filter(is_resting) {
+            index.remove(&book_level_key(&previous));
+        }
+        if is_resting(order) {
+            index.insert(book_level_key(order), order.id.clone());
+        }
+    });
+    BOOK_WALLET_INDEX.with(|index| {
+        index.borrow_mut().insert(wallet_book_key(&order.wallet_address, &order.id), ());
+    });
+}
+
+pub fn get_book_order(order_id: &str) -> Option<BookOrder> {
+    BOOK_ORDERS.with(|orders| orders.borrow().get(&order_id.to_string()))
+}
+
+pub fn get_book_orders_for_wallet(wallet_address: &str) -> Vec<BookOrder> {
+    let order_ids: Vec<String> = BOOK_WALLET_INDEX.with(|index| {
+        index.borrow()
+            .range(wallet_book_key(wallet_address, "")..)
+            .take_while(|(key, _)| key.wallet_address == wallet_address)
+            .map(|(key, _)| key.order_id)
+            .collect()
+    });
+    order_ids.iter().filter_map(|order_id| get_book_order(order_id)).collect()
+}
+
+// Resting orders on one instrument, bids then asks, each by ascending price
+// and then time
+pub fn get_resting_book_orders(base: &str, quote: &str) -> Vec<BookOrder> {
+    let start = BookLevelKey {
+        base: base.to_string(),
+        quote: quote.to_string(),
+        side: TradeSide::Buy,
+        price_bits: 0,
+        sequence: 0,
+    };
+    let order_ids: Vec<String> = BOOK_RESTING_INDEX.with(|index| {
+        index.borrow()
+            .range(start..)
+            .take_while(|(key, _)| key.base == base && key.quote == quote)
+            .map(|(_, order_id)| order_id)
+            .collect()
+    });
+    order_ids.iter().filter_map(|order_id| get_book_order(order_id)).collect()
+}
+
+pub fn append_book_trade(trade: &BookTrade) {
+    BOOK_TRADES.with(|trades| {
+        trades.borrow_mut().insert(trade.id, trade.clone());
+    });
+    BOOK_TRADE_INDEX.with(|index| {
+        index.borrow_mut().insert(book_trade_key(&trade.base, &trade.quote, trade.id), ());
+    });
+}
+
+pub fn next_book_trade_id() -> u64 {
+    BOOK_TRADES.with(|trades| {
+        trades.borrow().last_key_value().map(|(id, _)| id + 1).unwrap_or(0)
+    })
+}
+
+// Most recent trades on one instrument, newest first
+pub fn get_recent_book_trades(base: &str, quote: &str, limit: usize) -> Vec<BookTrade> {
+    let trade_ids: Vec<u64> = BOOK_TRADE_INDEX.with(|index| {
+        index.borrow()
+            .range(book_trade_key(base, quote, 0)..=book_trade_key(base, quote, u64::MAX))
+            .rev()
+            .take(limit)
+            .map(|(key, _)| key.id)
+            .collect()
+    });
+    BOOK_TRADES.with(|trades| {
+        let trades = trades.borrow();
+        trade_ids.iter().filter_map(|id| trades.get(id)).collect()
+    })
+}
+
+fn is_resting(order: &BookOrder) -> bool {
+    matches!(order.status, BookOrderStatus::Open | BookOrderStatus::PartiallyFilled)
+}
+
+fn book_level_key(order: &BookOrder) -> BookLevelKey {
+    BookLevelKey {
+        base: order.base.clone(),
+        quote: order.quote.clone(),
+        side: order.side.clone(),
+        price_bits: order.price.to_bits(),
+        sequence: order.sequence,
+    }
+}
+
+fn wallet_book_key(wallet_address: &str, order_id: &str) -> WalletBookKey {
+    WalletBookKey {
+        wallet_address: wallet_address.to_string(),
+        order_id: order_id.to_string(),
+    }
+}
+
+fn book_trade_key(base: &str, quote: &str, id: u64) -> BookTradeKey {
+    BookTradeKey {
+        base: base.to_string(),
+        quote: quote.to_string(),
+        id,
+    }
+}
+
+// Portfolio snapshot storage functions
+pub fn insert_portfolio_snapshot(snapshot: &PortfolioSnapshot) {
+    let key = SnapshotKey {
+        wallet_address: snapshot.wallet_address.clone(),
+        timestamp: snapshot.timestamp,
+    };
+    PORTFOLIO_SNAPSHOTS.with(|snapshots| {
+        snapshots.borrow_mut().insert(key, snapshot.clone());
+    });
+}
+
+// Snapshots taken within [start, end], oldest first
+pub fn get_portfolio_snapshots(wallet_address: &str, start: u64, end: u64) -> Vec<PortfolioSnapshot> {
+    let from = SnapshotKey {
+        wallet_address: wallet_address.to_string(),
+        timestamp: start,
+    };
+    let to = SnapshotKey {
+        wallet_address: wallet_address.to_string(),
+        timestamp: end,
+    };
+    PORTFOLIO_SNAPSHOTS.with(|snapshots| {
+        snapshots.borrow().range(from..=to).map(|(_, snapshot)| snapshot).collect()
+    })
+}
+
+pub fn get_latest_portfolio_snapshot(wallet_address: &str) -> Option<PortfolioSnapshot> {
+    let from = SnapshotKey {
+        wallet_address: wallet_address.to_string(),
+        timestamp: 0,
+    };
+    let to = SnapshotKey {
+        wallet_address: wallet_address.to_string(),
+        timestamp: u64::MAX,
+    };
+    PORTFOLIO_SNAPSHOTS.with(|snapshots| {
+        snapshots.borrow().range(from..=to).next_back().map(|(_, snapshot)| snapshot)
+    })
+}
+
+// Watchlist storage functions
+pub fn set_watchlist(watchlist: &Watchlist) {
+    WATCHLISTS.with(|watchlists| {
+        watchlists.borrow_mut().insert(watchlist.id.clone(), watchlist.clone());
+    });
+}
+
+pub fn get_watchlist(watchlist_id: &str) -> Option<Watchlist> {
+    WATCHLISTS.with(|watchlists| watchlists.borrow().get(&watchlist_id.to_string()))
+}
+
+pub fn remove_watchlist(watchlist_id: &str) {
+    WATCHLISTS.with(|watchlists| {
+        watchlists.borrow_mut().remove(&watchlist_id.to_string());
+    });
+}
+
+pub fn get_watchlists_for_wallet(wallet_address: &str) -> Vec<Watchlist> {
+    WATCHLISTS.with(|watchlists| {
+        watchlists.borrow()
+            .iter()
+            .filter(|(_, watchlist)| watchlist.wallet_address == wallet_address)
+            .map(|(_, watchlist)| watchlist)
+            .collect()
+    })
+}
+
+// Price alert storage functions
+pub fn set_price_alert(alert: &PriceAlert) {
+    PRICE_ALERTS.with(|alerts| {
+        alerts.borrow_mut().insert(alert.id.clone(), alert.clone());
+    });
+}
+
+pub fn get_price_alert(alert_id: &str) -> Option<PriceAlert> {
+    PRICE_ALERTS.with(|alerts| alerts.borrow().get(&alert_id.to_string()))
+}
+
+pub fn get_price_alerts_for_wallet(wallet_address: &str) -> Vec<PriceAlert> {
+    PRICE_ALERTS.with(|alerts| {
+        alerts.borrow()
+            .iter()
+            .filter(|(_, alert)| alert.wallet_address == wallet_address)
+            .map(|(_, alert)| alert)
+            .collect()
+    })
+}
+
+pub fn get_active_price_alerts() -> Vec<PriceAlert> {
+    PRICE_ALERTS.with(|alerts| {
+        alerts.borrow()
+            .iter()
+            .filter(|(_, alert)| alert.status == AlertStatus::Active)
+            .map(|(_, alert)| alert)
+            .collect()
+    })
+}
+
+// Candle storage functions
+pub fn get_candle(key: &CandleKey) -> Option<Candle> {
+    CANDLES.with(|candles| candles.borrow().get(key))
+}
+
+pub fn set_candle(candle: &Candle) {
+    let key = CandleKey {
+        symbol: candle.symbol.clone(),
+        resolution: candle.resolution,
+        bucket_start: candle.bucket_start,
+    };
+    CANDLES.with(|candles| {
+        candles.borrow_mut().insert(key, candle.clone());
+    });
+}
+
+// Candles whose bucket starts within [start, end], oldest first
+pub fn get_candles(symbol: &str, resolution: CandleResolution, start: u64, end: u64) -> Vec<Candle> {
+    let from = CandleKey {
+        symbol: symbol.to_string(),
+        resolution,
+        bucket_start: start,
+    };
+    let to = CandleKey {
+        symbol: symbol.to_string(),
+        resolution,
+        bucket_start: end,
+    };
+    CANDLES.with(|candles| {
+        candles.borrow().range(from..=to).map(|(_, candle)| candle).collect()
+    })
+}
+
+// Drop candles whose bucket starts before `cutoff`. Returns the number removed.
+pub fn remove_candles_before(symbol: &str, resolution: CandleResolution, cutoff: u64) -> usize {
+    let from = CandleKey {
+        symbol: symbol.to_string(),
+        resolution,
+        bucket_start: 0,
+    };
+    let to = CandleKey {
+        symbol: symbol.to_string(),
+        resolution,
+        bucket_start: cutoff,
+    };
+    CANDLES.with(|candles| {
+        let mut candles = candles.borrow_mut();
+        let expired: Vec<CandleKey> = candles.range(from..to).map(|(key, _)| key).collect();
+        for key in &expired {
+            candles.remove(key);
+        }
+        expired.len()
+    })
+}
+
+// Market data config storage functions
+pub fn get_market_data_config() -> MarketDataConfig {
+    MARKET_DATA_CONFIG.with(|config| config.borrow().get().clone())
+}
+
+pub fn set_market_data_config(config: MarketDataConfig) {
+    MARKET_DATA_CONFIG.with(|cell| {
+        cell.borrow_mut().set(config).expect("Failed to store market data config");
+    });
+}
+
+// Market simulator storage functions
+pub fn get_simulator_state() -> SimulatorState {
+    SIMULATOR_STATE.with(|state| state.borrow().get().clone())
+}
+
+pub fn set_simulator_state(state: SimulatorState) {
+    SIMULATOR_STATE.with(|cell| {
+        cell.borrow_mut().set(state).expect("Failed to store simulator state");
+    });
+}
+
+// Corporate action storage functions
+pub fn set_corporate_action(action: &CorporateAction) {
+    CORPORATE_ACTIONS.with(|actions| {
+        actions.borrow_mut().insert(action.id.clone(), action.clone());
+    });
+}
+
+pub fn get_corporate_action(action_id: &str) -> Option<CorporateAction> {
+    CORPORATE_ACTIONS.with(|actions| actions.borrow().get(&action_id.to_string()))
+}
+
+pub fn get_corporate_actions_for_stock(stock_id: &str) -> Vec<CorporateAction> {
+    CORPORATE_ACTIONS.with(|actions| {
+        actions.borrow()
+            .iter()
+            .filter(|(_, action)| action.stock_id == stock_id)
+            .map(|(_, action)| action)
+            .collect()
+    })
+}
+
+pub fn get_due_corporate_actions(now: u64) -> Vec<CorporateAction> {
+    CORPORATE_ACTIONS.with(|actions| {
+        actions.borrow()
+            .iter()
+            .filter(|(_, action)| action.status == CorporateActionStatus::Scheduled && action.ex_date <= now)
+            .map(|(_, action)| action)
+            .collect()
+    })
+}
+
+pub fn get_corporate_actions_with_unpaid_cash() -> Vec<CorporateAction> {
+    CORPORATE_ACTIONS.with(|actions| {
+        actions.borrow()
+            .iter()
+            .filter(|(_, action)| !action.unpaid_cash.is_empty())
+            .map(|(_, action)| action)
+            .collect()
+    })
+}
+
+// News storage functions
+pub fn insert_news_item(item: &NewsItem) {
+    let key = NewsKey {
+        symbol: item.symbol.clone(),
+        published_at: item.published_at,
+        id: item.id.clone(),
+    };
+    NEWS_ITEMS.with(|items| {
+        items.borrow_mut().insert(key, item.clone());
+    });
+}
+
+// A symbol's items published within [start, end], oldest first
+pub fn get_news_items(symbol: &str, start: u64, end: u64) -> Vec<NewsItem> {
+    let from = NewsKey {
+        symbol: symbol.to_string(),
+        published_at: start,
+        id: String::new(),
+    };
+    NEWS_ITEMS.with(|items| {
+        items.borrow()
+            .range(from..)
+            .take_while(|(key, _)| key.symbol == symbol && key.published_at <= end)
+            .map(|(_, item)| item)
+            .collect()
+    })
+}
+
+// Keep only a symbol's `keep` most recent items. Returns the number removed.
+pub fn prune_news_items(symbol: &str, keep: usize) -> usize {
+    let from = NewsKey {
+        symbol: symbol.to_string(),
+        published_at: 0,
+        id: String::new(),
+    };
+    NEWS_ITEMS.with(|items| {
+        let mut items = items.borrow_mut();
+        let keys: Vec<NewsKey> = items
+            .range(from..)
+            .take_while(|(key, _)| key.symbol == symbol)
+            .map(|(key, _)| key)
+            .collect();
+        let excess = keys.len().saturating_sub(keep);
+        for key in &keys[..excess] {
+            items.remove(key);
+        }
+        excess
+    })
+}
+
+// Fundamentals storage functions
+pub fn get_fundamentals(symbol: &str) -> Option<Fundamentals> {
+    FUNDAMENTALS.with(|fundamentals| fundamentals.borrow().get(&symbol.to_string()))
+}
+
+pub fn set_fundamentals(fundamentals: &Fundamentals) {
+    FUNDAMENTALS.with(|map| {
+        map.borrow_mut().insert(fundamentals.symbol.clone(), fundamentals.clone());
+    });
+}
+
+// FX storage functions
+fn fx_pair_key(base: &str, quote: &str) -> String {
+    format!("{}/{}", base, quote)
+}
+
+pub fn get_fx_rate(base: &str, quote: &str) -> Option<FxRate> {
+    FX_RATES.with(|rates| rates.borrow().get(&fx_pair_key(base, quote)))
+}
+
+pub fn set_fx_rate(rate: &FxRate) {
+    FX_RATES.with(|rates| {
+        rates.borrow_mut().insert(fx_pair_key(&rate.base, &rate.quote), rate.clone());
+    });
+}
+
+pub fn get_all_fx_rates() -> Vec<FxRate> {
+    FX_RATES.with(|rates| rates.borrow().iter().map(|(_, rate)| rate).collect())
+}
+
+// Stock universe storage functions
+pub fn get_universe_stock(symbol: &str) -> Option<UniverseStock> {
+    STOCK_UNIVERSE.with(|universe| universe.borrow().get(&symbol.to_string()))
+}
+
+pub fn set_universe_stock(stock: &UniverseStock) {
+    STOCK_UNIVERSE.with(|universe| {
+        universe.borrow_mut().insert(stock.symbol.clone(), stock.clone());
+    });
+}
+
+pub fn remove_universe_stock(symbol: &str) -> Option<UniverseStock> {
+    STOCK_UNIVERSE.with(|universe| universe.borrow_mut().remove(&symbol.to_string()))
+}
+
+pub fn get_all_universe_stocks() -> Vec<UniverseStock> {
+    STOCK_UNIVERSE.with(|universe| universe.borrow().iter().map(|(_, stock)| stock).collect())
+}
+
+pub fn is_universe_empty() -> bool {
+    STOCK_UNIVERSE.with(|universe| universe.borrow().is_empty())
+}
+
+// Market calendar storage functions
+fn holiday_key(exchange: Exchange, date: &str) -> String {
+    format!("{:?}/{}", exchange, date)
+}
+
+pub fn get_market_holiday(exchange: Exchange, date: &str) -> Option<MarketHoliday> {
+    MARKET_HOLIDAYS.with(|holidays| holidays.borrow().get(&holiday_key(exchange, date)))
+}
+
+pub fn set_market_holiday(holiday: &MarketHoliday) {
+    MARKET_HOLIDAYS.with(|holidays| {
+        holidays.borrow_mut().insert(holiday_key(holiday.exchange, &holiday.date), holiday.clone());
+    });
+}
+
+pub fn remove_market_holiday(exchange: Exchange, date: &str) -> Option<MarketHoliday> {
+    MARKET_HOLIDAYS.with(|holidays| holidays.borrow_mut().remove(&holiday_key(exchange, date)))
+}
+
+// An exchange's holidays in date order
+pub fn get_market_holidays(exchange: Exchange) -> Vec<MarketHoliday> {
+    let prefix = format!("{:?}/", exchange);
+    MARKET_HOLIDAYS.with(|holidays| {
+        holidays
+            .borrow()
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, holiday)| holiday)
+            .collect()
+    })
+}
+
+// Index storage functions
+pub fn get_index(index_id: &str) -> Option<IndexDefinition> {
+    INDICES.with(|indices| indices.borrow().get(&index_id.to_string()))
+}
+
+pub fn set_index(index: &IndexDefinition) {
+    INDICES.with(|indices| {
+        indices.borrow_mut().insert(index.id.clone(), index.clone());
+    });
+}
+
+pub fn get_all_indices() -> Vec<IndexDefinition> {
+    INDICES.with(|indices| indices.borrow().iter().map(|(_, index)| index).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_transaction(id: &str, timestamp: u64) -> Web3Transaction {
+        Web3Transaction {
+            id: id.to_string(),
+            from: "0xabc".to_string(),
+            to: None,
+            amount: 10.0,
+            transaction_type: TransactionType::Deposit,
+            timestamp,
+            status: TransactionStatus::Confirmed,
+            hash: None,
+        }
+    }
+
+    fn test_user(wallet_address: &str, transactions: Vec<Web3Transaction>) -> UserData {
+        UserData {
+            wallet_address: wallet_address.to_string(),
+            dual_balance: DualBalance {
+                rupees_balance: 0.0,
+                token_balance: 0.0,
+                last_updated: 0,
+            },
+            staking_pools: Vec::new(),
+            achievements: Vec::new(),
+            transactions,
+            created_at: 0,
+            last_activity: 0,
+        }
+    }
+
+    #[test]
+    fn test_transaction_log_sequences_per_wallet() {
+        assert_eq!(append_transaction("0xlog_a", test_transaction("a1", 1)), 0);
+        assert_eq!(append_transaction("0xlog_b", test_transaction("b1", 2)), 0);
+        assert_eq!(append_transaction("0xlog_a", test_transaction("a2", 3)), 1);
+
+        let ids: Vec<String> = get_transactions("0xlog_a").into_iter().map(|tx| tx.id).collect();
+        assert_eq!(ids, vec!["a1", "a2"]);
+        assert_eq!(get_transaction_count("0xlog_a"), 2);
+        assert_eq!(get_transaction_count("0xlog_b"), 1);
+    }
+
+    #[test]
+    fn test_migrate_embedded_transactions() {
+        let mut users = HashMap::new();
+        users.insert(
+            "0xmigrate".to_string(),
+            test_user("0xmigrate", vec![test_transaction("late", 20), test_transaction("early", 10)]),
+        );
+
+        assert_eq!(migrate_embedded_transactions(&mut users), 2);
+        assert!(users["0xmigrate"].transactions.is_empty());
+
+        let ids: Vec<String> = get_transactions("0xmigrate").into_iter().map(|tx| tx.id).collect();
+        assert_eq!(ids, vec!["early", "late"]);
+
+        // Running again is a no-op
+        assert_eq!(migrate_embedded_transactions(&mut users), 0);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::currency;
+use crate::savings;
+use crate::stock_sse;
+use crate::orders;
+use crate::instruments;
+use crate::fx;
+use crate::market_calendar;
+use crate::indices;
+
+const MAX_ORDER_QUANTITY: u64 = 1_000_000;
+const MAX_STOCK_ID_LENGTH: usize = 32;
+
+// Buy shares at the current cached price, paying in rupees
+pub fn buy_stock(wallet_address: String, stock_id: String, quantity: u64) -> CanisterResult<StockTrade> {
+    auth::verify_session(&wallet_address)?;
+
+    let stock_id = normalize_stock_id(&stock_id)?;
+    indices::ensure_not_index(&stock_id)?;
+    instruments::ensure_tradable(&stock_id)?;
+    instruments::validate_order(&instruments::lookup(&stock_id), quantity, None)?;
+    market_calendar::ensure_trading(&stock_id, ic_cdk::api::time())?;
+    let price = current_price(&stock_id)?;
+
+    execute_buy(&wallet_address, &stock_id, quantity, price)
+}
+
+// Sell shares at the current cached price for rupees
+pub fn sell_stock(wallet_address: String, stock_id: String, quantity: u64) -> CanisterResult<StockTrade> {
+    auth::verify_session(&wallet_address)?;
+
+    let stock_id = normalize_stock_id(&stock_id)?;
+    indices::ensure_not_index(&stock_id)?;
+    instruments::ensure_tradable(&stock_id)?;
+    instruments::validate_order(&instruments::lookup(&stock_id), quantity, None)?;
+    market_calendar::ensure_trading(&stock_id, ic_cdk::api::time())?;
+    let available = orders::available_shares(&wallet_address, &stock_id);
+    if quantity > available {
+        return Err(CanisterError::InvalidInput(format!(
+            "Cannot sell {} shares of {}, only {} available outside open orders",
+            quantity, stock_id, available
+        )));
+    }
+    let price = current_price(&stock_id)?;
+
+    execute_sell(&wallet_address, &stock_id, quantity, price)
+}
+
+// Settle a buy at `price`, in the stock's quote currency, without session
+// checks; callers authorize. The trade is paid and recorded in rupees.
+pub fn execute_buy(wallet_address: &str, stock_id: &str, quantity: u64, price: f64) -> CanisterResult<StockTrade> {
+    validate_order(quantity, price)?;
+
+    let now = ic_cdk::api::time();
+    let (quote_currency, fx_rate) = rupee_rate(stock_id, now)?;
+    let price = price * fx_rate;
+
+    let mut user_data = storage::get_user_data(wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let total = utils::safe_multiply(price, quantity as f64)?;
+    let mut writes = PendingWrites::default();
+    currency::debit(&mut user_data, &mut writes, currency::RUPEES, total)?;
+
+    let holding = storage::get_holding(wallet_address, stock_id)
+        .unwrap_or_else(|| empty_holding(wallet_address, stock_id));
+    let holding = apply_buy(holding, quantity, price, now);
+
+    user_data.last_activity = now;
+
+    let trade = StockTrade {
+        id: utils::generate_trade_id(),
+        wallet_address: wallet_address.to_string(),
+        stock_id: stock_id.to_string(),
+        side: TradeSide::Buy,
+        quantity,
+        price,
+        total,
+        realized_pnl: None,
+        timestamp: now,
+        quote_currency,
+        fx_rate,
+    };
+
+    settle(wallet_address, user_data, writes, &holding, &trade, TransactionType::StockBuy)?;
+
+    Ok(trade)
+}
+
+// Settle a sell at `price`, in the stock's quote currency, without session
+// checks; callers authorize. Proceeds are credited and recorded in rupees.
+pub fn execute_sell(wallet_address: &str, stock_id: &str, quantity: u64, price: f64) -> CanisterResult<StockTrade> {
+    validate_order(quantity, price)?;
+
+    let now = ic_cdk::api::time();
+    let (quote_currency, fx_rate) = rupee_rate(stock_id, now)?;
+    let price = price * fx_rate;
+
+    let mut user_data = storage::get_user_data(wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+    let holding = storage::get_holding(wallet_address, stock_id)
+        .ok_or(CanisterError::NotFound(format!("No {} holding", stock_id)))?;
+
+    let (holding, realized_pnl) = apply_sell(holding, quantity, price, now)?;
+
+    let total = utils::safe_multiply(price, quantity as f64)?;
+    let mut writes = PendingWrites::default();
+    currency::credit(&mut user_data, &mut writes, currency::RUPEES, total)?;
+    savings::sweep_incoming_credit(&mut user_data, &mut writes, total)?;
+
+    user_data.last_activity = now;
+
+    let trade = StockTrade {
+        id: utils::generate_trade_id(),
+        wallet_address: wallet_address.to_string(),
+        stock_id: stock_id.to_string(),
+        side: TradeSide::Sell,
+        quantity,
+        price,
+        total,
+        realized_pnl: Some(realized_pnl),
+        timestamp: now,
+        quote_currency,
+        fx_rate,
+    };
+
+    settle(wallet_address, user_data, writes, &holding, &trade, TransactionType::StockSell)?;
+
+    Ok(trade)
+}
+
+// Open positions valued at current prices in rupees, plus rupee cash
+pub fn get_portfolio(wallet_address: String) -> CanisterResult<Portfolio> {
+    let user_data = storage::get_user_data(&wallet_address)
+        .ok_or(CanisterError::UserNotFound)?;
+
+    let holdings = storage::get_holdings_for_wallet(&wallet_address);
+    let realized_pnl: f64 = holdings.iter().map(|holding| holding.realized_pnl).sum();
+
+    let now = ic_cdk::api::time();
+    let mut positions = Vec::new();
+    for holding in holdings.iter().filter(|holding| holding.quantity > 0) {
+        // A position without a usable quote or rupee rate is carried at cost
+        // and flagged, rather than failing the whole portfolio
+        let position = match position_quote(&holding.stock_id, now) {
+            Ok((name, price)) => value_position(holding, &name, Some(price)),
+            Err(_) => value_position(holding, &instruments::lookup(&holding.stock_id).name, None),
+        };
+        positions.push(position);
+    }
+
+    let holdings_value: f64 = positions.iter().map(|position| position.market_value).sum();
+    let cost_basis: f64 = positions.iter().map(|position| position.cost_basis).sum();
+    let cash_balance = user_data.dual_balance.rupees_balance;
+
+    Ok(Portfolio {
+        wallet_address,
+        positions,
+        cash_balance,
+        holdings_value,
+        cost_basis,
+        unrealized_pnl: holdings_value - cost_basis,
+        realized_pnl,
+        total_value: cash_balance + holdings_value,
+        valued_at: now,
+    })
+}
+
+// Fills for a wallet, most recent first
+pub fn get_stock_trades(wallet_address: String) -> Vec<StockTrade> {
+    let mut trades = storage::get_stock_trades(&wallet_address);
+    trades.reverse();
+    trades
+}
+
+fn settle(
+    wallet_address: &str,
+    user_data: UserData,
+    writes: PendingWrites,
+    holding: &Holding,
+    trade: &StockTrade,
+    transaction_type: TransactionType,
+) -> CanisterResult<()> {
+    let transaction = Web3Transaction {
+        id: trade.id.clone(),
+        from: wallet_address.to_string(),
+        to: None,
+        amount: trade.total,
+        transaction_type,
+        timestamp: trade.timestamp,
+        status: TransactionStatus::Confirmed,
+        hash: Some(utils::generate_transaction_hash()),
+    };
+
+    storage::commit_user_data(wallet_address, user_data, writes)?;
+    storage::set_holding(holding);
+    storage::append_stock_trade(trade);
+    storage::append_transaction(wallet_address, transaction);
+
+    Ok(())
+}
+
+// Last quoted price in the stock's currency; fails rather than trade on a
+// generated price when no data has been fetched for it
+pub fn current_price(stock_id: &str) -> CanisterResult<f64> {
+    let stock = stock_sse::get_quoted_stock(stock_id)?;
+    if stock.current_price <= 0.0 || !stock.current_price.is_finite() {
+        return Err(CanisterError::InternalError(format!("No valid price for {}", stock_id)));
+    }
+    Ok(stock.current_price)
+}
+
+// Name and rupee price of a held position; baskets are valued from their index
+fn position_quote(stock_id: &str, now: u64) -> CanisterResult<(String, f64)> {
+    if let Some(index) = storage::get_index(stock_id) {
+        return Ok((index.name, indices::index_price(stock_id, now)?));
+    }
+    let stock = stock_sse::get_quoted_stock(stock_id)?;
+    let (_, fx_rate) = rupee_rate(stock_id, now)?;
+    Ok((stock.name, stock.current_price * fx_rate))
+}
+
+// The stock's quote currency and rupees per unit of it. Fails if that
+// currency's rupee rate is stale. Index baskets are valued in rupees.
+pub fn rupee_rate(stock_id: &str, now: u64) -> CanisterResult<(String, f64)> {
+    if indices::is_index(stock_id) {
+        return Ok((instruments::INR.to_string(), 1.0));
+    }
+    let quote_currency = instruments::lookup(stock_id).currency;
+    let fx_rate = fx::rupees_per_unit(&quote_currency, now)?;
+    Ok((quote_currency, fx_rate))
+}
+
+pub fn normalize_stock_id(stock_id: &str) -> CanisterResult<String> {
+    let stock_id = stock_id.trim().to_uppercase();
+    if stock_id.is_empty()
+        || stock_id.len() > MAX_STOCK_ID_LENGTH
+        || !stock_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(CanisterError::InvalidInput("Invalid stock id".to_string()));
+    }
+    Ok(stock_id)
+}
+
+pub fn validate_order(quantity: u64, price: f64) -> CanisterResult<()> {
+    if quantity == 0 || quantity > MAX_ORDER_QUANTITY {
+        return Err(CanisterError::InvalidInput(format!("Quantity must be between 1 and {}", MAX_ORDER_QUANTITY)));
+    }
+    if price <= 0.0 || !price.is_finite() {
+        return Err(CanisterError::InvalidAmount);
+    }
+    Ok(())
+}
+
+fn empty_holding(wallet_address: &str, stock_id: &str) -> Holding {
+    Holding {
+        wallet_address: wallet_address.to_string(),
+        stock_id: stock_id.to_string(),
+        quantity: 0,
+        average_cost: 0.0,
+        realized_pnl: 0.0,
+        updated_at: 0,
+    }
+}
+
+// Buying moves the average cost towards the fill price, weighted by quantity
+fn apply_buy(holding: Holding, quantity: u64, price: f64, now: u64) -> Holding {
+    let new_quantity = holding.quantity + quantity;
+    let cost = holding.average_cost * holding.quantity as f64 + price * quantity as f64;
+
+    Holding {
+        quantity: new_quantity,
+        average_cost: cost / new_quantity as f64,
+        updated_at: now,
+        ..holding
+    }
+}
+
+// Selling realizes P&L against the average cost, which itself is unchanged.
+// Returns the updated holding and the P&L realized by this sale.
+fn apply_sell(holding: Holding, quantity: u64, price: f64, now: u64) -> CanisterResult<(Holding, f64)> {
+    if quantity > holding.quantity {
+        return Err(CanisterError::InvalidInput(format!(
+            "Cannot sell {} shares of {}, only {} held",
+            quantity, holding.stock_id, holding.quantity
+        )));
+    }
+
+    let realized = (price - holding.average_cost) * quantity as f64;
+    let remaining = holding.quantity - quantity;
+
+    Ok((
+        Holding {
+            quantity: remaining,
+            // A closed position starts a fresh cost basis on the next buy
+            average_cost: if remaining == 0 { 0.0 } else { holding.average_cost },
+            realized_pnl: holding.realized_pnl + realized,
+            updated_at: now,
+            ..holding
+        },
+        realized,
+    ))
+}
+
+// Unpriced positions are valued at their average cost
+fn value_position(holding: &Holding, name: &str, current_price: Option<f64>) -> PortfolioPosition {
+    let priced = current_price.is_some();
+    let current_price = current_price.unwrap_or(holding.average_cost);
+    let cost_basis = holding.average_cost * holding.quantity as f64;
+    let market_value = current_price * holding.quantity as f64;
+    let unrealized_pnl = market_value - cost_basis;
+
+    PortfolioPosition {
+        stock_id: holding.stock_id.clone(),
+        name: name.to_string(),
+        quantity: holding.quantity,
+        average_cost: holding.average_cost,
+        current_price,
+        cost_basis,
+        market_value,
+        unrealized_pnl,
+        unrealized_pnl_percent: if cost_basis > 0.0 { unrealized_pnl / cost_basis * 100.0 } else { 0.0 },
+        realized_pnl: holding.realized_pnl,
+        priced,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_cost_is_quantity_weighted() {
+        let holding = apply_buy(empty_holding("0xabc", "TCS"), 10, 100.0, 1);
+        let holding = apply_buy(holding, 30, 200.0, 2);
+
+        assert_eq!(holding.quantity, 40);
+        assert!((holding.average_cost - 175.0).abs() < 1e-9);
+        assert_eq!(holding.updated_at, 2);
+    }
+
+    #[test]
+    fn test_sell_realizes_pnl_against_average_cost() {
+        let holding = apply_buy(empty_holding("0xabc", "TCS"), 40, 175.0, 1);
+        let (holding, realized) = apply_sell(holding, 10, 200.0, 2).unwrap();
+
+        assert!((realized - 250.0).abs() < 1e-9);
+        assert_eq!(holding.quantity, 30);
+        assert_eq!(holding.average_cost, 175.0);
+
+        let (closed, realized) = apply_sell(holding, 30, 150.0, 3).unwrap();
+        assert!((realized + 750.0).abs() < 1e-9);
+        assert_eq!(closed.quantity, 0);
+        assert_eq!(closed.average_cost, 0.0);
+        assert!((closed.realized_pnl + 500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cannot_oversell() {
+        let holding = apply_buy(empty_holding("0xabc", "INFY"), 5, 1450.0, 1);
+        assert!(apply_sell(holding, 6, 1500.0, 2).is_err());
+    }
+
+    #[test]
+    fn test_value_position_unrealized_pnl() {
+        let holding = apply_buy(empty_holding("0xabc", "ITC"), 100, 400.0, 1);
+        let position = value_position(&holding, "ITC Limited", Some(420.0));
+
+        assert!((position.market_value - 42_000.0).abs() < 1e-9);
+        assert!((position.unrealized_pnl - 2_000.0).abs() < 1e-9);
+        assert!((position.unrealized_pnl_percent - 5.0).abs() < 1e-9);
+        assert!(position.priced);
+
+        let unpriced = value_position(&holding, "ITC Limited", None);
+        assert!(!unpriced.priced);
+        assert!((unpriced.market_value - 40_000.0).abs() < 1e-9);
+        assert_eq!(unpriced.unrealized_pnl, 0.0);
+    }
+}
@@ -49,6 +49,153 @@ pub struct DualBalance {
     pub last_updated: u64,
 }
 
+// Currency Registry Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ExchangePair {
+    pub quote: String, // symbol of the currency received
+    pub rate: f64,     // units of `quote` per unit of the base currency
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CurrencyDefinition {
+    pub symbol: String, // lowercase, e.g. "rupees" or "diya_coins"
+    pub display_name: String,
+    pub decimals: u8,
+    pub mintable: bool, // whether controllers may issue new units directly
+    pub exchange_pairs: Vec<ExchangePair>,
+    pub enabled: bool,  // disabled currencies keep balances but cannot move
+    pub created_at: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AssetBalance {
+    pub symbol: String,
+    pub display_name: String,
+    pub decimals: u8,
+    pub balance: f64,
+}
+
+// Key into the per-asset balance map for registry (non built-in) currencies
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BalanceKey {
+    pub wallet_address: String,
+    pub symbol: String,
+}
+
+// Interest Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct InterestTier {
+    pub min_balance: f64,         // rupee balance needed to earn this tier's rate
+    pub annual_rate_percent: f64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct InterestConfig {
+    pub enabled: bool,
+    pub tiers: Vec<InterestTier>, // balances below the lowest tier earn nothing
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            tiers: vec![
+                InterestTier { min_balance: 1_000.0, annual_rate_percent: 3.0 },
+                InterestTier { min_balance: 10_000.0, annual_rate_percent: 3.5 },
+                InterestTier { min_balance: 100_000.0, annual_rate_percent: 4.0 },
+            ],
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default)]
+pub struct InterestAccount {
+    pub accrued_interest: f64, // accrued but not yet credited
+    pub total_credited: f64,
+    pub last_credited_at: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct InterestSummary {
+    pub current_rate_percent: f64,
+    pub accrued_interest: f64, // includes interest since the last balance checkpoint
+    pub total_credited: f64,
+    pub last_credited_at: Option<u64>,
+}
+
+// Central Bank Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct MonetaryPolicy {
+    pub policy_rate_percent: f64,
+    pub neutral_rate_percent: f64,     // policy rate at which deposit tiers pay their quoted rate
+    pub lending_spread_percent: f64,   // loans are priced at policy rate + spread
+    pub exchange_rate: f64,            // tokens per rupee
+    pub exchange_rate_sensitivity: f64, // exchange-rate move per point of policy-rate change
+    pub inflation_target_percent: f64,
+    pub inflation_percent: f64,
+    pub updated_at: u64,
+}
+
+impl Default for MonetaryPolicy {
+    fn default() -> Self {
+        Self {
+            policy_rate_percent: 6.5,
+            neutral_rate_percent: 6.5,
+            lending_spread_percent: 2.5,
+            exchange_rate: 0.1,
+            exchange_rate_sensitivity: 1.0,
+            inflation_target_percent: 4.0,
+            inflation_percent: 4.0,
+            updated_at: 0,
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum RateDecisionStatus {
+    Scheduled,
+    Applied,
+    Cancelled,
+}
+
+// Fields left as None keep their current value when the decision applies
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RateDecisionRequest {
+    pub policy_rate_percent: Option<f64>,
+    pub exchange_rate: Option<f64>, // overrides the sensitivity-derived move
+    pub inflation_target_percent: Option<f64>,
+    pub inflation_percent: Option<f64>,
+    pub effective_at: u64,
+    pub note: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RateDecision {
+    pub id: String,
+    pub policy_rate_percent: Option<f64>,
+    pub exchange_rate: Option<f64>,
+    pub inflation_target_percent: Option<f64>,
+    pub inflation_percent: Option<f64>,
+    pub effective_at: u64,
+    pub note: Option<String>,
+    pub status: RateDecisionStatus,
+    pub created_at: u64,
+    pub applied_at: Option<u64>,
+}
+
+// Rates in force from `effective_at` until the next record
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RateRecord {
+    pub effective_at: u64,
+    pub decision_id: Option<String>,
+    pub policy_rate_percent: f64,
+    pub deposit_rate_adjustment_percent: f64,
+    pub lending_rate_percent: f64,
+    pub exchange_rate: f64,
+    pub inflation_target_percent: f64,
+    pub inflation_percent: f64,
+}
+
 // Price feed types for buffer API
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct PriceEntry {
@@ -64,10 +211,56 @@ pub struct ExchangeResult {
     pub from_amount: f64,
     pub to_amount: f64,
     pub rate: f64,
+    pub rate_source: RateSource,
     pub transaction: Option<Web3Transaction>,
     pub error: Option<String>,
 }
 
+// Where an exchange rate came from
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum RateSource {
+    Fixed,          // central bank policy rate
+    Oracle,         // derived live from the price feeds
+    OracleFallback, // oracle mode, but feeds were missing or stale
+    Registry,       // pair declared in the currency registry
+}
+
+// Oracle peg Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ExchangeRateMode {
+    Fixed,
+    OraclePegged,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum PegFallback {
+    FixedRate, // fall back to the central bank rate
+    Reject,    // refuse to exchange until the feeds recover
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PegConfig {
+    pub mode: ExchangeRateMode,
+    pub reference_symbol: String,         // price feed in USD, e.g. "ICP"
+    pub reference_units_per_token: f64,   // 1 token is worth this much of the reference asset
+    pub spread_percent: f64,              // total bid/ask spread, half charged each way
+    pub max_staleness_seconds: u64,
+    pub fallback: PegFallback,
+}
+
+impl Default for PegConfig {
+    fn default() -> Self {
+        Self {
+            mode: ExchangeRateMode::Fixed,
+            reference_symbol: "ICP".to_string(),
+            reference_units_per_token: 0.01,
+            spread_percent: 1.0,
+            max_staleness_seconds: 15 * 60,
+            fallback: PegFallback::FixedRate,
+        }
+    }
+}
+
 // Achievement Types
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub enum AchievementCategory {
@@ -108,6 +301,11 @@ pub enum TransactionType {
     Deposit,
     Withdraw,
     Exchange,
+    Transfer,
+    Interest,
+    StockBuy,
+    StockSell,
+    Dividend,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
@@ -129,6 +327,118 @@ pub struct Web3Transaction {
     pub hash: Option<String>,
 }
 
+// Standing Instruction Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum InstructionAction {
+    Transfer {
+        to: String,
+        currency: String,
+        amount: f64,
+    },
+    Exchange {
+        from_currency: String,
+        to_currency: String,
+        amount: f64,
+    },
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum Recurrence {
+    Once,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum InstructionStatus {
+    Active,
+    Paused,
+    Cancelled,
+    Completed,
+    Failed,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub retry_interval: u64, // in nanoseconds
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            retry_interval: 60 * 60 * 1_000_000_000, // 1 hour in nanoseconds
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StandingInstruction {
+    pub id: String,
+    pub wallet_address: String,
+    pub action: InstructionAction,
+    pub recurrence: Recurrence,
+    pub scheduled_at: u64,  // occurrence currently being served
+    pub next_run_at: u64,   // next attempt (later than scheduled_at while retrying)
+    pub end_at: Option<u64>,
+    pub status: InstructionStatus,
+    pub retry_policy: RetryPolicy,
+    pub retry_count: u32,
+    pub execution_count: u32,
+    pub last_run_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+}
+
+// Savings Goal Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum SavingsGoalStatus {
+    Active,
+    Completed,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SavingsGoal {
+    pub id: String,
+    pub wallet_address: String,
+    pub name: String,
+    pub target_amount: f64,
+    pub saved_amount: f64, // rupees ring-fenced from the spendable balance
+    pub deadline: Option<u64>,
+    pub auto_sweep_percent: Option<f64>, // share of every incoming rupee credit, 0-100
+    pub status: SavingsGoalStatus,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub completed_at: Option<u64>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SavingsGoalUpdate {
+    pub name: Option<String>,
+    pub target_amount: Option<f64>,
+    pub deadline: Option<u64>,
+    pub auto_sweep_percent: Option<f64>, // Some(0.0) turns auto-sweep off
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SavingsGoalProgress {
+    pub goal: SavingsGoal,
+    pub percent_complete: f64,
+    pub remaining_amount: f64,
+    pub days_remaining: Option<u64>,
+    pub required_daily_saving: Option<f64>,
+    pub on_track: bool,
+}
+
+// Key into the stable transaction log: one ordered sequence per wallet
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TransactionKey {
+    pub wallet_address: String,
+    pub sequence: u64,
+}
+
 // Authentication Types
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct Web3Session {
@@ -183,14 +493,25 @@ pub struct UserData {
     pub dual_balance: DualBalance,
     pub staking_pools: Vec<()> /* staking removed */,
     pub achievements: Vec<Achievement>,
+    // Legacy embedded history. Transactions now live in the stable transaction
+    // log (see storage::append_transaction); this is drained on upgrade.
     pub transactions: Vec<Web3Transaction>,
     pub created_at: u64,
     pub last_activity: u64,
 }
 
+// Balance changes kept outside the user entry: registry currency balances and
+// interest accrued at a checkpoint. Written together with the user by
+// storage::commit_user_data and dropped if the caller bails out first.
+#[derive(Clone, Debug, Default)]
+pub struct PendingWrites {
+    pub asset_balances: Vec<(String, f64)>,
+    pub accrued_interest: f64,
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct GlobalSettings {
-    pub exchange_rate: f64, // 1 Rupee = 0.1 Token
+    pub exchange_rate: f64, // 1 Rupee = 0.1 Token; mirrors the central bank policy
     pub staking_apys: HashMap<u32, f64>, // Staking removed: left empty by default
     pub achievement_definitions: Vec<Achievement>,
     pub session_timeout: u64, // in nanoseconds
@@ -246,6 +567,11 @@ impl From<&str> for TransactionType {
             "deposit" => TransactionType::Deposit,
             "withdraw" => TransactionType::Withdraw,
             "exchange" => TransactionType::Exchange,
+            "transfer" => TransactionType::Transfer,
+            "interest" => TransactionType::Interest,
+            "stock_buy" => TransactionType::StockBuy,
+            "stock_sell" => TransactionType::StockSell,
+            "dividend" => TransactionType::Dividend,
             // "stake" mapping removed
             _ => TransactionType::Deposit,
         }
@@ -306,7 +632,7 @@ impl Storable for UserData {
     };
 }
 
-impl Storable for Web3Session {
+impl Storable for BalanceKey {
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(candid::encode_one(self).unwrap())
     }
@@ -316,110 +642,72 @@ impl Storable for Web3Session {
     }
 
     const BOUND: Bound = Bound::Bounded {
-        max_size: 512,
+        max_size: 256,
         is_fixed_size: false,
     };
 }
 
-// Server-Sent Events (SSE) Types
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct SseEvent {
-    pub id: u64,
-    pub event_type: String,
-    pub data: String,
-    pub timestamp: u64,
-}
+impl Storable for CurrencyDefinition {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub enum SseEventType {
-    PeerJoined,
-    PeerLeft,
-    Offer,
-    Answer,
-    IceCandidate,
-    RoomState,
-}
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct PeerJoinedEvent {
-    pub peer_id: String,
-    pub meta: HashMap<String, String>,
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct PeerLeftEvent {
-    pub peer_id: String,
-}
+impl Storable for InterestConfig {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct OfferEvent {
-    pub from: String,
-    pub to: String,
-    pub sdp: String,
-}
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct AnswerEvent {
-    pub from: String,
-    pub to: String,
-    pub sdp: String,
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct IceCandidateEvent {
-    pub from: String,
-    pub to: String,
-    pub candidate: HashMap<String, String>,
-}
+impl Storable for InterestAccount {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct RoomStateEvent {
-    pub peers: Vec<String>,
-    pub meta: HashMap<String, String>,
-}
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct SseConnection {
-    pub connection_id: String,
-    pub room_id: String,
-    pub peer_id: String,
-    pub last_event_id: Option<u64>,
-    pub connected_at: u64,
-    pub last_activity: u64,
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct SseRoom {
-    pub room_id: String,
-    pub connections: Vec<String>, // connection IDs
-    pub event_buffer: Vec<SseEvent>,
-    pub max_buffer_size: usize,
-    pub created_at: u64,
-    pub last_activity: u64,
-}
+impl Storable for MonetaryPolicy {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
 
-#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
-pub struct SseConfig {
-    pub max_connections_per_room: usize,
-    pub max_buffer_size_per_room: usize,
-    pub connection_timeout_ms: u64,
-    pub max_event_age_ms: u64,
-    pub cleanup_interval_ms: u64,
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-impl Default for SseConfig {
-    fn default() -> Self {
-        Self {
-            max_connections_per_room: 100,
-            max_buffer_size_per_room: 1000,
-            connection_timeout_ms: 5 * 60 * 1000, // 5 minutes
-            max_event_age_ms: 10 * 60 * 1000, // 10 minutes
-            cleanup_interval_ms: 60 * 1000, // 1 minute
-        }
+impl Storable for RateDecision {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
     }
+
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-impl Storable for SseConnection {
+impl Storable for RateRecord {
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(candid::encode_one(self).unwrap())
     }
@@ -428,13 +716,10 @@ impl Storable for SseConnection {
         candid::decode_one(&bytes).unwrap()
     }
 
-    const BOUND: Bound = Bound::Bounded {
-        max_size: 1024,
-        is_fixed_size: false,
-    };
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-impl Storable for SseRoom {
+impl Storable for PegConfig {
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(candid::encode_one(self).unwrap())
     }
@@ -443,14 +728,205 @@ impl Storable for SseRoom {
         candid::decode_one(&bytes).unwrap()
     }
 
-    const BOUND: Bound = Bound::Bounded {
-        max_size: 8192, // Large enough for event buffer
-        is_fixed_size: false,
-    };
+    const BOUND: Bound = Bound::Unbounded;
 }
 
-// Stock-related types for SSE streaming
-#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+impl Storable for TransactionKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for Web3Transaction {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for StandingInstruction {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for SavingsGoal {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for Web3Session {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 512,
+        is_fixed_size: false,
+    };
+}
+
+// Server-Sent Events (SSE) Types
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SseEvent {
+    pub id: u64,
+    pub event_type: String,
+    pub data: String,
+    pub timestamp: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub enum SseEventType {
+    PeerJoined,
+    PeerLeft,
+    Offer,
+    Answer,
+    IceCandidate,
+    RoomState,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PeerJoinedEvent {
+    pub peer_id: String,
+    pub meta: HashMap<String, String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PeerLeftEvent {
+    pub peer_id: String,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct OfferEvent {
+    pub from: String,
+    pub to: String,
+    pub sdp: String,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AnswerEvent {
+    pub from: String,
+    pub to: String,
+    pub sdp: String,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct IceCandidateEvent {
+    pub from: String,
+    pub to: String,
+    pub candidate: HashMap<String, String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RoomStateEvent {
+    pub peers: Vec<String>,
+    pub meta: HashMap<String, String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SseConnection {
+    pub connection_id: String,
+    pub room_id: String,
+    pub peer_id: String,
+    pub last_event_id: Option<u64>,
+    pub connected_at: u64,
+    pub last_activity: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SseRoom {
+    pub room_id: String,
+    pub connections: Vec<String>, // connection IDs
+    pub event_buffer: Vec<SseEvent>,
+    pub max_buffer_size: usize,
+    pub created_at: u64,
+    pub last_activity: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SseConfig {
+    pub max_connections_per_room: usize,
+    pub max_buffer_size_per_room: usize,
+    pub connection_timeout_ms: u64,
+    pub max_event_age_ms: u64,
+    pub cleanup_interval_ms: u64,
+}
+
+impl Default for SseConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_room: 100,
+            max_buffer_size_per_room: 1000,
+            connection_timeout_ms: 5 * 60 * 1000, // 5 minutes
+            max_event_age_ms: 10 * 60 * 1000, // 10 minutes
+            cleanup_interval_ms: 60 * 1000, // 1 minute
+        }
+    }
+}
+
+impl Storable for SseConnection {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 1024,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for SseRoom {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 8192, // Large enough for event buffer
+        is_fixed_size: false,
+    };
+}
+
+// Stock-related types for SSE streaming
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
 pub struct StockPrice {
     pub timestamp: u64,
     pub price: f64,
@@ -471,6 +947,7 @@ pub struct StockMetrics {
     pub industry_avg_pe: f64,
     pub outstanding_shares: u64,
     pub volatility: f64,
+    pub provenance: Option<MetricProvenance>, // None on entries cached before provenance was tracked
 }
 
 #[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
@@ -483,6 +960,8 @@ pub struct Stock {
     pub metrics: StockMetrics,
     pub news: Vec<String>,
     pub last_update: u64,
+    pub currency: Option<String>,     // ISO code prices are quoted in; None on entries cached before it was tracked
+    pub exchange: Option<Exchange>,
 }
 
 #[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
@@ -525,4 +1004,1173 @@ impl Storable for StockSubscription {
     }
 
     const BOUND: Bound = Bound::Unbounded;
-}
\ No newline at end of file
+}
+
+// Paper trading types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HoldingKey {
+    pub wallet_address: String,
+    pub stock_id: String,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Holding {
+    pub wallet_address: String,
+    pub stock_id: String,
+    pub quantity: u64,
+    pub average_cost: f64, // rupees per share, weighted by quantity bought
+    pub realized_pnl: f64, // kept after the position is closed
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct StockTrade {
+    pub id: String,
+    pub wallet_address: String,
+    pub stock_id: String,
+    pub side: TradeSide,
+    pub quantity: u64,
+    pub price: f64,
+    pub total: f64,
+    pub realized_pnl: Option<f64>, // sells only
+    pub timestamp: u64,
+    pub quote_currency: String, // currency the stock is priced in; price and total are rupees
+    pub fx_rate: f64,           // rupees per unit of quote_currency at settlement
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct PortfolioPosition {
+    pub stock_id: String,
+    pub name: String,
+    pub quantity: u64,
+    pub average_cost: f64,
+    pub current_price: f64,
+    pub cost_basis: f64,
+    pub market_value: f64,
+    pub unrealized_pnl: f64,
+    pub unrealized_pnl_percent: f64,
+    pub realized_pnl: f64,
+    pub priced: bool, // false when no quote was available and the position is carried at cost
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Portfolio {
+    pub wallet_address: String,
+    pub positions: Vec<PortfolioPosition>,
+    pub cash_balance: f64,
+    pub holdings_value: f64,
+    pub cost_basis: f64,
+    pub unrealized_pnl: f64,
+    pub realized_pnl: f64,
+    pub total_value: f64,
+    pub valued_at: u64,
+}
+
+impl Storable for HoldingKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for Holding {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for StockTrade {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Conditional stock order types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum StockOrderType {
+    Limit,      // buy at or below / sell at or above the trigger price
+    StopLoss,   // sell once the price falls to the trigger
+    TakeProfit, // sell once the price rises to the trigger
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum TimeInForce {
+    GoodTillCancelled,
+    Day,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum StockOrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+    Expired,
+    Rejected,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct StockOrderRequest {
+    pub stock_id: String,
+    pub side: TradeSide,
+    pub order_type: StockOrderType,
+    pub quantity: u64,
+    pub trigger_price: f64,
+    pub time_in_force: TimeInForce,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct StockOrder {
+    pub id: String,
+    pub wallet_address: String,
+    pub stock_id: String,
+    pub side: TradeSide,
+    pub order_type: StockOrderType,
+    pub quantity: u64,
+    pub trigger_price: f64,
+    pub time_in_force: TimeInForce,
+    pub reserved_rupees: f64, // held back from the balance while a buy is open
+    pub status: StockOrderStatus,
+    pub created_at: u64,
+    pub expires_at: Option<u64>,
+    pub closed_at: Option<u64>,
+    pub fill_price: Option<f64>,
+    pub trade_id: Option<String>,
+    pub status_reason: Option<String>,
+}
+
+impl Storable for StockOrder {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Player order book types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum BookOrderStatus {
+    Open,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct BookOrderRequest {
+    pub base: String,  // asset being bought or sold
+    pub quote: String, // currency the price is quoted in
+    pub side: TradeSide,
+    pub price: f64,
+    pub quantity: f64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct BookOrder {
+    pub id: String,
+    pub wallet_address: String,
+    pub base: String,
+    pub quote: String,
+    pub side: TradeSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub remaining: f64,
+    pub sequence: u64, // time priority within a price level
+    pub status: BookOrderStatus,
+    pub status_reason: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct BookTrade {
+    pub id: u64,
+    pub base: String,
+    pub quote: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub buy_order_id: String,
+    pub sell_order_id: String,
+    pub aggressor_side: TradeSide,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub struct DepthLevel {
+    pub price: f64,
+    pub quantity: f64,
+    pub order_count: u32,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct OrderBookSnapshot {
+    pub base: String,
+    pub quote: String,
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+    pub last_trade_price: Option<f64>,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct BookOrderResult {
+    pub order: BookOrder,
+    pub trades: Vec<BookTrade>,
+}
+
+impl Storable for BookOrder {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for BookTrade {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Resting order index key. Orders sort by instrument, side, price and then
+// time; positive prices order the same as their f64 bit patterns.
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BookLevelKey {
+    pub base: String,
+    pub quote: String,
+    pub side: TradeSide,
+    pub price_bits: u64,
+    pub sequence: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WalletBookKey {
+    pub wallet_address: String,
+    pub order_id: String,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BookTradeKey {
+    pub base: String,
+    pub quote: String,
+    pub id: u64,
+}
+
+impl Storable for BookLevelKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for WalletBookKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for BookTradeKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+// Portfolio analytics types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SnapshotKey {
+    pub wallet_address: String,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct PortfolioSnapshot {
+    pub wallet_address: String,
+    pub timestamp: u64,
+    pub holdings_value: f64,
+    pub cost_basis: f64,
+    pub cash_balance: f64,
+    pub net_flow: f64, // buys minus sells since the previous snapshot
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct PortfolioPerformance {
+    pub wallet_address: String,
+    pub start: u64,
+    pub end: u64,
+    pub snapshot_count: u32,
+    pub time_weighted_return_percent: f64,
+    pub annualized_volatility_percent: f64,
+    pub sharpe_ratio: Option<f64>,
+    pub max_drawdown_percent: f64,
+    pub risk_free_rate_percent: f64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SectorAllocation {
+    pub sector: String,
+    pub market_value: f64,
+    pub weight_percent: f64,
+    pub position_count: u32,
+}
+
+impl Storable for SnapshotKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for PortfolioSnapshot {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Watchlist and price alert types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum WatchAssetKind {
+    Stock,  // priced from the stock cache
+    Crypto, // priced from the price feed
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub struct WatchlistItem {
+    pub symbol: String,
+    pub kind: WatchAssetKind,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Watchlist {
+    pub id: String,
+    pub wallet_address: String,
+    pub name: String,
+    pub items: Vec<WatchlistItem>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum AlertCondition {
+    PriceAbove(f64),
+    PriceBelow(f64),
+    PercentChangeUp(f64),   // rise from the reference price, in percent
+    PercentChangeDown(f64), // fall from the reference price, in percent
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum AlertMode {
+    OneShot,
+    Rearm, // fires again once the condition has cleared
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum AlertStatus {
+    Active,
+    Triggered,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct PriceAlertRequest {
+    pub symbol: String,
+    pub kind: WatchAssetKind,
+    pub condition: AlertCondition,
+    pub mode: AlertMode,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct PriceAlert {
+    pub id: String,
+    pub wallet_address: String,
+    pub symbol: String,
+    pub kind: WatchAssetKind,
+    pub condition: AlertCondition,
+    pub mode: AlertMode,
+    pub status: AlertStatus,
+    pub armed: bool,
+    pub reference_price: f64,
+    pub last_price: Option<f64>,
+    pub trigger_count: u32,
+    pub last_triggered_at: Option<u64>,
+    pub created_at: u64,
+}
+
+impl Storable for Watchlist {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl Storable for PriceAlert {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Technical indicator types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndicatorSeries {
+    pub symbol: String,
+    pub window: u32,
+    // Every series below lines up with `timestamps`; None marks warm-up bars
+    pub timestamps: Vec<u64>,
+    pub close: Vec<f64>,
+    pub sma: Vec<Option<f64>>,
+    pub ema: Vec<Option<f64>>,
+    pub rsi: Vec<Option<f64>>,
+    pub macd: Vec<Option<f64>>,
+    pub macd_signal: Vec<Option<f64>>,
+    pub macd_histogram: Vec<Option<f64>>,
+    pub bollinger_upper: Vec<Option<f64>>,
+    pub bollinger_middle: Vec<Option<f64>>,
+    pub bollinger_lower: Vec<Option<f64>>,
+    pub atr: Vec<Option<f64>>,
+    pub vwap: Vec<Option<f64>>, // None throughout when the source has no volume
+}
+
+// Candle types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CandleResolution {
+    Minute1,
+    Minute5,
+    Hour1,
+    Day1,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CandleKey {
+    pub symbol: String,
+    pub resolution: CandleResolution,
+    pub bucket_start: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Candle {
+    pub symbol: String,
+    pub resolution: CandleResolution,
+    pub bucket_start: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64, // zero for feeds that carry no volume
+    pub tick_count: u32,
+    pub updated_at: u64, // timestamp of the latest price folded in
+}
+
+impl Storable for CandleKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for Candle {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Market data provider types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum MarketDataSource {
+    Polygon,
+    CoinGecko,
+    Mock, // deterministic prices generated in the canister, no HTTP outcalls
+    Simulator, // the seeded market simulator (stocks only)
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct MarketDataConfig {
+    pub stock_source: MarketDataSource,
+    pub crypto_source: MarketDataSource,
+    pub mock_seed: u64, // the same seed always produces the same mock market
+}
+
+impl Default for MarketDataConfig {
+    fn default() -> Self {
+        Self {
+            stock_source: MarketDataSource::Polygon,
+            crypto_source: MarketDataSource::CoinGecko,
+            mock_seed: 42,
+        }
+    }
+}
+
+impl Storable for MarketDataConfig {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Market simulator types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SimInstrument {
+    pub symbol: String,
+    pub sector: String, // instruments in a sector share a common random factor
+    pub initial_price: f64,
+    pub annual_drift: f64,      // e.g. 0.08 for 8% a year
+    pub annual_volatility: f64, // e.g. 0.25 for 25% a year
+    pub average_daily_volume: u64,
+    pub outstanding_shares: u64,
+    pub exchange: Option<Exchange>, // listing used for sessions and settlement; see instruments::lookup
+    pub currency: Option<String>,   // defaults to the exchange's currency
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SimulatorConfig {
+    pub seed: u64,
+    pub tick_interval_seconds: u64,
+    pub sector_correlation: f64, // share of each shock that comes from the sector factor, 0..=1
+    pub instruments: Vec<SimInstrument>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SimQuote {
+    pub symbol: String,
+    pub price: f64,
+    pub day: u64, // session the day_* fields belong to (timestamp / 1 day)
+    pub day_open: f64,
+    pub day_high: f64,
+    pub day_low: f64,
+    pub day_volume: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SimulatorState {
+    pub config: SimulatorConfig,
+    pub running: bool,
+    pub tick: u64,
+    pub rng_state: u64,
+    pub quotes: Vec<SimQuote>,
+    pub last_tick_at: Option<u64>,
+}
+
+impl Default for SimulatorState {
+    fn default() -> Self {
+        Self {
+            config: SimulatorConfig {
+                seed: 0,
+                tick_interval_seconds: 60,
+                sector_correlation: 0.0,
+                instruments: Vec::new(),
+            },
+            running: false,
+            tick: 0,
+            rng_state: 0,
+            quotes: Vec::new(),
+            last_tick_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct SimTick {
+    pub symbol: String,
+    pub tick: u64,
+    pub timestamp: u64,
+    pub price: f64,
+    pub volume: u64,
+}
+
+impl Storable for SimulatorState {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Corporate action types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum CorporateActionKind {
+    Split { new_shares: u32, old_shares: u32 },   // e.g. 2 for 1
+    Bonus { bonus_shares: u32, held_shares: u32 }, // e.g. 1 bonus for every 2 held
+    CashDividend { per_share: f64 },               // in the stock's quote currency, paid out in rupees
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum CorporateActionStatus {
+    Scheduled,
+    Applied,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum CorporateActionSource {
+    Admin,
+    Simulator,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct CorporateActionRequest {
+    pub stock_id: String,
+    pub kind: CorporateActionKind,
+    pub ex_date: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct CorporateAction {
+    pub id: String,
+    pub stock_id: String,
+    pub kind: CorporateActionKind,
+    pub ex_date: u64,
+    pub source: CorporateActionSource,
+    pub status: CorporateActionStatus,
+    pub created_at: u64,
+    pub applied_at: Option<u64>,
+    pub holders_adjusted: u32,
+    pub cash_paid: f64, // dividends plus cash in lieu of fractional shares
+    pub unpaid_cash: Vec<UnpaidCash>, // dividends and cash in lieu still owed, retried each sweep
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub struct UnpaidCash {
+    pub wallet_address: String,
+    pub amount: f64,
+}
+
+impl Storable for CorporateAction {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// News types
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum NewsSentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct NewsItemRequest {
+    pub symbol: String,
+    pub headline: String,
+    pub body: String,
+    pub source: String,
+    pub published_at: u64,
+    pub sentiment: NewsSentiment,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NewsKey {
+    pub symbol: String,
+    pub published_at: u64,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct NewsItem {
+    pub id: String,
+    pub symbol: String,
+    pub headline: String,
+    pub body: String,
+    pub source: String,
+    pub published_at: u64,
+    pub sentiment: NewsSentiment,
+    pub tags: Vec<String>,
+    pub ingested_at: u64,
+}
+
+impl Storable for NewsKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Bounded {
+        max_size: 256,
+        is_fixed_size: false,
+    };
+}
+
+impl Storable for NewsItem {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Fundamentals types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum MetricSource {
+    Sourced,     // Computed from reported financial statements
+    Estimated,   // Sector table or placeholder
+    Unavailable, // Statements show no meaningful value (a loss or negative equity); reported as 0
+}
+
+// Where each statement-based figure in StockMetrics came from
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct MetricProvenance {
+    pub pe_ratio: MetricSource,
+    pub eps: MetricSource,
+    pub debt_equity_ratio: MetricSource,
+    pub industry_avg_pe: MetricSource,
+    pub outstanding_shares: MetricSource,
+    pub period: Option<String>, // Reporting period of the statements used, e.g. "TTM 2024-06-30"
+}
+
+impl MetricProvenance {
+    pub fn estimated() -> Self {
+        Self {
+            pe_ratio: MetricSource::Estimated,
+            eps: MetricSource::Estimated,
+            debt_equity_ratio: MetricSource::Estimated,
+            industry_avg_pe: MetricSource::Estimated,
+            outstanding_shares: MetricSource::Estimated,
+            period: None,
+        }
+    }
+}
+
+// Figures from a company's latest financial statements. Any field the
+// provider did not report is None.
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Fundamentals {
+    pub symbol: String,
+    pub period: String,
+    pub net_income: Option<f64>,
+    pub eps: Option<f64>,
+    pub total_debt: Option<f64>,
+    pub shareholder_equity: Option<f64>,
+    pub shares_outstanding: Option<u64>,
+    pub source: String,
+    pub fetched_at: u64,
+}
+
+impl Storable for Fundamentals {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Instrument types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq, Eq)]
+pub enum Exchange {
+    NSE,
+    BSE,
+    NASDAQ,
+    NYSE,
+}
+
+// Regular session in exchange-local time
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct TradingHours {
+    pub open_minute: u16,        // minutes after local midnight
+    pub close_minute: u16,
+    pub utc_offset_minutes: i16, // e.g. 330 for IST
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct Instrument {
+    pub symbol: String,          // id used across the game, e.g. "HDFC"
+    pub name: String,
+    pub exchange: Exchange,
+    pub exchange_symbol: String, // ticker on the exchange, e.g. "HDFCBANK"
+    pub currency: String,
+    pub lot_size: u64,
+    pub tick_size: f64,
+    pub trading_hours: TradingHours,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct StockQuote {
+    pub symbol: String,
+    pub exchange: Exchange,
+    pub price: f64,
+    pub currency: String,
+    pub timestamp: u64,
+}
+
+// FX types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum FxRateSource {
+    Oracle,   // pushed by the oracle, either as a rate or as a "USDINR"-style price feed
+    Provider, // fetched from the market data provider
+}
+
+// Units of `quote` per one unit of `base`, e.g. USD/INR = 83.2
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct FxRate {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+    pub source: FxRateSource,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct FxConversion {
+    pub from: String,
+    pub to: String,
+    pub amount: f64,
+    pub converted: f64,
+    pub rate: f64,
+    pub rate_updated_at: u64,
+}
+
+impl Storable for FxRate {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Stock universe types
+// A stock the market summary and periodic refresh cover. Display metadata
+// overrides what the provider reports when set.
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct UniverseStock {
+    pub symbol: String,
+    pub enabled: bool,
+    pub priority: u32, // lower refreshes first; entries past the per-refresh cap wait
+    pub display_name: Option<String>,
+    pub sector: Option<String>, // overrides the listing's sector; see instruments::sector_of
+    pub logo_url: Option<String>,
+    pub exchange: Option<Exchange>, // for symbols outside the instrument list; see instruments::lookup
+    pub currency: Option<String>,   // defaults to the exchange's currency
+    pub added_at: u64,
+    pub updated_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct UniverseStockRequest {
+    pub symbol: String,
+    pub priority: u32,
+    pub display_name: Option<String>,
+    pub sector: Option<String>,
+    pub logo_url: Option<String>,
+    pub exchange: Option<Exchange>,
+    pub currency: Option<String>,
+}
+
+impl Storable for UniverseStock {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Market calendar types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq, Eq)]
+pub enum MarketPhase {
+    PreOpen, // orders are collected but nothing trades yet
+    Open,
+    Closed,
+}
+
+// A day an exchange is shut, or closes early when `early_close_minute` is set
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct MarketHoliday {
+    pub exchange: Exchange,
+    pub date: String,                    // local "YYYY-MM-DD"
+    pub name: String,
+    pub early_close_minute: Option<u16>, // minutes after local midnight
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct MarketStatus {
+    pub exchange: Exchange,
+    pub phase: MarketPhase,
+    pub is_open: bool,
+    pub time_zone: String,   // "IST" or "ET"
+    pub utc_offset_minutes: i16,
+    pub local_date: String,
+    pub holiday: Option<String>,
+    pub next_open: u64,
+    pub next_close: u64,
+}
+
+impl Storable for MarketHoliday {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+// Screener types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum ScreenerField {
+    Price,              // in rupees
+    PriceChangePercent, // over the query's change_days
+    MarketCap,          // in rupees
+    PeRatio,
+    PeToIndustryAvg, // P/E divided by the industry average; below 1 trades at a discount
+    Eps,
+    DebtEquityRatio,
+    BusinessGrowth,
+    Volatility,
+}
+
+// Bounds are inclusive. A stock without a value for the field never matches
+// a comparison, though it can match Not(...).
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum ScreenerCondition {
+    Above(ScreenerField, f64),
+    Below(ScreenerField, f64),
+    Between(ScreenerField, f64, f64),
+    All(Vec<ScreenerCondition>),
+    Any(Vec<ScreenerCondition>),
+    Not(Box<ScreenerCondition>),
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct ScreenerQuery {
+    pub condition: Option<ScreenerCondition>, // None matches every cached stock
+    pub change_days: Option<u32>,             // window for PriceChangePercent
+    pub sort_by: Option<ScreenerField>,       // None sorts by symbol
+    pub descending: bool,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct ScreenerRow {
+    pub symbol: String,
+    pub name: String,
+    pub exchange: Exchange,
+    pub currency: String,
+    pub price: f64, // in `currency`
+    pub price_change_percent: Option<f64>,
+    pub metrics: StockMetrics,
+    pub last_update: u64,
+    // Rupee values that Price and MarketCap conditions compare; None while
+    // the currency's rupee rate is stale
+    pub price_rupees: Option<f64>,
+    pub market_cap_rupees: Option<f64>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct ScreenerPage {
+    pub rows: Vec<ScreenerRow>,
+    pub total_matches: u32,
+    pub next_offset: Option<u32>, // None on the last page
+}
+
+// Index types
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq)]
+pub enum IndexWeighting {
+    MarketCap,
+    Equal,
+    Custom, // weights given with the constituents
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexConstituent {
+    pub symbol: String,
+    pub target_weight: f64, // fraction of the index value at the last rebalance
+    pub units: f64,         // shares held per index point; 0 until the index is established
+}
+
+// Values are in rupees. Constituent units are reset at each rebalance so the
+// index value carries over unchanged.
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexDefinition {
+    pub id: String, // also the stock id a bought basket is held under
+    pub name: String,
+    pub weighting: IndexWeighting,
+    pub constituents: Vec<IndexConstituent>,
+    pub base_value: f64,
+    pub base_date: Option<u64>,       // None until every constituent could first be priced
+    pub rebalance_interval_days: u32, // 0 never rebalances
+    pub last_rebalanced_at: Option<u64>,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexConstituentRequest {
+    pub symbol: String,
+    pub weight: Option<f64>, // required for Custom weighting, ignored otherwise
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexDefinitionRequest {
+    pub id: String,
+    pub name: String,
+    pub weighting: IndexWeighting,
+    pub constituents: Vec<IndexConstituentRequest>,
+    pub base_value: f64,
+    pub rebalance_interval_days: u32,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexConstituentValue {
+    pub symbol: String,
+    pub price: f64, // rupees
+    pub weight: f64, // current share of the index value
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize, Serialize)]
+pub struct IndexValue {
+    pub id: String,
+    pub name: String,
+    pub value: f64,
+    pub change_since_base_percent: f64,
+    pub constituents: Vec<IndexConstituentValue>,
+    pub timestamp: u64,
+}
+
+impl Storable for IndexDefinition {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(candid::encode_one(self).unwrap())
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        candid::decode_one(&bytes).unwrap()
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
@@ -0,0 +1,210 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::auth;
+use crate::trading;
+use crate::fx;
+
+// Each refreshed stock costs several outcalls, so a refresh covers at most
+// this many enabled entries, lowest priority value first
+const MAX_REFRESHED_STOCKS: usize = 25;
+
+const MAX_DISPLAY_NAME_LEN: usize = 100;
+const MAX_SECTOR_LEN: usize = 50;
+const MAX_LOGO_URL_LEN: usize = 300;
+
+// Seeded into an empty universe, in refresh order
+const DEFAULT_UNIVERSE: [&str; 15] = [
+    "AAPL", "GOOGL", "MSFT", "AMZN", "TSLA",
+    "NVDA", "META", "NFLX", "AMD", "INTC",
+    "RELIANCE", "TCS", "INFY", "HDFC", "ICICI",
+];
+
+// Seed the default universe on a fresh canister. An emptied universe is
+// seeded again on upgrade; disable entries to take them out of rotation.
+pub fn ensure_seeded() {
+    if !storage::is_universe_empty() {
+        return;
+    }
+
+    let now = ic_cdk::api::time();
+    for (priority, symbol) in DEFAULT_UNIVERSE.iter().enumerate() {
+        storage::set_universe_stock(&UniverseStock {
+            symbol: symbol.to_string(),
+            enabled: true,
+            priority: priority as u32,
+            display_name: None,
+            sector: None,
+            logo_url: None,
+            exchange: None,
+            currency: None,
+            added_at: now,
+            updated_at: now,
+        });
+    }
+}
+
+// Add a stock or update its priority and display metadata (controllers only).
+// New entries start enabled; existing ones keep their enabled flag.
+pub fn add_universe_stock(request: UniverseStockRequest) -> CanisterResult<UniverseStock> {
+    auth::verify_controller()?;
+    let request = validate_request(request)?;
+    let now = ic_cdk::api::time();
+
+    let existing = storage::get_universe_stock(&request.symbol);
+    let stock = UniverseStock {
+        enabled: existing.as_ref().is_none_or(|stock| stock.enabled),
+        added_at: existing.as_ref().map_or(now, |stock| stock.added_at),
+        symbol: request.symbol,
+        priority: request.priority,
+        display_name: request.display_name,
+        sector: request.sector,
+        logo_url: request.logo_url,
+        exchange: request.exchange,
+        currency: request.currency,
+        updated_at: now,
+    };
+    storage::set_universe_stock(&stock);
+    Ok(stock)
+}
+
+pub fn remove_universe_stock(symbol: String) -> CanisterResult<UniverseStock> {
+    auth::verify_controller()?;
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    storage::remove_universe_stock(&symbol)
+        .ok_or_else(|| CanisterError::NotFound(format!("{} is not in the stock universe", symbol)))
+}
+
+pub fn set_universe_stock_enabled(symbol: String, enabled: bool) -> CanisterResult<UniverseStock> {
+    auth::verify_controller()?;
+    let symbol = trading::normalize_stock_id(&symbol)?;
+    let mut stock = storage::get_universe_stock(&symbol)
+        .ok_or_else(|| CanisterError::NotFound(format!("{} is not in the stock universe", symbol)))?;
+
+    stock.enabled = enabled;
+    stock.updated_at = ic_cdk::api::time();
+    storage::set_universe_stock(&stock);
+    Ok(stock)
+}
+
+// Every entry, enabled or not, in refresh order
+pub fn list_universe_stocks() -> Vec<UniverseStock> {
+    let mut stocks = storage::get_all_universe_stocks();
+    sort_by_priority(&mut stocks);
+    stocks
+}
+
+// Enabled entries in refresh order
+pub fn enabled_stocks() -> Vec<UniverseStock> {
+    list_universe_stocks().into_iter().filter(|stock| stock.enabled).collect()
+}
+
+// Enabled entries the periodic refresh fetches from the provider
+pub fn refreshed_stocks() -> Vec<UniverseStock> {
+    let mut stocks = enabled_stocks();
+    stocks.truncate(MAX_REFRESHED_STOCKS);
+    stocks
+}
+
+// Overlay an entry's display name on provider data
+pub fn apply_display(entry: &UniverseStock, stock: &mut Stock) {
+    if let Some(display_name) = &entry.display_name {
+        stock.name = display_name.clone();
+    }
+}
+
+fn sort_by_priority(stocks: &mut [UniverseStock]) {
+    stocks.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.symbol.cmp(&b.symbol)));
+}
+
+fn validate_request(mut request: UniverseStockRequest) -> CanisterResult<UniverseStockRequest> {
+    request.symbol = trading::normalize_stock_id(&request.symbol)?;
+    request.display_name = normalize_text("Display name", request.display_name, MAX_DISPLAY_NAME_LEN)?;
+    request.sector = normalize_text("Sector", request.sector, MAX_SECTOR_LEN)?;
+    request.logo_url = normalize_text("Logo URL", request.logo_url, MAX_LOGO_URL_LEN)?;
+    request.currency = fx::normalize_optional_currency(request.currency)?;
+
+    if request.logo_url.as_ref().is_some_and(|url| !url.starts_with("https://")) {
+        return Err(CanisterError::InvalidInput("Logo URL must use https".to_string()));
+    }
+    Ok(request)
+}
+
+// Trimmed; blank values are treated as unset
+fn normalize_text(field: &str, value: Option<String>, max_len: usize) -> CanisterResult<Option<String>> {
+    let Some(value) = value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > max_len {
+        return Err(CanisterError::InvalidInput(format!("{} exceeds {} characters", field, max_len)));
+    }
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(symbol: &str, priority: u32) -> UniverseStock {
+        UniverseStock {
+            symbol: symbol.to_string(),
+            enabled: true,
+            priority,
+            display_name: None,
+            sector: None,
+            logo_url: None,
+            exchange: None,
+            currency: None,
+            added_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn request(symbol: &str) -> UniverseStockRequest {
+        UniverseStockRequest {
+            symbol: symbol.to_string(),
+            priority: 0,
+            display_name: None,
+            sector: None,
+            logo_url: None,
+            exchange: None,
+            currency: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_by_priority() {
+        let mut stocks = vec![entry("TCS", 2), entry("MSFT", 1), entry("AAPL", 1)];
+        sort_by_priority(&mut stocks);
+        let symbols: Vec<&str> = stocks.iter().map(|stock| stock.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT", "TCS"]);
+    }
+
+    #[test]
+    fn test_validate_request() {
+        let mut named = request(" reliance ");
+        named.display_name = Some("  Reliance Industries  ".to_string());
+        named.sector = Some("   ".to_string());
+        let validated = validate_request(named).unwrap();
+        assert_eq!(validated.symbol, "RELIANCE");
+        assert_eq!(validated.display_name.as_deref(), Some("Reliance Industries"));
+        assert_eq!(validated.sector, None);
+
+        let mut insecure = request("AAPL");
+        insecure.logo_url = Some("http://example.com/aapl.png".to_string());
+        assert!(validate_request(insecure).is_err());
+
+        let mut listed = request("BAJAJ-AUTO");
+        listed.exchange = Some(Exchange::NSE);
+        listed.currency = Some(" inr ".to_string());
+        assert_eq!(validate_request(listed).unwrap().currency.as_deref(), Some("INR"));
+
+        let mut bad_currency = request("AAPL");
+        bad_currency.currency = Some("DOLLARS".to_string());
+        assert!(validate_request(bad_currency).is_err());
+
+        let mut long_name = request("AAPL");
+        long_name.display_name = Some("x".repeat(MAX_DISPLAY_NAME_LEN + 1));
+        assert!(validate_request(long_name).is_err());
+    }
+}
@@ -81,10 +81,8 @@ pub fn validate_amount(amount: f64) -> CanisterResult<()> {
 
 // Currency validation
 pub fn validate_currency(currency: &str) -> CanisterResult<()> {
-    match currency.to_lowercase().as_str() {
-        "rupees" | "tokens" => Ok(()),
-        _ => Err(CanisterError::InvalidCurrency),
-    }
+    // Rupees, tokens and any enabled currency in the registry
+    crate::currency::validate_currency(currency)
 }
 
 // Generate unique transaction ID
@@ -98,23 +96,104 @@ pub fn generate_transaction_id() -> String {
 // Generate unique staking pool ID
 // Staking ID generation removed
 
+// Generate unique standing instruction ID
+pub fn generate_instruction_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("si_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique savings goal ID
+pub fn generate_goal_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("goal_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique central bank rate decision ID
+pub fn generate_decision_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("rd_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique stock trade ID
+pub fn generate_trade_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("trade_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique stock order ID
+pub fn generate_order_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("ord_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique order book order ID
+pub fn generate_book_order_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("bo_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique watchlist ID
+pub fn generate_watchlist_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("wl_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique price alert ID
+pub fn generate_alert_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("alert_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique corporate action ID
+pub fn generate_corporate_action_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("ca_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
+// Generate unique news item ID
+pub fn generate_news_id() -> String {
+    let timestamp = ic_cdk::api::time();
+    let random_bytes = get_random_bytes(8);
+    format!("news_{}_{}", timestamp, hex::encode(random_bytes))
+}
+
 // Generate transaction hash (simulated)
 pub fn generate_transaction_hash() -> String {
     let random_bytes = get_random_bytes(32);
     format!("0x{}", hex::encode(random_bytes))
 }
 
+thread_local! {
+    // Distinguishes IDs generated within the same message (time() is constant there)
+    static RANDOM_NONCE: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
 // Get random bytes using IC's randomness
 fn get_random_bytes(len: usize) -> Vec<u8> {
     let mut bytes = vec![0u8; len];
     // Use timestamp-based randomness since raw_rand is async
     let timestamp = ic_cdk::api::time();
     let random_seed = timestamp.to_be_bytes().to_vec();
+    let nonce = RANDOM_NONCE.with(|n| {
+        let value = n.get().wrapping_add(1);
+        n.set(value);
+        value
+    });
     
     // Use SHA256 to generate more random bytes if needed
     let mut hasher = Sha256::new();
     hasher.update(&random_seed);
     hasher.update(&ic_cdk::api::time().to_be_bytes());
+    hasher.update(nonce.to_be_bytes());
     let hash = hasher.finalize();
     
     for (i, byte) in bytes.iter_mut().enumerate() {
@@ -234,6 +313,96 @@ pub fn millis_to_nanos(millis: u64) -> u64 {
     millis * 1_000_000
 }
 
+pub const NANOS_PER_SECOND: u64 = 1_000_000_000;
+pub const NANOS_PER_DAY: u64 = 24 * 60 * 60 * NANOS_PER_SECOND;
+
+// Days since 1970-01-01 to a (year, month, day) civil date (UTC)
+pub fn days_to_civil(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// (year, month, day) civil date (UTC) to days since 1970-01-01
+pub fn civil_to_days(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Parse an ISO-8601 UTC timestamp ("2024-06-24T14:05:00Z", fractional seconds
+// ignored) to nanoseconds since the epoch
+pub fn parse_utc_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim().strip_suffix('Z')?;
+    let (date, time) = value.split_once('T')?;
+    let days = u64::try_from(parse_civil_date(date)?).ok()?;
+
+    let time = time.split('.').next()?;
+    let mut time_parts = time.splitn(3, ':');
+    let hour: u64 = time_parts.next()?.parse().ok()?;
+    let minute: u64 = time_parts.next()?.parse().ok()?;
+    let second: u64 = time_parts.next()?.parse().ok()?;
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    Some(days * NANOS_PER_DAY + ((hour * 60 + minute) * 60 + second) * 1_000_000_000)
+}
+
+// Parse a "YYYY-MM-DD" date to days since 1970-01-01
+pub fn parse_civil_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(civil_to_days(year, month, day))
+}
+
+// Days since 1970-01-01 to a "YYYY-MM-DD" date
+pub fn format_civil_date(days: i64) -> String {
+    let (year, month, day) = days_to_civil(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ => {
+            if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 { 29 } else { 28 }
+        }
+    }
+}
+
+// Add calendar months to a nanosecond timestamp, clamping to the end of shorter months
+pub fn add_months(timestamp: u64, months: u32) -> u64 {
+    let days = (timestamp / NANOS_PER_DAY) as i64;
+    let time_of_day = timestamp % NANOS_PER_DAY;
+    let (year, month, day) = days_to_civil(days);
+    
+    let month_index = month as i64 - 1 + months as i64;
+    let new_year = year + month_index.div_euclid(12);
+    let new_month = (month_index.rem_euclid(12) + 1) as u32;
+    let new_day = day.min(days_in_month(new_year, new_month));
+    
+    civil_to_days(new_year, new_month, new_day) as u64 * NANOS_PER_DAY + time_of_day
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,5 +441,42 @@ mod tests {
         assert!(validate_currency("bitcoin").is_err());
     }
 
+    #[test]
+    fn test_civil_date_round_trip() {
+        assert_eq!(days_to_civil(0), (1970, 1, 1));
+        assert_eq!(days_to_civil(19_782), (2024, 2, 29));
+        assert_eq!(civil_to_days(2024, 2, 29), 19_782);
+        assert_eq!(civil_to_days(1970, 1, 1), 0);
+    }
+
+    #[test]
+    fn test_add_months_clamps_day() {
+        let jan_31 = civil_to_days(2024, 1, 31) as u64 * NANOS_PER_DAY + 5;
+        let feb_29 = civil_to_days(2024, 2, 29) as u64 * NANOS_PER_DAY + 5;
+        assert_eq!(add_months(jan_31, 1), feb_29);
+
+        let dec_15 = civil_to_days(2023, 12, 15) as u64 * NANOS_PER_DAY;
+        let jan_15 = civil_to_days(2024, 1, 15) as u64 * NANOS_PER_DAY;
+        assert_eq!(add_months(dec_15, 1), jan_15);
+    }
+
+    #[test]
+    fn test_parse_utc_timestamp() {
+        let feb_29 = civil_to_days(2024, 2, 29) as u64 * NANOS_PER_DAY;
+        assert_eq!(parse_utc_timestamp("2024-02-29T00:00:00Z"), Some(feb_29));
+        assert_eq!(parse_utc_timestamp("2024-02-29T01:02:03.456Z"), Some(feb_29 + 3_723_000_000_000));
+        assert_eq!(parse_utc_timestamp("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_utc_timestamp("2024-02-29 00:00:00"), None);
+    }
+
+    #[test]
+    fn test_parse_and_format_civil_date() {
+        let days = parse_civil_date("2026-11-26").unwrap();
+        assert_eq!(days, civil_to_days(2026, 11, 26));
+        assert_eq!(format_civil_date(days), "2026-11-26");
+        assert_eq!(parse_civil_date("2026-13-01"), None);
+        assert_eq!(parse_civil_date("26-11"), None);
+    }
+
     // Staking-related tests removed
 }
\ No newline at end of file
@@ -0,0 +1,363 @@
+use crate::types::*;
+use crate::error::*;
+use crate::storage;
+use crate::utils;
+use crate::auth;
+use crate::trading;
+use crate::sse;
+use ic_cdk_timers::{TimerId, set_timer_interval};
+use std::cell::RefCell;
+
+// Watchlist and alert limits
+const ALERT_INTERVAL_SECONDS: u64 = 60;
+const MAX_WATCHLISTS_PER_WALLET: usize = 10;
+const MAX_ITEMS_PER_WATCHLIST: usize = 50;
+const MAX_ACTIVE_ALERTS_PER_WALLET: usize = 50;
+const MAX_WATCHLIST_NAME_LENGTH: usize = 50;
+
+thread_local! {
+    static ALERT_TIMER: RefCell<Option<TimerId>> = const { RefCell::new(None) };
+}
+
+pub fn start_alert_monitor() {
+    ALERT_TIMER.with(|timer_cell| {
+        if timer_cell.borrow().is_some() {
+            return;
+        }
+
+        let timer_id = set_timer_interval(std::time::Duration::from_secs(ALERT_INTERVAL_SECONDS), || {
+            let fired = evaluate_alerts(ic_cdk::api::time());
+            if fired > 0 {
+                ic_cdk::println!("🔔 Fired {} price alerts", fired);
+            }
+        });
+
+        *timer_cell.borrow_mut() = Some(timer_id);
+    });
+}
+
+pub fn create_watchlist(wallet_address: String, name: String, items: Vec<WatchlistItem>) -> CanisterResult<Watchlist> {
+    auth::verify_session(&wallet_address)?;
+
+    if storage::get_watchlists_for_wallet(&wallet_address).len() >= MAX_WATCHLISTS_PER_WALLET {
+        return Err(CanisterError::RateLimited("Too many watchlists".to_string()));
+    }
+
+    let mut normalized = Vec::new();
+    for item in items {
+        add_item(&mut normalized, item)?;
+    }
+
+    let now = ic_cdk::api::time();
+    let watchlist = Watchlist {
+        id: utils::generate_watchlist_id(),
+        wallet_address,
+        name: validate_name(&name)?,
+        items: normalized,
+        created_at: now,
+        updated_at: now,
+    };
+
+    storage::set_watchlist(&watchlist);
+
+    Ok(watchlist)
+}
+
+pub fn get_watchlists(wallet_address: String) -> Vec<Watchlist> {
+    let mut watchlists = storage::get_watchlists_for_wallet(&wallet_address);
+    watchlists.sort_by_key(|watchlist| watchlist.created_at);
+    watchlists
+}
+
+pub fn add_to_watchlist(wallet_address: String, watchlist_id: String, item: WatchlistItem) -> CanisterResult<Watchlist> {
+    auth::verify_session(&wallet_address)?;
+
+    let mut watchlist = get_owned_watchlist(&wallet_address, &watchlist_id)?;
+    add_item(&mut watchlist.items, item)?;
+    watchlist.updated_at = ic_cdk::api::time();
+    storage::set_watchlist(&watchlist);
+
+    Ok(watchlist)
+}
+
+pub fn remove_from_watchlist(wallet_address: String, watchlist_id: String, symbol: String) -> CanisterResult<Watchlist> {
+    auth::verify_session(&wallet_address)?;
+
+    let mut watchlist = get_owned_watchlist(&wallet_address, &watchlist_id)?;
+    let symbol = symbol.trim().to_uppercase();
+    let before = watchlist.items.len();
+    watchlist.items.retain(|item| item.symbol != symbol);
+    if watchlist.items.len() == before {
+        return Err(CanisterError::NotFound(format!("{} is not on the watchlist", symbol)));
+    }
+    watchlist.updated_at = ic_cdk::api::time();
+    storage::set_watchlist(&watchlist);
+
+    Ok(watchlist)
+}
+
+pub fn delete_watchlist(wallet_address: String, watchlist_id: String) -> CanisterResult<()> {
+    auth::verify_session(&wallet_address)?;
+
+    get_owned_watchlist(&wallet_address, &watchlist_id)?;
+    storage::remove_watchlist(&watchlist_id);
+
+    Ok(())
+}
+
+// Set a price alert. Percent-change alerts are measured from the price now.
+pub fn create_price_alert(wallet_address: String, request: PriceAlertRequest) -> CanisterResult<PriceAlert> {
+    auth::verify_session(&wallet_address)?;
+
+    let symbol = trading::normalize_stock_id(&request.symbol)?;
+    validate_condition(&request.condition)?;
+
+    let active = storage::get_price_alerts_for_wallet(&wallet_address)
+        .iter()
+        .filter(|alert| alert.status == AlertStatus::Active)
+        .count();
+    if active >= MAX_ACTIVE_ALERTS_PER_WALLET {
+        return Err(CanisterError::RateLimited("Too many active alerts".to_string()));
+    }
+
+    let price = current_price(&symbol, &request.kind)
+        .ok_or(CanisterError::NotFound(format!("No price available for {}", symbol)))?;
+
+    let alert = PriceAlert {
+        id: utils::generate_alert_id(),
+        wallet_address,
+        symbol,
+        kind: request.kind,
+        // A condition that already holds waits until it clears before firing
+        armed: !condition_met(&request.condition, price, price),
+        condition: request.condition,
+        mode: request.mode,
+        status: AlertStatus::Active,
+        reference_price: price,
+        last_price: Some(price),
+        trigger_count: 0,
+        last_triggered_at: None,
+        created_at: ic_cdk::api::time(),
+    };
+
+    storage::set_price_alert(&alert);
+
+    Ok(alert)
+}
+
+pub fn cancel_price_alert(wallet_address: String, alert_id: String) -> CanisterResult<PriceAlert> {
+    auth::verify_session(&wallet_address)?;
+
+    let mut alert = storage::get_price_alert(&alert_id)
+        .ok_or(CanisterError::NotFound(format!("Alert {}", alert_id)))?;
+
+    if alert.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+    if alert.status != AlertStatus::Active {
+        return Err(CanisterError::InvalidInput("Only active alerts can be cancelled".to_string()));
+    }
+
+    alert.status = AlertStatus::Cancelled;
+    storage::set_price_alert(&alert);
+
+    Ok(alert)
+}
+
+// A wallet's alerts, most recently created first
+pub fn get_price_alerts(wallet_address: String) -> Vec<PriceAlert> {
+    let mut alerts = storage::get_price_alerts_for_wallet(&wallet_address);
+    alerts.sort_by_key(|alert| std::cmp::Reverse(alert.created_at));
+    alerts
+}
+
+// Check every active alert against the cached prices. Returns the number fired.
+pub fn evaluate_alerts(now: u64) -> usize {
+    let mut fired = 0;
+
+    for mut alert in storage::get_active_price_alerts() {
+        let Some(price) = current_price(&alert.symbol, &alert.kind) else {
+            continue;
+        };
+
+        if advance(&mut alert, price, now) {
+            notify(&alert, price);
+            fired += 1;
+        }
+        storage::set_price_alert(&alert);
+    }
+
+    fired
+}
+
+// Move an alert on to a new price. Returns true if it fired. One-shot alerts
+// finish when they fire; re-arming alerts wait for the condition to clear,
+// and percent-change alerts then measure from the price they fired at.
+fn advance(alert: &mut PriceAlert, price: f64, now: u64) -> bool {
+    alert.last_price = Some(price);
+    let met = condition_met(&alert.condition, alert.reference_price, price);
+
+    if !alert.armed {
+        alert.armed = !met;
+        return false;
+    }
+    if !met {
+        return false;
+    }
+
+    alert.trigger_count += 1;
+    alert.last_triggered_at = Some(now);
+    match alert.mode {
+        AlertMode::OneShot => alert.status = AlertStatus::Triggered,
+        AlertMode::Rearm => {
+            if matches!(alert.condition, AlertCondition::PercentChangeUp(_) | AlertCondition::PercentChangeDown(_)) {
+                alert.reference_price = price;
+            }
+            alert.armed = false;
+        }
+    }
+
+    true
+}
+
+fn condition_met(condition: &AlertCondition, reference_price: f64, price: f64) -> bool {
+    match condition {
+        AlertCondition::PriceAbove(target) => price >= *target,
+        AlertCondition::PriceBelow(target) => price <= *target,
+        AlertCondition::PercentChangeUp(percent) => price >= reference_price * (1.0 + percent / 100.0),
+        AlertCondition::PercentChangeDown(percent) => price <= reference_price * (1.0 - percent / 100.0),
+    }
+}
+
+fn current_price(symbol: &str, kind: &WatchAssetKind) -> Option<f64> {
+    let price = match kind {
+        WatchAssetKind::Stock => storage::get_stock_cache(symbol).map(|cache| cache.stock_data.current_price),
+        WatchAssetKind::Crypto => storage::get_price_feed(symbol),
+    }?;
+    (price > 0.0 && price.is_finite()).then_some(price)
+}
+
+// Alerts go to the wallet's own room, `alerts_<wallet>`
+fn notify(alert: &PriceAlert, price: f64) {
+    let room_id = format!("alerts_{}", alert.wallet_address);
+    let event_data = serde_json::json!({
+        "type": "price_alert",
+        "alert_id": alert.id,
+        "symbol": alert.symbol,
+        "condition": format!("{:?}", alert.condition),
+        "price": price,
+        "trigger_count": alert.trigger_count,
+        "timestamp": alert.last_triggered_at
+    });
+
+    let result = sse::create_room(&room_id)
+        .and_then(|_| sse::broadcast_event(&room_id, SseEventType::RoomState, event_data));
+    if let Err(e) = result {
+        ic_cdk::println!("⚠️ Failed to push alert {}: {:?}", alert.id, e);
+    }
+}
+
+fn add_item(items: &mut Vec<WatchlistItem>, item: WatchlistItem) -> CanisterResult<()> {
+    let symbol = trading::normalize_stock_id(&item.symbol)?;
+    if items.iter().any(|existing| existing.symbol == symbol) {
+        return Err(CanisterError::InvalidInput(format!("{} is already on the watchlist", symbol)));
+    }
+    if items.len() >= MAX_ITEMS_PER_WATCHLIST {
+        return Err(CanisterError::RateLimited("Watchlist is full".to_string()));
+    }
+    items.push(WatchlistItem { symbol, kind: item.kind });
+    Ok(())
+}
+
+fn get_owned_watchlist(wallet_address: &str, watchlist_id: &str) -> CanisterResult<Watchlist> {
+    let watchlist = storage::get_watchlist(watchlist_id)
+        .ok_or(CanisterError::NotFound(format!("Watchlist {}", watchlist_id)))?;
+
+    if watchlist.wallet_address != wallet_address {
+        return Err(CanisterError::UnauthorizedAccess);
+    }
+
+    Ok(watchlist)
+}
+
+fn validate_name(name: &str) -> CanisterResult<String> {
+    let name = utils::sanitize_string(name.trim());
+    if name.is_empty() {
+        return Err(CanisterError::InvalidInput("Watchlist name cannot be empty".to_string()));
+    }
+    Ok(name.chars().take(MAX_WATCHLIST_NAME_LENGTH).collect())
+}
+
+fn validate_condition(condition: &AlertCondition) -> CanisterResult<()> {
+    let valid = match condition {
+        AlertCondition::PriceAbove(target) | AlertCondition::PriceBelow(target) => *target > 0.0 && target.is_finite(),
+        AlertCondition::PercentChangeUp(percent) => *percent > 0.0 && percent.is_finite(),
+        AlertCondition::PercentChangeDown(percent) => *percent > 0.0 && *percent < 100.0,
+    };
+    if !valid {
+        return Err(CanisterError::InvalidInput("Invalid alert threshold".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(condition: AlertCondition, mode: AlertMode) -> PriceAlert {
+        PriceAlert {
+            id: "alert_test".to_string(),
+            wallet_address: "0xabc".to_string(),
+            symbol: "BTC".to_string(),
+            kind: WatchAssetKind::Crypto,
+            condition,
+            mode,
+            status: AlertStatus::Active,
+            armed: true,
+            reference_price: 100.0,
+            last_price: None,
+            trigger_count: 0,
+            last_triggered_at: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_one_shot_fires_once() {
+        let mut alert = alert(AlertCondition::PriceAbove(110.0), AlertMode::OneShot);
+        assert!(!advance(&mut alert, 105.0, 1));
+        assert!(advance(&mut alert, 111.0, 2));
+        assert_eq!(alert.status, AlertStatus::Triggered);
+        assert_eq!(alert.last_triggered_at, Some(2));
+    }
+
+    #[test]
+    fn test_rearm_waits_for_condition_to_clear() {
+        let mut alert = alert(AlertCondition::PriceBelow(90.0), AlertMode::Rearm);
+        assert!(advance(&mut alert, 89.0, 1));
+        assert!(!advance(&mut alert, 85.0, 2)); // still below, not re-armed
+        assert!(!advance(&mut alert, 95.0, 3)); // cleared, re-arms
+        assert!(advance(&mut alert, 88.0, 4));
+        assert_eq!(alert.trigger_count, 2);
+        assert_eq!(alert.status, AlertStatus::Active);
+    }
+
+    #[test]
+    fn test_percent_change_rearm_moves_reference() {
+        let mut alert = alert(AlertCondition::PercentChangeUp(10.0), AlertMode::Rearm);
+        assert!(!advance(&mut alert, 109.0, 1));
+        assert!(advance(&mut alert, 111.0, 2));
+        assert_eq!(alert.reference_price, 111.0);
+
+        assert!(!advance(&mut alert, 112.0, 3)); // re-arms from the new reference
+        assert!(!advance(&mut alert, 121.0, 4));
+        assert!(advance(&mut alert, 123.0, 5));
+    }
+
+    #[test]
+    fn test_validate_condition() {
+        assert!(validate_condition(&AlertCondition::PriceAbove(50_000.0)).is_ok());
+        assert!(validate_condition(&AlertCondition::PriceBelow(-1.0)).is_err());
+        assert!(validate_condition(&AlertCondition::PercentChangeDown(100.0)).is_err());
+    }
+}